@@ -0,0 +1,203 @@
+//! Benchmarks for `Map`, run with `cargo bench`.
+//! Every workload is run a few times and the fastest run is reported as time per operation, next to `std::collections::HashMap` doing the same work.
+
+use azeezd_hash::hash_map::{Hashable, Map};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Amount of times every workload is run. The fastest run is reported
+const RUNS: usize = 5;
+
+/// # `bench`
+/// Runs the given workload `RUNS` times and prints the fastest time per operation. The workload returns the amount of operations it did
+fn bench<F: FnMut() -> usize>(name: &str, mut workload: F) {
+    let mut best = Duration::MAX;
+    let mut operations = 1;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        operations = black_box(workload()).max(1);
+        best = best.min(start.elapsed());
+    }
+
+    println!(
+        "{:<48} {:>10.1} ns/op",
+        name,
+        best.as_nanos() as f64 / operations as f64
+    );
+}
+
+/// # `keys`
+/// Returns `amount` distinct row-like keys
+fn keys(amount: usize) -> Vec<String> {
+    (0..amount).map(|idx| format!("Student{}", idx)).collect()
+}
+
+/// # `colliding_keys`
+/// Returns `2^bits` distinct keys that all have the same hash code.
+/// "AB" and "B!" hash the same with the `hash * 33 + byte` of `Hashable for String`, and so does every string made of the same amount of those two blocks.
+fn colliding_keys(bits: u32) -> Vec<String> {
+    (0..1usize << bits)
+        .map(|idx| {
+            (0..bits)
+                .map(|bit| if idx >> bit & 1 == 0 { "AB" } else { "B!" })
+                .collect()
+        })
+        .collect()
+}
+
+/// # `Xorshift`
+/// Small deterministic random number generator so that every run does the same work
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn insert(amount: usize) {
+    let keys = keys(amount);
+
+    bench(&format!("Map::insert {}", amount), || {
+        let mut map: Map<String, usize> = Map::new();
+        for (idx, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), idx).unwrap();
+        }
+        amount
+    });
+
+    bench(&format!("HashMap::insert {}", amount), || {
+        let mut map: HashMap<String, usize> = HashMap::new();
+        for (idx, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), idx);
+        }
+        amount
+    });
+
+    bench(&format!("Map::insert_many {}", amount), || {
+        let mut map: Map<String, usize> = Map::new();
+        map.insert_many(keys.iter().cloned().zip(0..)).unwrap();
+        amount
+    });
+}
+
+fn get(amount: usize) {
+    let keys = keys(amount);
+    let missing: Vec<String> = (0..amount).map(|idx| format!("Teacher{}", idx)).collect();
+    let map: Map<String, usize> = keys.iter().cloned().zip(0..).collect();
+    let std_map: HashMap<String, usize> = keys.iter().cloned().zip(0..).collect();
+
+    bench(&format!("Map::get_ref hit {}", amount), || {
+        keys.iter().filter(|key| map.get_ref(key).is_some()).count()
+    });
+    bench(&format!("Map::get_ref miss {}", amount), || {
+        missing.iter().filter(|key| map.get_ref(key).is_none()).count()
+    });
+    bench(&format!("HashMap::get hit {}", amount), || {
+        keys.iter().filter(|key| std_map.contains_key(*key)).count()
+    });
+}
+
+fn remove(amount: usize) {
+    let keys = keys(amount);
+    let map: Map<String, usize> = keys.iter().cloned().zip(0..).collect();
+
+    bench(&format!("Map::remove {}", amount), || {
+        let mut map = map.clone();
+        for key in keys.iter() {
+            map.remove(key.clone()).unwrap();
+        }
+        amount
+    });
+
+    bench(&format!("Map::remove_many {}", amount), || {
+        let mut map = map.clone();
+        map.remove_many(keys.iter().cloned()).len()
+    });
+
+    bench(&format!("Map::retain half {}", amount), || {
+        let mut map = map.clone();
+        map.retain(|_, value| *value % 2 == 0);
+        amount
+    });
+}
+
+/// Random inserts, gets and removes over a fixed set of keys, the map stays around half full of those keys
+fn mixed(amount: usize) {
+    let keys = keys(amount);
+
+    bench(&format!("Map mixed {}", amount * 4), || {
+        let mut random = Xorshift(0x2545_f491_4f6c_dd1d);
+        let mut map: Map<String, usize> = Map::new();
+        for _ in 0..amount * 4 {
+            let key = &keys[random.next() as usize % amount];
+            match random.next() % 3 {
+                0 => {
+                    let _ = map.insert(key.clone(), 0);
+                }
+                1 => {
+                    black_box(map.get_ref(key));
+                }
+                _ => {
+                    let _ = map.remove(key.clone());
+                }
+            }
+        }
+        amount * 4
+    });
+}
+
+/// Lookups in maps resized so that they are filled to the given percentages. Higher load factors give longer probe sequences
+fn load_factors(amount: usize) {
+    let keys = keys(amount);
+
+    for percent in [10, 25, 50, 75] {
+        let mut map: Map<String, usize> = Map::new();
+        map.resize(amount * 100 / percent).unwrap();
+        for (idx, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), idx).unwrap();
+        }
+
+        bench(&format!("Map::get_ref {}% full {}", percent, amount), || {
+            keys.iter().filter(|key| map.get_ref(key).is_some()).count()
+        });
+    }
+}
+
+/// Keys that all share one hash code turn every operation into a scan of one long probe sequence
+fn adversarial(bits: u32) {
+    let keys = colliding_keys(bits);
+    let amount = keys.len();
+    assert!(keys.iter().all(|key| key.hash_code() == keys[0].hash_code()));
+
+    bench(&format!("Map::insert colliding {}", amount), || {
+        let mut map: Map<String, usize> = Map::new();
+        for (idx, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), idx).unwrap();
+        }
+        amount
+    });
+
+    let map: Map<String, usize> = keys.iter().cloned().zip(0..).collect();
+    bench(&format!("Map::get_ref colliding {}", amount), || {
+        keys.iter().filter(|key| map.get_ref(key).is_some()).count()
+    });
+}
+
+fn main() {
+    for amount in [1_000, 100_000] {
+        insert(amount);
+        get(amount);
+        remove(amount);
+        mixed(amount);
+        println!();
+    }
+    load_factors(10_000);
+    println!();
+    adversarial(10);
+}
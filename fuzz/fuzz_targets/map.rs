@@ -0,0 +1,65 @@
+//! Interprets the fuzzer input as a sequence of operations on `Map` and checks every result against `std::collections::HashMap`.
+//! Run with `cargo fuzz run map` from the repository root.
+#![no_main]
+
+use azeezd_hash::hash_map::Map;
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+
+fuzz_target!(|data: &[u8]| {
+    let mut map: Map<String, u8> = Map::new();
+    let mut model: HashMap<String, u8> = HashMap::new();
+
+    // Every operation takes 3 bytes: which operation, the key and the value.
+    // Keys are short strings over a small alphabet so that collisions and reuse of removed slots happen often
+    for operation in data.chunks_exact(3) {
+        let key: String = format!("{:x}", operation[1] & 0x3f);
+        let value = operation[2];
+
+        match operation[0] % 8 {
+            0 | 1 => assert_eq!(
+                map.insert(key.clone(), value).is_ok(),
+                !model.contains_key(&key) && model.insert(key, value).is_none()
+            ),
+            2 => assert_eq!(map.get_ref(&key), model.get(&key)),
+            3 => assert_eq!(map.remove(key.clone()).ok(), model.remove(&key)),
+            4 => assert_eq!(
+                map.set(key.clone(), value).ok(),
+                model.get_mut(&key).map(|old| std::mem::replace(old, value))
+            ),
+            5 => {
+                map.retain(|_, current| *current < value);
+                model.retain(|_, current| *current < value);
+            }
+            6 => {
+                let mut extracted = map.extract_if(|_, current| *current == value);
+                let mut expected: Vec<(String, u8)> = model
+                    .iter()
+                    .filter(|(_, current)| **current == value)
+                    .map(|(key, current)| (key.clone(), *current))
+                    .collect();
+                model.retain(|_, current| *current != value);
+                extracted.sort();
+                expected.sort();
+                assert_eq!(extracted, expected);
+            }
+            _ => {
+                // Resizing never loses pairs, but may fail when asked to shrink below the amount of pairs
+                let size = value as usize + 1;
+                assert_eq!(map.resize(size).is_ok(), size >= model.len());
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+    }
+
+    assert_eq!(map.iter().count(), model.len());
+    for (key, value) in model.iter() {
+        assert_eq!(map[key], *value);
+    }
+    let mut keys = map.keys().clone();
+    keys.sort();
+    let mut model_keys: Vec<String> = model.keys().cloned().collect();
+    model_keys.sort();
+    assert_eq!(keys, model_keys);
+});
@@ -0,0 +1,72 @@
+//! Feeds the fuzzer input as queries to a table and checks that the query parser never panics
+//! and that the table keeps the same rows as a simple model of the row names, which follows transactions and savepoints.
+//! Run with `cargo fuzz run query` from the repository root.
+#![no_main]
+
+use azeezd_hash::tools::{query, Statement, Table};
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashSet;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let path = std::env::temp_dir().join(format!("azeezd-hash-fuzz-{}.csv", std::process::id()));
+    std::fs::write(&path, "u:Age,w:Program\nBob,10,CDATE\nBobby,20,CDATE").unwrap();
+    let mut table = Table::new(path.to_string_lossy().to_string()).unwrap();
+    let mut rows: HashSet<String> = ["Bob", "Bobby"].iter().map(|row| row.to_string()).collect();
+    // The rows when the open transaction started and at each of its savepoints, which a ROLLBACK brings back
+    let mut begun: Option<HashSet<String>> = None;
+    let mut savepoints: Vec<(String, HashSet<String>)> = Vec::new();
+
+    for line in text.lines() {
+        // These touch the file system or end the process. Keywords may be in any case and follow whitespace or comments,
+        // so the parsed statement is checked rather than the text
+        let statement = Statement::parse(line);
+        if matches!(statement, Ok(Statement::Save { .. } | Statement::Abort)) {
+            continue;
+        }
+
+        let before = table.get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap().len();
+        let result = query(&mut table, line);
+        let after: HashSet<String> = table
+            .get(&["*".to_string()], &["*".to_string()], None, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(row, _)| row)
+            .collect();
+
+        // Failed queries never change which rows exist
+        if result.is_err() {
+            assert_eq!(after, rows);
+            continue;
+        }
+        let position = |savepoints: &[(String, HashSet<String>)], name: &str| {
+            savepoints.iter().position(|(other, _)| other == name).unwrap()
+        };
+        match statement {
+            Ok(Statement::Begin) => begun = Some(after.clone()),
+            Ok(Statement::Commit) => {
+                begun = None;
+                savepoints.clear();
+            }
+            Ok(Statement::Savepoint { name }) => {
+                savepoints.retain(|(other, _)| *other != name);
+                savepoints.push((name, after.clone()));
+            }
+            Ok(Statement::Release { savepoint }) => savepoints.truncate(position(&savepoints, &savepoint)),
+            Ok(Statement::Rollback { savepoint: None }) => {
+                assert_eq!(begun.take().as_ref(), Some(&after));
+                savepoints.clear();
+            }
+            Ok(Statement::Rollback { savepoint: Some(name) }) => {
+                let idx = position(&savepoints, &name);
+                assert_eq!(savepoints[idx].1, after);
+                savepoints.truncate(idx + 1);
+            }
+            _ => assert!(after.len() <= before + 1),
+        }
+        rows = after;
+    }
+});
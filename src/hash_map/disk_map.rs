@@ -0,0 +1,611 @@
+use super::{checksum, mix, random_seed, ByteReader, Deserialize, HashKeys, Hashable, Serialize};
+use std::fs::{rename, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+/// # `PAGE_SIZE`
+/// Size in bytes of every page in the file. The header, every bucket and every overflow page take exactly one page.
+pub const PAGE_SIZE: usize = 4096;
+
+/// # `MAGIC`
+/// The first bytes of every disk map file. Used to recognize the format.
+pub const MAGIC: &[u8; 4] = b"AZDM";
+
+/// # `VERSION`
+/// The version of the file layout. Bumped whenever the layout changes.
+const VERSION: u16 = 1;
+
+/// Bytes taken by the fixed fields at the start of the header page, the metadata follows them
+const HEADER_FIELDS: usize = 62;
+
+/// Bytes at the start of a bucket or overflow page: the next page in the chain (`u64`) and the amount of used bytes (`u32`)
+const PAGE_HEADER: usize = 12;
+
+/// # `PAGE_CAPACITY`
+/// The amount of bytes in a page that can hold records
+pub const PAGE_CAPACITY: usize = PAGE_SIZE - PAGE_HEADER;
+
+/// # `MAX_METADATA`
+/// The amount of bytes of metadata the header page can hold next to the header fields and its checksum
+pub const MAX_METADATA: usize = PAGE_SIZE - HEADER_FIELDS - 4;
+
+/// # `DiskMap`
+/// A hash map living in a file rather than in memory. Only the header is read when opened, buckets are read and written one page at a time when they are used.
+/// Pages are read and written at their offset in the file rather than memory mapped, which keeps the crate free of dependencies and `unsafe`
+/// while only the pages in use are ever in memory.
+///
+/// The file is a sequence of `PAGE_SIZE` pages:
+/// - Page 0 is the header holding the format version, bucket count, hash seed, amount of pairs, amount of pages, the first free page,
+///   the amount of free pages and some metadata for the owner of the map
+/// - Pages 1 to bucket count are the buckets. A key is always stored in the bucket its `Hashable::keyed_hash` points to, keyed by the
+///   seed, so keys picked to share a `hash_code` do not pile up in one chain
+/// - Every page after those is an overflow page, chained after a bucket when it runs out of room. An overflow page left empty is taken out
+///   of its chain and put on the free page list, chained through the same `next` field, to be used again before the file grows
+///
+/// Every page holds records of a `u32` length followed by the serialized key and value.
+pub struct DiskMap<Key, Value> {
+    file: File,
+    path: String,
+    bucket_count: u64,
+    seed: u64,
+    size: u64,
+    page_count: u64,
+    free_page: u64,
+    free_count: u64,
+    metadata: Vec<u8>,
+    types: PhantomData<(Key, Value)>,
+}
+
+impl<Key, Value> std::fmt::Debug for DiskMap<Key, Value> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskMap")
+            .field("path", &self.path)
+            .field("bucket_count", &self.bucket_count)
+            .field("len", &self.size)
+            .finish()
+    }
+}
+
+/// # `Page`
+/// A bucket or overflow page read from the file
+struct Page {
+    next: u64,
+    records: Vec<u8>,
+}
+
+impl Page {
+    /// # `record_offsets`
+    /// Returns the start and end of every record in this page as `(usize, usize)`, not counting the length prefix
+    fn record_offsets(&self) -> Result<Vec<(usize, usize)>, &'static str> {
+        let mut offsets = Vec::new();
+        let mut position = 0;
+
+        while position < self.records.len() {
+            if position + 4 > self.records.len() {
+                return Err("Corrupted page in disk map");
+            }
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&self.records[position..position + 4]);
+            let start = position + 4;
+            let end = start + u32::from_le_bytes(len) as usize;
+            if end > self.records.len() {
+                return Err("Corrupted page in disk map");
+            }
+            offsets.push((start, end));
+            position = end;
+        }
+
+        Ok(offsets)
+    }
+}
+
+/// # `io`
+/// Turns any IO error into the error message used by the map
+fn io<T>(result: std::io::Result<T>) -> Result<T, &'static str> {
+    result.map_err(|_| "Error while accessing the disk map file")
+}
+
+impl<Key, Value> DiskMap<Key, Value>
+where
+    Key: PartialEq + Hashable + Serialize + Deserialize,
+    Value: Serialize + Deserialize,
+{
+    /// # `create`
+    /// Creates a new empty `DiskMap` at the given path with the given amount of buckets and metadata. An existing file at the path is overwritten.
+    pub fn create(
+        path: &str,
+        bucket_count: u64,
+        metadata: Vec<u8>,
+    ) -> Result<DiskMap<Key, Value>, &'static str> {
+        if metadata.len() > MAX_METADATA {
+            return Err("Disk map metadata does not fit in the header");
+        }
+        let bucket_count = bucket_count.max(1);
+
+        let file = io(OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path))?;
+
+        let mut map = DiskMap {
+            file,
+            path: path.to_string(),
+            bucket_count,
+            seed: random_seed(),
+            size: 0,
+            page_count: bucket_count + 1,
+            free_page: 0,
+            free_count: 0,
+            metadata,
+            types: PhantomData,
+        };
+
+        // Every bucket starts as an empty page, written in one go
+        io(map.file.set_len((bucket_count + 1) * PAGE_SIZE as u64))?;
+        map.write_header()?;
+        Ok(map)
+    }
+
+    /// # `open`
+    /// Opens an existing `DiskMap` file. Only the header page is read.
+    pub fn open(path: &str) -> Result<DiskMap<Key, Value>, &'static str> {
+        let mut file = io(OpenOptions::new().read(true).write(true).open(path))?;
+
+        let mut header = vec![0u8; PAGE_SIZE];
+        if file.read_exact(&mut header).is_err() || &header[..4] != MAGIC {
+            return Err("File is not a disk map");
+        }
+
+        let crc_at = PAGE_SIZE - 4;
+        let mut crc = [0u8; 4];
+        crc.copy_from_slice(&header[crc_at..]);
+        if checksum(&header[..crc_at]) != u32::from_le_bytes(crc) {
+            return Err("Disk map header is corrupted");
+        }
+
+        let mut reader = ByteReader::new(&header[4..HEADER_FIELDS]);
+        let version = u16::from_le_bytes(reader.take_array()?);
+        if version != VERSION {
+            return Err("Unsupported disk map version");
+        }
+        if u32::from_le_bytes(reader.take_array()?) as usize != PAGE_SIZE {
+            return Err("Unsupported disk map page size");
+        }
+        let bucket_count = u64::from_le_bytes(reader.take_array()?);
+        let seed = u64::from_le_bytes(reader.take_array()?);
+        let size = u64::from_le_bytes(reader.take_array()?);
+        let page_count = u64::from_le_bytes(reader.take_array()?);
+        let free_page = u64::from_le_bytes(reader.take_array()?);
+        let free_count = u64::from_le_bytes(reader.take_array()?);
+        let metadata_len = u32::from_le_bytes(reader.take_array()?) as usize;
+
+        if bucket_count == 0
+            || page_count <= bucket_count
+            || metadata_len > MAX_METADATA
+            || free_page >= page_count
+            || free_count >= page_count - bucket_count
+        {
+            return Err("Disk map header is corrupted");
+        }
+
+        Ok(DiskMap {
+            file,
+            path: path.to_string(),
+            bucket_count,
+            seed,
+            size,
+            page_count,
+            free_page,
+            free_count,
+            metadata: header[HEADER_FIELDS..HEADER_FIELDS + metadata_len].to_vec(),
+            types: PhantomData,
+        })
+    }
+
+    /// # `len`
+    /// Returns the amount of key-value pairs stored in the map
+    pub fn len(&self) -> usize {
+        self.size as usize
+    }
+
+    /// # `is_empty`
+    /// Returns `true` if the map holds no key-value pairs
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// # `path`
+    /// Returns the path of the file holding the map
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// # `metadata`
+    /// Returns the metadata stored in the header
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// # `set_metadata`
+    /// Replaces the metadata stored in the header
+    pub fn set_metadata(&mut self, metadata: Vec<u8>) -> Result<(), &'static str> {
+        if metadata.len() > MAX_METADATA {
+            return Err("Disk map metadata does not fit in the header");
+        }
+        self.metadata = metadata;
+        self.write_header()
+    }
+
+    /// # `get`
+    /// Returns the value stored at the given key as `Ok(Option<Value>)`. `None` is returned if the key is not available
+    pub fn get(&self, key: &Key) -> Result<Option<Value>, &'static str> {
+        let mut page_idx = self.bucket_of(key);
+
+        while page_idx != 0 {
+            let page = self.read_page(page_idx)?;
+            for (start, end) in page.record_offsets()? {
+                let mut reader = ByteReader::new(&page.records[start..end]);
+                if Key::deserialize(&mut reader)? == *key {
+                    return Ok(Some(Value::deserialize(&mut reader)?));
+                }
+            }
+            page_idx = page.next;
+        }
+
+        Ok(None)
+    }
+
+    /// # `contains_key`
+    /// Returns `Ok(true)` if the given key is stored in the map
+    pub fn contains_key(&self, key: &Key) -> Result<bool, &'static str> {
+        Ok(self.locate(key)?.is_some())
+    }
+
+    /// # `insert`
+    /// Takes a key and a value and tries to insert them into the map. `Err()` is returned if the given key already exists.
+    pub fn insert(&mut self, key: Key, value: Value) -> Result<(), &'static str> {
+        if self.locate(&key)?.is_some() {
+            return Err("Key already exists in Map");
+        }
+
+        self.store(&key, &value)?;
+        self.size += 1;
+        self.write_header()?;
+        self.size_control()
+    }
+
+    /// # `set`
+    /// Takes a key and a value and sets the value at that key to the given value. Returns the previous value as `Ok(Value)`
+    pub fn set(&mut self, key: Key, value: Value) -> Result<Value, &'static str> {
+        let (page_idx, mut page, start, end) =
+            self.locate(&key)?.ok_or("Such key does not exist")?;
+
+        let mut reader = ByteReader::new(&page.records[start..end]);
+        Key::deserialize(&mut reader)?;
+        let old = Value::deserialize(&mut reader)?;
+
+        let record = Self::record(&key, &value)?;
+        let old_len = end - start + 4;
+        if page.records.len() - old_len + record.len() <= PAGE_CAPACITY {
+            // The new record fits where the old one was
+            page.records.splice(start - 4..end, record);
+            self.write_page(page_idx, &page)?;
+        } else {
+            // Too big for this page, move it to one with room
+            page.records.drain(start - 4..end);
+            self.write_page(page_idx, &page)?;
+            self.free_if_empty(&key, page_idx, &page)?;
+            self.store(&key, &value)?;
+            self.size_control()?;
+        }
+
+        Ok(old)
+    }
+
+    /// # `remove`
+    /// Removes the pair with the given key from the map and returns its value as `Ok(Value)`
+    pub fn remove(&mut self, key: Key) -> Result<Value, &'static str> {
+        let (page_idx, mut page, start, end) =
+            self.locate(&key)?.ok_or("Key does not exist in Map")?;
+
+        let mut reader = ByteReader::new(&page.records[start..end]);
+        Key::deserialize(&mut reader)?;
+        let value = Value::deserialize(&mut reader)?;
+
+        page.records.drain(start - 4..end);
+        self.write_page(page_idx, &page)?;
+        self.free_if_empty(&key, page_idx, &page)?;
+        self.size -= 1;
+        self.write_header()?;
+
+        Ok(value)
+    }
+
+    /// # `iter`
+    /// Returns an iterator over every key-value pair in the map. Pages are read one at a time while iterating.
+    pub fn iter(&self) -> DiskMapIter<'_, Key, Value> {
+        DiskMapIter {
+            map: self,
+            bucket: 0,
+            next_page: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// # `keys`
+    /// Returns a vector of every key stored in the map
+    pub fn keys(&self) -> Result<Vec<Key>, &'static str> {
+        self.iter().map(|pair| pair.map(|(key, _)| key)).collect()
+    }
+
+    /// # `flush`
+    /// Makes sure every write has reached the disk
+    pub fn flush(&mut self) -> Result<(), &'static str> {
+        io(self.file.sync_all())
+    }
+
+    /// # `rehash`
+    /// Rewrites the map into a new file with the given amount of buckets, getting rid of overflow chains. The new file replaces the old one.
+    pub fn rehash(&mut self, bucket_count: u64) -> Result<(), &'static str> {
+        let tmp_path = format!("{}.tmp", self.path);
+        let mut new_map: DiskMap<Key, Value> =
+            DiskMap::create(&tmp_path, bucket_count, self.metadata.clone())?;
+
+        for pair in self.iter() {
+            let (key, value) = pair?;
+            new_map.store(&key, &value)?;
+            new_map.size += 1;
+        }
+        new_map.write_header()?;
+        new_map.flush()?;
+
+        io(rename(&tmp_path, &self.path))?;
+        new_map.path = self.path.clone();
+        *self = new_map;
+        Ok(())
+    }
+
+    /// # `size_control`
+    /// Rehashes the map into more buckets once there are more overflow pages than half the buckets.
+    /// Grows with "* 2 + 1" rather than the "* 2 - 1" of `Map` since a map with a single bucket would otherwise never grow
+    fn size_control(&mut self) -> Result<(), &'static str> {
+        let overflow_pages = self.page_count - 1 - self.bucket_count - self.free_count;
+        if overflow_pages > self.bucket_count >> 1 {
+            return self.rehash(self.bucket_count * 2 + 1);
+        }
+        Ok(())
+    }
+
+    /// # `bucket_of`
+    /// Returns the page index of the bucket the given key belongs to
+    fn bucket_of(&self, key: &Key) -> u64 {
+        1 + key.keyed_hash(self.hash_keys()) as u64 % self.bucket_count
+    }
+
+    /// # `hash_keys`
+    /// Returns the keys of `Hashable::keyed_hash`, both made from the seed
+    fn hash_keys(&self) -> HashKeys {
+        (self.seed, mix(0, self.seed))
+    }
+
+    /// # `record`
+    /// Serializes a key-value pair into a record, including its length prefix
+    fn record(key: &Key, value: &Value) -> Result<Vec<u8>, &'static str> {
+        let mut record = vec![0u8; 4];
+        key.serialize(&mut record);
+        value.serialize(&mut record);
+
+        if record.len() > PAGE_CAPACITY {
+            return Err("Key and value are too big to fit in a page");
+        }
+        let len = (record.len() - 4) as u32;
+        record[..4].copy_from_slice(&len.to_le_bytes());
+        Ok(record)
+    }
+
+    /// # `locate`
+    /// Finds the record of the given key. Returns the index of the page holding it, the page itself and the start and end of the record
+    fn locate(&self, key: &Key) -> Result<Option<(u64, Page, usize, usize)>, &'static str> {
+        let mut page_idx = self.bucket_of(key);
+
+        while page_idx != 0 {
+            let page = self.read_page(page_idx)?;
+            for (start, end) in page.record_offsets()? {
+                if Key::deserialize(&mut ByteReader::new(&page.records[start..end]))? == *key {
+                    return Ok(Some((page_idx, page, start, end)));
+                }
+            }
+            page_idx = page.next;
+        }
+
+        Ok(None)
+    }
+
+    /// # `store`
+    /// Writes a record for the given pair into the first page of its bucket's chain with enough room.
+    /// A new overflow page is added at the end of the chain if none has room. Does not check whether the key exists.
+    fn store(&mut self, key: &Key, value: &Value) -> Result<(), &'static str> {
+        let record = Self::record(key, value)?;
+        let mut page_idx = self.bucket_of(key);
+
+        loop {
+            let mut page = self.read_page(page_idx)?;
+            if page.records.len() + record.len() <= PAGE_CAPACITY {
+                page.records.extend_from_slice(&record);
+                return self.write_page(page_idx, &page);
+            }
+
+            if page.next == 0 {
+                // Chain a free page, or a new overflow page at the end of the file if there is none
+                let new_idx = match self.free_page {
+                    0 => {
+                        self.page_count += 1;
+                        self.page_count - 1
+                    }
+                    free => {
+                        self.free_page = self.read_page(free)?.next;
+                        self.free_count -= 1;
+                        free
+                    }
+                };
+                page.next = new_idx;
+                self.write_page(page_idx, &page)?;
+                self.write_page(new_idx, &Page { next: 0, records: record })?;
+                return self.write_header();
+            }
+            page_idx = page.next;
+        }
+    }
+
+    /// # `free_if_empty`
+    /// Takes an overflow page without records out of the chain of the key's bucket and puts it first on the free page list.
+    /// Buckets stay where they are even when empty
+    fn free_if_empty(&mut self, key: &Key, page_idx: u64, page: &Page) -> Result<(), &'static str> {
+        if !page.records.is_empty() || page_idx <= self.bucket_count {
+            return Ok(());
+        }
+
+        let mut previous_idx = self.bucket_of(key);
+        let mut previous = self.read_page(previous_idx)?;
+        while previous.next != page_idx {
+            if previous.next == 0 {
+                return Err("Corrupted page in disk map");
+            }
+            previous_idx = previous.next;
+            previous = self.read_page(previous_idx)?;
+        }
+        previous.next = page.next;
+        self.write_page(previous_idx, &previous)?;
+
+        self.write_page(page_idx, &Page { next: self.free_page, records: Vec::new() })?;
+        self.free_page = page_idx;
+        self.free_count += 1;
+        self.write_header()
+    }
+
+    /// # `read_page`
+    /// Reads the page at the given index from the file
+    fn read_page(&self, idx: u64) -> Result<Page, &'static str> {
+        let mut bytes = vec![0u8; PAGE_SIZE];
+        let mut file = &self.file;
+        io(file.seek(SeekFrom::Start(idx * PAGE_SIZE as u64)))?;
+        io(file.read_exact(&mut bytes))?;
+
+        let mut reader = ByteReader::new(&bytes);
+        let next = u64::from_le_bytes(reader.take_array()?);
+        let used = u32::from_le_bytes(reader.take_array()?) as usize;
+        if used > PAGE_CAPACITY || next >= self.page_count {
+            return Err("Corrupted page in disk map");
+        }
+
+        Ok(Page {
+            next,
+            records: reader.take(used)?.to_vec(),
+        })
+    }
+
+    /// # `write_page`
+    /// Writes the given page at the given index into the file
+    fn write_page(&mut self, idx: u64, page: &Page) -> Result<(), &'static str> {
+        let mut bytes = Vec::with_capacity(PAGE_SIZE);
+        bytes.extend_from_slice(&page.next.to_le_bytes());
+        bytes.extend_from_slice(&(page.records.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&page.records);
+        bytes.resize(PAGE_SIZE, 0);
+
+        io(self.file.seek(SeekFrom::Start(idx * PAGE_SIZE as u64)))?;
+        io(self.file.write_all(&bytes))
+    }
+
+    /// # `write_header`
+    /// Writes the header page with the current fields of the map
+    fn write_header(&mut self) -> Result<(), &'static str> {
+        let mut header = Vec::with_capacity(PAGE_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&self.bucket_count.to_le_bytes());
+        header.extend_from_slice(&self.seed.to_le_bytes());
+        header.extend_from_slice(&self.size.to_le_bytes());
+        header.extend_from_slice(&self.page_count.to_le_bytes());
+        header.extend_from_slice(&self.free_page.to_le_bytes());
+        header.extend_from_slice(&self.free_count.to_le_bytes());
+        header.extend_from_slice(&(self.metadata.len() as u32).to_le_bytes());
+        header.extend_from_slice(&self.metadata);
+        header.resize(PAGE_SIZE - 4, 0);
+
+        let crc = checksum(&header);
+        header.extend_from_slice(&crc.to_le_bytes());
+
+        io(self.file.seek(SeekFrom::Start(0)))?;
+        io(self.file.write_all(&header))
+    }
+}
+
+/// # `DiskMapIter`
+/// Iterator over the pairs of a `DiskMap`, created by `DiskMap::iter`. Yields `Err()` once if a page could not be read
+pub struct DiskMapIter<'a, Key, Value> {
+    map: &'a DiskMap<Key, Value>,
+    bucket: u64,
+    next_page: u64,
+    pending: Vec<(Key, Value)>,
+}
+
+impl<Key, Value> Iterator for DiskMapIter<'_, Key, Value>
+where
+    Key: PartialEq + Hashable + Serialize + Deserialize,
+    Value: Serialize + Deserialize,
+{
+    type Item = Result<(Key, Value), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.pending.pop() {
+                return Some(Ok(pair));
+            }
+
+            // Current chain is done, move on to the next bucket
+            if self.next_page == 0 {
+                if self.bucket == self.map.bucket_count {
+                    return None;
+                }
+                self.bucket += 1;
+                self.next_page = self.bucket;
+            }
+
+            let page = match self.map.read_page(self.next_page) {
+                Ok(page) => page,
+                Err(e) => return Some(self.fail(e)),
+            };
+            self.next_page = page.next;
+
+            let offsets = match page.record_offsets() {
+                Ok(offsets) => offsets,
+                Err(e) => return Some(self.fail(e)),
+            };
+            // Stored reversed so that popping gives them in page order
+            for (start, end) in offsets.into_iter().rev() {
+                let mut reader = ByteReader::new(&page.records[start..end]);
+                match Key::deserialize(&mut reader)
+                    .and_then(|key| Ok((key, Value::deserialize(&mut reader)?)))
+                {
+                    Ok(pair) => self.pending.push(pair),
+                    Err(e) => return Some(self.fail(e)),
+                }
+            }
+        }
+    }
+}
+
+impl<Key, Value> DiskMapIter<'_, Key, Value> {
+    /// # `fail`
+    /// Stops the iteration and returns the given error
+    fn fail<T>(&mut self, error: &'static str) -> Result<T, &'static str> {
+        self.bucket = self.map.bucket_count;
+        self.next_page = 0;
+        self.pending.clear();
+        Err(error)
+    }
+}
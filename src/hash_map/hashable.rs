@@ -1,3 +1,5 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
 use std::{num::Wrapping, slice::from_raw_parts};
 
 /// # `default_hash<T>`
@@ -19,7 +21,7 @@ pub fn _default_hash<T>(to_hash: &T) -> usize {
             hash = ((hash << 5) + hash) + Wrapping(byte.0 as usize);
         }
     }
-    return hash.0;
+    hash.0
 }
 
 /// # `Hashable`
@@ -28,6 +30,50 @@ pub trait Hashable {
     /// # `hash_code`
     /// Returns the hash code of this data
     fn hash_code(&self) -> usize;
+
+    /// # `keyed_hash`
+    /// Returns a hash of this data that depends on the given secret keys, used by `Map` once it suspects that its keys were picked to collide
+    /// and always by `DiskMap`. The default hashes `hash_code`, which does not help against keys whose `hash_code` is the same.
+    /// Types that can be picked by users should hash their content instead.
+    fn keyed_hash(&self, keys: HashKeys) -> usize {
+        let mut hasher = keyed_hasher(keys);
+        hasher.write_usize(self.hash_code());
+        hasher.finish() as usize
+    }
+}
+
+/// # `HashKeys`
+/// The two secret keys of `Hashable::keyed_hash`. Unlike a `RandomState` they can be stored, so a `DiskMap` hashes the same once opened again
+pub type HashKeys = (u64, u64);
+
+/// # `random_keys`
+/// Returns new random keys for `Hashable::keyed_hash`
+pub fn random_keys() -> HashKeys {
+    (random_seed(), random_seed())
+}
+
+/// # `keyed_hasher`
+/// Returns a SipHash-2-4 hasher using the given keys. `SipHasher` is deprecated only in favour of `RandomState`, whose keys can not be given
+#[allow(deprecated)]
+pub fn keyed_hasher(keys: HashKeys) -> impl Hasher {
+    std::hash::SipHasher::new_with_keys(keys.0, keys.1)
+}
+
+/// # `mix`
+/// Spreads the bits of a hash code combined with a seed so that similar hash codes land far from each other.
+/// Maps use a random seed each so that which keys end up next to each other can not be known ahead.
+pub fn mix(hash: usize, seed: u64) -> u64 {
+    // Finalizer of splitmix64
+    let mut x = (hash as u64) ^ seed;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// # `random_seed`
+/// Returns a new random seed. Every call gives a different seed
+pub fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
 }
 
 impl Hashable for String {
@@ -42,4 +88,12 @@ impl Hashable for String {
 
         hash.0
     }
+
+    /// # `keyed_hash`
+    /// Returns a SipHash of the characters' bytes using the given keys. Strings built to share a `hash_code` do not share this hash.
+    fn keyed_hash(&self, keys: HashKeys) -> usize {
+        let mut hasher = keyed_hasher(keys);
+        hasher.write(self.as_bytes());
+        hasher.finish() as usize
+    }
 }
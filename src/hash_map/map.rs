@@ -1,200 +1,309 @@
 use super::*;
+use std::fmt::{Debug, Formatter};
+use std::iter::FromIterator;
+use std::ops::Index;
+
+/// # `PROBE_LIMIT`
+/// An insertion probing more slots than this is checked for keys picked to collide (hash flooding).
+/// Long probe sequences also happen with honest keys since linear probing forms clusters, so this alone is not enough to tell.
+pub const PROBE_LIMIT: usize = 64;
+
+/// # `COLLISION_LIMIT`
+/// If a long probe sequence passes more keys with the exact same `hash_code` as the inserted key than this, the keys were picked to collide.
+/// Honest keys practically never share a full hash code.
+pub const COLLISION_LIMIT: usize = 8;
 
 /// # `Map`
 /// A Hash map storing a key and a value. The key is used for hashing.
-#[derive(Debug)]
+///
+/// Hash codes are mixed with a random seed picked for every Map, so where keys land differs between Maps and between runs.
+/// That does not help against keys which share the same `hash_code`, for those the Map switches to `Hashable::keyed_hash`
+/// with secret random keys once an insertion probes more than `PROBE_LIMIT` slots and passes more than `COLLISION_LIMIT` such keys.
+#[derive(Clone)]
 pub struct Map<Key, Value> {
     buckets: Vec<SlotStatus<Element<Key, Value>>>,
     size: usize,
+    removed: usize,
     keys: Vec<Key>,
-    updated: bool
+    updated: bool,
+    seed: u64,
+    keyed: Option<HashKeys>,
 }
 
-impl<Key, Value> Map<Key, Value>
-where
-    Key: Clone + PartialEq + Hashable,
-    Value: Clone + PartialEq,
-{
+/// # `empty_buckets`
+/// Creates a vector of the given size filled with `SlotStatus::Empty`. Unlike `vec![SlotStatus::Empty; size]` this does not require the elements to be `Clone`
+fn empty_buckets<T>(size: usize) -> Vec<SlotStatus<T>> {
+    (0..size).map(|_| SlotStatus::Empty).collect()
+}
+
+impl<Key, Value> Map<Key, Value> {
     /// # `new`
     /// Create a new empty Map with the initial size of 31.
     pub fn new() -> Map<Key, Value> {
         Map {
-            buckets: vec![SlotStatus::Empty; 31],
+            buckets: empty_buckets(31),
             size: 0,
+            removed: 0,
             keys: Vec::with_capacity(31),
-            updated: true
+            updated: true,
+            seed: random_seed(),
+            keyed: None,
         }
     }
 
-    /// # `insert`
-    /// Takes a key and a value and tries to insert them into the Map. Returns a `Result<(), &'static str>` where `Err()` is returned if the given key already exists.
-    /// Otherwise `Ok(())`
-    pub fn insert(&mut self, key: Key, value: Value) -> Result<(), &'static str> {
-        let hash = key.hash_code() % self.buckets.len();
-
-        // First occurance of a removed slot. This will be saved to store the element rather than at an empty
-        let mut removed_idx: Option<usize> = None;
-
-        // Linear probing starts
-        for idx in 0..self.buckets.len() {
-            let vec_idx = (hash + idx) % self.buckets.len(); // index inside the bucket vector
+    /// # `is_keyed`
+    /// Returns `true` if the Map detected colliding keys and switched to hashing with `Hashable::keyed_hash`
+    pub fn is_keyed(&self) -> bool {
+        self.keyed.is_some()
+    }
 
-            if let Some(slot) = self.buckets.get(vec_idx) {
-                match slot {
-                    SlotStatus::Empty => {
-                        self.size_control()?;
-                        // Empty reached, element can be placed
-                        // Determine placement of element
-                        let idx = if removed_idx.is_none() {
-                            vec_idx
-                        } else {
-                            removed_idx.unwrap()
-                        };
-                        self.buckets[idx] = SlotStatus::Occupied(Element::new(key.clone(), value));
-                        self.keys.push(key);
-                        self.size += 1;
-                        return Ok(());
-                    }
-                    SlotStatus::Occupied(item) => {
-                        if item.key == key {
-                            // Check key's existance in map
-                            return Err("Key already exists in Map");
-                        }
-                    }
-                    SlotStatus::Removed => {
-                        if removed_idx.is_none() {
-                            // Store first occurance for later use
-                            removed_idx = Some(vec_idx);
-                        }
-                    }
-                }
-            }
-        }
+    /// # `len`
+    /// Returns the amount of key-value pairs currently stored in the Map
+    pub fn len(&self) -> usize {
+        self.size
+    }
 
-        Ok(())
+    /// # `is_empty`
+    /// Returns `true` if the Map holds no key-value pairs
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
     }
 
-    /// # `remove`
-    /// Removes an item from the Map with the given key.
-    /// Returns a `Result<Value, &'static str>` where successful removal returns the value held by the item wrapped in `Ok()`.
-    pub fn remove(&mut self, key: Key) -> Result<Value, &'static str> {
-        let hash = key.hash_code() % self.buckets.len();
+    /// # `iter`
+    /// Returns an iterator over every key-value pair stored in the Map as `(&Key, &Value)`. The order is the order of the buckets, which is arbitrary.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.buckets.iter().filter_map(|slot| match slot {
+            SlotStatus::Occupied(item) => Some((&item.key, &item.value)),
+            _ => None,
+        })
+    }
+}
 
-        // Linear probing starts
-        for idx in 0..self.buckets.len() {
-            // Index inside the bucket vector
-            let vec_idx = (hash + idx) & self.buckets.len();
-
-            if let Some(slot) = self.buckets.get(vec_idx) {
-                match slot {
-                    SlotStatus::Empty => return Err("Key does not exist in Map"), // Reached empty means item was not in or near slot
-                    SlotStatus::Occupied(item) => {
-                        // Found an item
-                        if item.key == key {
-                            // Found the item
-                            let value = item.value.clone();
-                            self.buckets[vec_idx] = SlotStatus::Removed;
-                            self.size -= 1;
-                            self.updated = false;
-                            return Ok(value);
-                        }
-                    }
-                    SlotStatus::Removed => {} // Just skip over removed, nothing to do here
-                }
-            }
+/// Methods that only need to find keys inside the Map. These place no requirements on `Value`.
+impl<Key, Value> Map<Key, Value>
+where
+    Key: PartialEq + Hashable,
+{
+    /// # `hash_of`
+    /// Returns the hash the Map uses for the given key. Either the seeded `hash_code`, or the `keyed_hash` once colliding keys were detected
+    fn hash_of(&self, key: &Key) -> usize {
+        match &self.keyed {
+            Some(keys) => key.keyed_hash(*keys),
+            None => mix(key.hash_code(), self.seed) as usize,
         }
-
-        // This really should never happen but just in case
-        Err("Unknown error occured")
     }
 
-    /// # `get`
-    /// Returns the value stored at the given key as `Option<Value>`. `None` is returned if the key is not available
-    pub fn get(&self, key: Key) -> Option<Value> {
-        let hash = key.hash_code() % self.buckets.len();
+    /// # `find`
+    /// Returns the index inside the buckets of the slot holding the given key as `Option<usize>`. `None` is returned if the key is not available
+    fn find(&self, key: &Key) -> Option<usize> {
+        let hash = self.hash_of(key) % self.buckets.len();
 
         // Linear probing starts
         for idx in 0..self.buckets.len() {
             let vec_idx = (hash + idx) % self.buckets.len();
 
-            if let Some(slot) = self.buckets.get(vec_idx) {
-                match slot {
-                    SlotStatus::Empty => return None,
-                    SlotStatus::Occupied(item) => {
-                        if item.key == key {
-                            return Some(item.value.clone());
-                        }
+            match &self.buckets[vec_idx] {
+                SlotStatus::Empty => return None,
+                SlotStatus::Occupied(item) => {
+                    if item.key == *key {
+                        return Some(vec_idx);
                     }
-                    SlotStatus::Removed => {}
                 }
+                SlotStatus::Removed => {}
             }
         }
 
         None
     }
-    
-    /// # `set`
-    /// Takes a key and a value and sets the value at that key to the given value. Return `Ok(())` if successful, else `Err()` with the error message
-    pub fn set(&mut self, key: Key, value: Value) -> Result<(), &'static str> {
-        let hash = key.hash_code() % self.buckets.len();
 
-        // Linear probing starts
-        for idx in 0..self.buckets.len() {
-            let vec_idx = (hash + idx) % self.buckets.len();
+    /// # `get_ref`
+    /// Returns a reference to the value stored at the given key as `Option<&Value>`. `None` is returned if the key is not available
+    pub fn get_ref(&self, key: &Key) -> Option<&Value> {
+        match &self.buckets[self.find(key)?] {
+            SlotStatus::Occupied(item) => Some(&item.value),
+            _ => None,
+        }
+    }
 
-            if let Some(slot) = self.buckets.get_mut(vec_idx) {
-                match slot {
-                    SlotStatus::Empty => return Err("Such key does not exist"),
-                    SlotStatus::Occupied(item) => {
-                        if item.key == key {
-                            item.value = value;
-                            return Ok(());
-                        }
-                    }
-                    SlotStatus::Removed => {}
-                }
+    /// # `get_mut`
+    /// Returns a mutable reference to the value stored at the given key as `Option<&mut Value>`. `None` is returned if the key is not available
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+        let idx = self.find(key)?;
+        match &mut self.buckets[idx] {
+            SlotStatus::Occupied(item) => Some(&mut item.value),
+            _ => None,
+        }
+    }
+
+    /// # `contains_key`
+    /// Returns `true` if the given key is stored in the Map
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// # `remove`
+    /// Removes an item from the Map with the given key.
+    /// Returns a `Result<Value, &'static str>` where successful removal returns the value held by the item wrapped in `Ok()`.
+    /// The value is moved out of its slot, leaving `SlotStatus::Removed` in its place until `clean_removed` rebuilds the buckets.
+    pub fn remove(&mut self, key: Key) -> Result<Value, &'static str> {
+        let idx = self.find(&key).ok_or("Key does not exist in Map")?;
+
+        match std::mem::replace(&mut self.buckets[idx], SlotStatus::Removed) {
+            SlotStatus::Occupied(item) => {
+                self.size -= 1;
+                self.removed += 1;
+                self.updated = false;
+                self.clean_removed();
+                Ok(item.value)
             }
+            // This really should never happen but just in case
+            _ => Err("Unknown error occured"),
         }
+    }
 
-        Err("Unexpected error occured")
+    /// # `set`
+    /// Takes a key and a value and sets the value at that key to the given value.
+    /// Returns the previous value, moved out of the slot, as `Ok(Value)` if successful, else `Err()` with the error message
+    pub fn set(&mut self, key: Key, value: Value) -> Result<Value, &'static str> {
+        match self.get_mut(&key) {
+            Some(current) => Ok(std::mem::replace(current, value)),
+            None => Err("Such key does not exist"),
+        }
     }
 
     /// # `resize`
     /// Resizes the Map into the given size as `usize`. Returns `Ok(())` on success.
-    /// This is a performance-heavy process.
+    /// This is a performance-heavy process. Elements are moved into their new slots rather than copied.
     pub fn resize(&mut self, size: usize) -> Result<(), &'static str> {
         if self.size > size {
             return Err("Map is bigger than given size");
         }
 
-        let mut new_bucket: Vec<SlotStatus<Element<Key, Value>>> = vec![SlotStatus::Empty; size];
+        let old_buckets = std::mem::replace(&mut self.buckets, empty_buckets(size));
 
-        for slot in self.buckets.iter() {
+        for slot in old_buckets.into_iter() {
             if let SlotStatus::Occupied(item) = slot {
-                let hash = item.key.hash_code() % size;
-
-                for idx in 0..size {
-                    let vec_idx = (hash + idx) % size;
-
-                    if let Some(new_slot) = new_bucket.get(vec_idx) {
-                        if let SlotStatus::Empty = new_slot {
-                            new_bucket[vec_idx] = SlotStatus::Occupied(Element::new(
-                                item.key.clone(),
-                                item.value.clone(),
-                            ));
-                            break;
-                        }
-                    }
-                }
+                let hash = self.hash_of(&item.key) % size;
+
+                // Find the first empty slot using linear probing. There are no removed slots in the new buckets.
+                let vec_idx = (0..size)
+                    .map(|idx| (hash + idx) % size)
+                    .find(|vec_idx| matches!(self.buckets[*vec_idx], SlotStatus::Empty))
+                    .ok_or("Unknown error occured")?;
+
+                self.buckets[vec_idx] = SlotStatus::Occupied(item);
             }
         }
+        self.removed = 0;
 
-        self.buckets = new_bucket;
+        Ok(())
+    }
 
+    /// # `reserve`
+    /// Grows the Map, following the same size sequence as automatic resizing, so that `additional` more pairs can be inserted without resizing in between.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), &'static str> {
+        let needed = self.size + additional;
+        let mut max = self.buckets.len();
+
+        while needed > (max >> 1) + (max >> 2) {
+            max = max * 2 - 1;
+        }
+
+        if max != self.buckets.len() {
+            return self.resize(max);
+        }
         Ok(())
     }
 
-    
+    /// # `clear`
+    /// Removes every key-value pair from the Map. The amount of buckets is kept.
+    pub fn clear(&mut self) {
+        self.buckets = empty_buckets(self.buckets.len());
+        self.keys.clear();
+        self.size = 0;
+        self.removed = 0;
+        self.updated = true;
+    }
+
+    /// # `retain`
+    /// Keeps only the key-value pairs for which the given predicate returns `true`. The predicate may change the values it is given.
+    /// This is done in a single pass over the buckets rather than removing the keys one by one.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Key, &mut Value) -> bool,
+    {
+        self.extract_if(|key, value| !keep(key, value));
+    }
+
+    /// # `extract_if`
+    /// Removes every key-value pair for which the given predicate returns `true` and returns them as `Vec<(Key, Value)>`.
+    /// This is done in a single pass over the buckets rather than removing the keys one by one.
+    pub fn extract_if<F>(&mut self, mut extract: F) -> Vec<(Key, Value)>
+    where
+        F: FnMut(&Key, &mut Value) -> bool,
+    {
+        let mut extracted: Vec<(Key, Value)> = Vec::new();
+
+        for slot in self.buckets.iter_mut() {
+            let matched = match slot {
+                SlotStatus::Occupied(item) => extract(&item.key, &mut item.value),
+                _ => false,
+            };
+
+            if matched {
+                if let SlotStatus::Occupied(item) = std::mem::replace(slot, SlotStatus::Removed) {
+                    extracted.push((item.key, item.value));
+                }
+            }
+        }
+
+        if !extracted.is_empty() {
+            self.size -= extracted.len();
+            self.removed += extracted.len();
+            self.updated = false;
+            self.clean_removed();
+        }
+
+        extracted
+    }
+
+    /// # `remove_many`
+    /// Removes every given key that exists in the Map and returns the removed pairs as `Vec<(Key, Value)>`. Keys that do not exist are skipped.
+    pub fn remove_many<I>(&mut self, keys: I) -> Vec<(Key, Value)>
+    where
+        I: IntoIterator<Item = Key>,
+    {
+        let mut removed: Vec<(Key, Value)> = Vec::new();
+
+        for key in keys {
+            if let Some(idx) = self.find(&key) {
+                if let SlotStatus::Occupied(item) =
+                    std::mem::replace(&mut self.buckets[idx], SlotStatus::Removed)
+                {
+                    removed.push((item.key, item.value));
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            self.size -= removed.len();
+            self.removed += removed.len();
+            self.updated = false;
+            self.clean_removed();
+        }
+
+        removed
+    }
+
+    /// # `clean_removed`
+    /// Rebuilds the buckets when more than a quarter of them are removed slots.
+    /// Removed slots never stop a probe, so having too many of them makes every search longer.
+    fn clean_removed(&mut self) {
+        if self.removed > self.buckets.len() >> 2 {
+            // Resizing into the same size only moves the occupied slots, which gets rid of the removed ones
+            let _ = self.resize(self.buckets.len());
+        }
+    }
 
     /// # 'size_control`
     /// Checks whether the Map requires resizing and does so if the requirements are met.
@@ -205,11 +314,123 @@ where
         let margin = self.size > (max >> 1) + (max >> 2);
 
         if margin {
-            println!("RESIZE");
             return self.resize(max * 2 - 1);
         }
         Ok(())
     }
+}
+
+/// Methods that need to store the key a second time to keep track of the insertion order.
+impl<Key, Value> Map<Key, Value>
+where
+    Key: Clone + PartialEq + Hashable,
+{
+    /// # `insert`
+    /// Takes a key and a value and tries to insert them into the Map. Returns a `Result<(), &'static str>` where `Err()` is returned if the given key already exists.
+    /// Otherwise `Ok(())`
+    pub fn insert(&mut self, key: Key, value: Value) -> Result<(), &'static str> {
+        // Resize before probing so that the found slot is a slot of the final buckets
+        self.size_control()?;
+
+        let hash = self.hash_of(&key) % self.buckets.len();
+
+        // First occurance of a removed slot. This will be saved to store the element rather than at an empty
+        let mut removed_idx: Option<usize> = None;
+
+        // Linear probing starts
+        for idx in 0..self.buckets.len() {
+            let vec_idx = (hash + idx) % self.buckets.len(); // index inside the bucket vector
+
+            match &self.buckets[vec_idx] {
+                SlotStatus::Empty => {
+                    // Empty reached, element can be placed
+                    // Determine placement of element
+                    let place_idx = removed_idx.unwrap_or(vec_idx);
+                    let flooded = self.is_flooded(&key, hash, idx);
+                    self.place(place_idx, key, value);
+                    return self.flood_control(flooded);
+                }
+                SlotStatus::Occupied(item) => {
+                    if item.key == key {
+                        // Check key's existance in map
+                        return Err("Key already exists in Map");
+                    }
+                }
+                SlotStatus::Removed => {
+                    if removed_idx.is_none() {
+                        // Store first occurance for later use
+                        removed_idx = Some(vec_idx);
+                    }
+                }
+            }
+        }
+
+        // The whole Map was probed without finding an empty slot, the key can still go into a removed one
+        if let Some(idx) = removed_idx {
+            let flooded = self.is_flooded(&key, hash, self.buckets.len());
+            self.place(idx, key, value);
+            return self.flood_control(flooded);
+        }
+
+        Err("Map is full")
+    }
+
+    /// # `is_flooded`
+    /// Takes a key being inserted, where its probing started and how many slots were probed.
+    /// Returns `true` if the probe sequence was longer than `PROBE_LIMIT` and passed more than `COLLISION_LIMIT` keys with the same `hash_code`.
+    /// The hash codes are only calculated for long probe sequences, which are rare.
+    fn is_flooded(&self, key: &Key, start: usize, probes: usize) -> bool {
+        if probes <= PROBE_LIMIT || self.keyed.is_some() {
+            return false;
+        }
+
+        let hash_code = key.hash_code();
+        let collisions = (0..probes)
+            .filter(|idx| match &self.buckets[(start + idx) % self.buckets.len()] {
+                SlotStatus::Occupied(item) => item.key.hash_code() == hash_code,
+                _ => false,
+            })
+            .count();
+
+        collisions > COLLISION_LIMIT
+    }
+
+    /// # `flood_control`
+    /// Switches the Map to `Hashable::keyed_hash` with new secret keys and places every element again if colliding keys were detected
+    fn flood_control(&mut self, flooded: bool) -> Result<(), &'static str> {
+        if flooded {
+            self.keyed = Some(random_keys());
+            return self.resize(self.buckets.len());
+        }
+        Ok(())
+    }
+
+    /// # `place`
+    /// Stores a new element at the given bucket index and keeps track of its key
+    fn place(&mut self, idx: usize, key: Key, value: Value) {
+        if let SlotStatus::Removed = self.buckets[idx] {
+            self.removed -= 1;
+        }
+        self.keys.push(key.clone());
+        self.buckets[idx] = SlotStatus::Occupied(Element::new(key, value));
+        self.size += 1;
+    }
+
+    /// # `insert_many`
+    /// Inserts every given key-value pair into the Map. The Map is grown once up front instead of resizing in between insertions.
+    /// Returns `Err()` at the first key that already exists, the pairs before it are kept inserted.
+    pub fn insert_many<I>(&mut self, pairs: I) -> Result<(), &'static str>
+    where
+        I: IntoIterator<Item = (Key, Value)>,
+    {
+        let pairs = pairs.into_iter();
+        self.reserve(pairs.size_hint().0)?;
+
+        for (key, value) in pairs {
+            self.insert(key, value)?;
+        }
+        Ok(())
+    }
 
     /// # `keys`
     /// Return a vector of the keys currently in the map
@@ -219,15 +440,112 @@ where
         }
 
         let mut new_keys: Vec<Key> = Vec::with_capacity(self.size);
+        // A key removed and then inserted again shows up twice in the old keys
+        let mut seen: Map<Key, ()> = Map::new();
 
         for key in self.keys.iter() {
-            if let Some(_) = self.get(key.to_owned()) {
-                new_keys.push(key.to_owned());
+            if self.contains_key(key) && seen.insert(key.clone(), ()).is_ok() {
+                new_keys.push(key.clone());
             }
         }
 
         self.keys = new_keys;
         self.updated = true;
-        return &self.keys;
+        &self.keys
+    }
+}
+
+/// Methods that hand out copies of the stored values.
+impl<Key, Value> Map<Key, Value>
+where
+    Key: PartialEq + Hashable,
+    Value: Clone,
+{
+    /// # `get`
+    /// Returns the value stored at the given key as `Option<Value>`. `None` is returned if the key is not available
+    pub fn get(&self, key: Key) -> Option<Value> {
+        self.get_ref(&key).cloned()
+    }
+
+    /// # `extend_from`
+    /// Copies every key-value pair of the given Map into this one. Keys that already exist have their value replaced
+    pub fn extend_from(&mut self, other: &Map<Key, Value>)
+    where
+        Key: Clone,
+    {
+        // Reserving is only an optimization, the insertions resize on their own when needed
+        let _ = self.reserve(other.len());
+        self.extend(other.iter().map(|(key, value)| (key.clone(), value.clone())));
+    }
+}
+
+impl<Key, Value> Default for Map<Key, Value> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl<Key, Value> Debug for Map<Key, Value>
+where
+    Key: Debug,
+    Value: Debug,
+{
+    /// Only the stored key-value pairs are printed, empty and removed slots are left out
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Key, Value> PartialEq for Map<Key, Value>
+where
+    Key: PartialEq + Hashable,
+    Value: PartialEq,
+{
+    /// Two Maps are equal if they hold the same key-value pairs, no matter how these are laid out in the buckets
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get_ref(key) == Some(value))
+    }
+}
+
+impl<Key, Value> Index<&Key> for Map<Key, Value>
+where
+    Key: PartialEq + Hashable,
+{
+    type Output = Value;
+
+    /// Returns a reference to the value stored at the given key.
+    /// Panics if the key does not exist in the Map
+    fn index(&self, key: &Key) -> &Value {
+        self.get_ref(key).expect("Key does not exist in Map")
+    }
+}
+
+impl<Key, Value> Extend<(Key, Value)> for Map<Key, Value>
+where
+    Key: Clone + PartialEq + Hashable,
+{
+    /// Inserts every given key-value pair. Keys that already exist have their value replaced
+    fn extend<T: IntoIterator<Item = (Key, Value)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            if let Some(current) = self.get_mut(&key) {
+                *current = value;
+            } else {
+                let _ = self.insert(key, value);
+            }
+        }
+    }
+}
+
+impl<Key, Value> FromIterator<(Key, Value)> for Map<Key, Value>
+where
+    Key: Clone + PartialEq + Hashable,
+{
+    fn from_iter<T: IntoIterator<Item = (Key, Value)>>(iter: T) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
     }
 }
@@ -1,5 +1,7 @@
+mod disk_map;
 mod hashable;
 mod map;
+mod serialize;
 
 #[derive(Clone, Copy, Debug)]
 /// # `SlotStatus<T>`
@@ -20,11 +22,7 @@ pub struct Element<Key, Value> {
     pub key: Key,
 }
 
-impl<Key, Value> Element<Key, Value>
-where
-    Key: Clone + PartialEq + Hashable,
-    Value: Clone,
-{
+impl<Key, Value> Element<Key, Value> {
     /// # `new`
     /// Creates a new Element using the given `Key` and `Value`
     pub fn new(key: Key, value: Value) -> Element<Key, Value> {
@@ -32,4 +30,9 @@ where
     }
 }
 
-pub use {hashable::Hashable, map::Map};
+pub use {
+    disk_map::{DiskMap, DiskMapIter, MAGIC as DISK_MAGIC},
+    hashable::{keyed_hasher, mix, random_keys, random_seed, HashKeys, Hashable},
+    map::Map,
+    serialize::{checksum, read_varint, write_varint, ByteReader, Deserialize, Serialize},
+};
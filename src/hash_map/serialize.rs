@@ -0,0 +1,324 @@
+use super::{Hashable, Map};
+
+/// # `MAGIC`
+/// The first bytes of every serialized `Map`. Used to recognize the format.
+const MAGIC: &[u8; 4] = b"AZHM";
+
+/// # `VERSION`
+/// The version of the binary format written by `Map::to_bytes`. Bumped whenever the layout changes.
+pub const VERSION: u16 = 1;
+
+/// # `Serialize`
+/// A trait used to turn data into a compact sequence of bytes that can be turned back using `Deserialize`
+pub trait Serialize {
+    /// # `serialize`
+    /// Appends the bytes representing this data to the given buffer
+    fn serialize(&self, out: &mut Vec<u8>);
+}
+
+/// # `Deserialize`
+/// A trait used to read data back from the bytes written by its `Serialize` implementation
+pub trait Deserialize: Sized {
+    /// # `deserialize`
+    /// Reads this data from the given `ByteReader`. Returns `Err()` with the error if the bytes do not hold valid data
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str>;
+}
+
+/// # `ByteReader`
+/// A cursor over a slice of bytes that `Deserialize` implementations read from
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    /// # `new`
+    /// Creates a reader starting at the first of the given bytes
+    pub fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, position: 0 }
+    }
+
+    /// # `take`
+    /// Returns the next `amount` bytes and moves past them. `Err()` is returned if there are not enough bytes left
+    pub fn take(&mut self, amount: usize) -> Result<&'a [u8], &'static str> {
+        if self.remaining() < amount {
+            return Err("Unexpected end of data");
+        }
+        let bytes = &self.bytes[self.position..self.position + amount];
+        self.position += amount;
+        Ok(bytes)
+    }
+
+    /// # `take_array`
+    /// Returns the next `N` bytes as an array and moves past them
+    pub fn take_array<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// # `remaining`
+    /// Returns the amount of bytes that are left to read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+}
+
+/// # `write_varint`
+/// Writes an unsigned integer using as few bytes as possible (LEB128). Each byte holds 7 bits of the number and the highest bit tells whether more bytes follow.
+pub fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// # `read_varint`
+/// Reads an unsigned integer written by `write_varint`
+pub fn read_varint(reader: &mut ByteReader) -> Result<u64, &'static str> {
+    let mut value: u64 = 0;
+
+    for shift in (0..64).step_by(7) {
+        let byte = reader.take(1)?[0];
+        // The tenth byte may only hold the last bit of a u64
+        if shift == 63 && byte > 1 {
+            return Err("Integer too large");
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err("Integer too large")
+}
+
+/// # `checksum`
+/// Calculates the CRC-32 (the one used by zip and png) of the given bytes
+pub fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            // Shift out one bit, xor with the reversed polynomial if that bit was set
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+/// Unsigned integers are stored as varints
+macro_rules! serialize_unsigned {
+    ($($t:ty),*) => {$(
+        impl Serialize for $t {
+            fn serialize(&self, out: &mut Vec<u8>) {
+                write_varint(*self as u64, out);
+            }
+        }
+
+        impl Deserialize for $t {
+            fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+                <$t>::try_from(read_varint(reader)?).map_err(|_| "Integer out of range")
+            }
+        }
+    )*};
+}
+
+/// Signed integers are zigzag encoded (0, -1, 1, -2, 2...) so small negative numbers stay small, then stored as varints
+macro_rules! serialize_signed {
+    ($($t:ty),*) => {$(
+        impl Serialize for $t {
+            fn serialize(&self, out: &mut Vec<u8>) {
+                let value = *self as i64;
+                write_varint(((value << 1) ^ (value >> 63)) as u64, out);
+            }
+        }
+
+        impl Deserialize for $t {
+            fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+                let zigzag = read_varint(reader)?;
+                let value = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+                <$t>::try_from(value).map_err(|_| "Integer out of range")
+            }
+        }
+    )*};
+}
+
+/// Floats are stored as their little endian bytes so that every bit, including NaN payloads, survives
+macro_rules! serialize_float {
+    ($($t:ty),*) => {$(
+        impl Serialize for $t {
+            fn serialize(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+
+        impl Deserialize for $t {
+            fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+                Ok(<$t>::from_le_bytes(reader.take_array()?))
+            }
+        }
+    )*};
+}
+
+serialize_unsigned!(u8, u16, u32, u64, usize);
+serialize_signed!(i8, i16, i32, i64, isize);
+serialize_float!(f32, f64);
+
+impl Serialize for bool {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl Deserialize for bool {
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+        match reader.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Invalid boolean"),
+        }
+    }
+}
+
+impl Serialize for String {
+    /// Stored as the length in bytes followed by the UTF-8 bytes
+    fn serialize(&self, out: &mut Vec<u8>) {
+        self.len().serialize(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Deserialize for String {
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+        let len = usize::deserialize(reader)?;
+        let bytes = reader.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8 in string")
+    }
+}
+
+/// # `read_len`
+/// Reads the amount of items of a collection. `Err()` if it is more than the bytes left to read, since every item takes at least one.
+/// Types that would serialize to no bytes, such as `()`, are left out so this holds
+fn read_len(reader: &mut ByteReader) -> Result<usize, &'static str> {
+    let len = usize::deserialize(reader)?;
+    if len > reader.remaining() {
+        return Err("Length is longer than the data left");
+    }
+    Ok(len)
+}
+
+impl<T: Serialize> Serialize for Vec<T> {
+    /// Stored as the amount of items followed by every item
+    fn serialize(&self, out: &mut Vec<u8>) {
+        self.len().serialize(out);
+        for item in self.iter() {
+            item.serialize(out);
+        }
+    }
+}
+
+impl<T: Deserialize> Deserialize for Vec<T> {
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+        // The length is read from data that can not be trusted. Every item of a stored type takes at least one byte, so a
+        // length above what is left to read is corrupted data rather than a reason to loop or allocate that often
+        let len = read_len(reader)?;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(T::deserialize(reader)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<Key, Value> Serialize for Map<Key, Value>
+where
+    Key: Serialize,
+    Value: Serialize,
+{
+    /// Stored as the amount of pairs followed by every key and its value. No header or checksum, see `Map::to_bytes` for that
+    fn serialize(&self, out: &mut Vec<u8>) {
+        self.len().serialize(out);
+        for (key, value) in self.iter() {
+            key.serialize(out);
+            value.serialize(out);
+        }
+    }
+}
+
+impl<Key, Value> Deserialize for Map<Key, Value>
+where
+    Key: Clone + PartialEq + Hashable + Deserialize,
+    Value: Deserialize,
+{
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+        let len = read_len(reader)?;
+        let mut map = Map::new();
+        map.reserve(len)?;
+
+        for _ in 0..len {
+            let key = Key::deserialize(reader)?;
+            let value = Value::deserialize(reader)?;
+            map.insert(key, value)?;
+        }
+        Ok(map)
+    }
+}
+
+impl<Key, Value> Map<Key, Value>
+where
+    Key: Serialize,
+    Value: Serialize,
+{
+    /// # `to_bytes`
+    /// Serializes the Map into a self-contained sequence of bytes with the layout:
+    /// - `AZHM`, 4 bytes identifying the format
+    /// - The format version as a little endian `u16`
+    /// - The pairs as written by `Serialize`
+    /// - A CRC-32 of everything before it as a little endian `u32`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        self.serialize(&mut out);
+
+        let crc = checksum(&out);
+        out.extend_from_slice(&crc.to_le_bytes());
+        out
+    }
+}
+
+impl<Key, Value> Map<Key, Value>
+where
+    Key: Clone + PartialEq + Hashable + Deserialize,
+    Value: Deserialize,
+{
+    /// # `from_bytes`
+    /// Reads a Map written by `Map::to_bytes`. Returns `Err()` if the bytes are not in that format, were written by another version,
+    /// do not match their checksum or hold invalid data.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Map<Key, Value>, &'static str> {
+        if bytes.len() < MAGIC.len() + 2 + 4 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("Data is not a serialized Map");
+        }
+
+        let (content, crc) = bytes.split_at(bytes.len() - 4);
+        if checksum(content) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err("Checksum mismatch, data is corrupted");
+        }
+
+        let mut reader = ByteReader::new(&content[MAGIC.len()..]);
+        if u16::from_le_bytes(reader.take_array()?) != VERSION {
+            return Err("Unsupported Map format version");
+        }
+
+        let map = Map::deserialize(&mut reader)?;
+        if reader.remaining() != 0 {
+            return Err("Unexpected data after Map");
+        }
+        Ok(map)
+    }
+}
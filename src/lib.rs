@@ -0,0 +1,2 @@
+pub mod hash_map;
+pub mod tools;
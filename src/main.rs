@@ -1,30 +1,48 @@
-mod hash_map;
-mod tools;
-
-use tools::{Table, query};
+use azeezd_hash::tools::{Database, query_database};
 use std::env::args;
+use std::path::Path;
 
 fn main() {
-    let mut args = args();
+    let args = args();
     if args.len() < 2 {
         println!("Insufficient amount of arguments given");
         return;
-    } 
-    if let Ok(mut table) = Table::new(args.nth(1).unwrap()) {
-        println!("Table opened!");
-        let input = std::io::stdin();
-        let mut buffer = String::new();
-        loop {
-            if let Ok(_) = input.read_line(&mut buffer) {
-                if let Err(e) = query(&mut table, &buffer.trim_end().to_string()) {
-                    println!("{}", e);
+    }
+    // A directory is opened as a database of its tables, files as a database of those tables with the first in use
+    let paths = args.skip(1).collect::<Vec<String>>();
+    let opened = if paths.len() == 1 && Path::new(&paths[0]).is_dir() {
+        Database::open(&paths[0])
+    } else {
+        let mut database = Database::new();
+        paths
+            .iter()
+            .map(|path| database.open_table(path.clone(), None))
+            .collect::<Result<Vec<String>, String>>()
+            .and_then(|names| database.use_table(&names[0]))
+            .map(|_| database)
+    };
+    match opened {
+        Ok(mut database) => {
+            match database.names().len() {
+                1 => println!("Table opened!"),
+                _ => println!("Database opened with tables: {}", database.names().join(", ")),
+            }
+            let input = std::io::stdin();
+            let mut buffer = String::new();
+            loop {
+                match input.read_line(&mut buffer) {
+                    // End of input, such as when the queries are piped in from a file
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if let Err(e) = query_database(&mut database, buffer.trim_end()) {
+                            println!("{}", e);
+                        }
+                    }
+                    Err(_) => println!("Error while reading from stdin"),
                 }
-            } else {
-                println!("Error while reading from stdin");
+                buffer.clear();
             }
-            buffer.clear();
         }
-    } else {
-        println!("Unable to open table");
-    }  
-}
\ No newline at end of file
+        Err(e) => println!("Unable to open table: {}", e),
+    }
+}
@@ -0,0 +1,259 @@
+use super::{ColumnType, DataItem, Decimal};
+use crate::hash_map::{HashKeys, Hashable, Map};
+use std::num::Wrapping;
+
+/// # `Aggregate`
+/// The functions that turn the values of many rows into one: `COUNT`, `SUM`, `AVG`, `MIN` and `MAX`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    /// # `from_name`
+    /// Returns the aggregate with the given name in any case, `None` if there is no such aggregate
+    pub fn from_name(name: &str) -> Option<Aggregate> {
+        [Aggregate::Count, Aggregate::Sum, Aggregate::Avg, Aggregate::Min, Aggregate::Max]
+            .into_iter()
+            .find(|aggregate| aggregate.name().eq_ignore_ascii_case(name))
+    }
+
+    /// # `name`
+    /// Returns the name of the aggregate as it is written in queries
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "COUNT",
+            Aggregate::Sum => "SUM",
+            Aggregate::Avg => "AVG",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+        }
+    }
+
+    /// # `check`
+    /// `Err()` if the aggregate can not be used on values of the given type, such as `SUM` of text
+    pub fn check(&self, kind: Option<ColumnType>) -> Result<(), String> {
+        match (self, kind) {
+            (Aggregate::Sum | Aggregate::Avg, Some(kind)) if !kind.is_numeric() => {
+                Err(format!("{} only works on numbers, not {:?}", self.name(), kind))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// # `kind`
+    /// Returns the type of the result of the aggregate over values of the given type. Sums of whole numbers are a `Long`,
+    /// or a `ULong` for `ULong` values, sums of floats a `Double` and sums of decimals a `Decimal`. Averages are always a `Double`
+    pub fn kind(&self, kind: Option<ColumnType>) -> Option<ColumnType> {
+        match self {
+            Aggregate::Count => Some(ColumnType::ULong),
+            Aggregate::Sum => match kind? {
+                ColumnType::Float | ColumnType::Double => Some(ColumnType::Double),
+                ColumnType::Decimal => Some(ColumnType::Decimal),
+                ColumnType::ULong => Some(ColumnType::ULong),
+                _ => Some(ColumnType::Long),
+            },
+            Aggregate::Avg => Some(ColumnType::Double),
+            Aggregate::Min | Aggregate::Max => kind,
+        }
+    }
+}
+
+/// # `Sum`
+/// The running total of an `Accumulator`. Whole numbers are added as an `i128` and decimals as an `i128` mantissa with the
+/// largest scale seen so far, so neither can overflow before a result has to be returned. Adding a float turns the sum into a float.
+/// Whole numbers also keep whether every value was a `ULong`, which makes the result a `ULong` as `Aggregate::kind` says
+#[derive(Debug, Clone, Copy)]
+enum Sum {
+    Empty,
+    Integer(i128, bool),
+    Decimal(i128, u8),
+    Float(f64),
+}
+
+/// # `SUM_OVERFLOW`
+/// Error given when a sum does not fit the type of its result
+const SUM_OVERFLOW: &str = "SUM is too big for its type";
+
+impl Sum {
+    /// # `add`
+    /// Adds a number to the sum, values that are not numbers are ignored
+    fn add(self, value: &DataItem) -> Result<Sum, &'static str> {
+        let integer = match value {
+            DataItem::UInteger(e) => Some(*e as i128),
+            DataItem::Integer(e) => Some(*e as i128),
+            DataItem::Long(e) => Some(*e as i128),
+            DataItem::ULong(e) => Some(*e as i128),
+            _ => None,
+        };
+
+        let unsigned = matches!(value, DataItem::ULong(_));
+
+        Ok(match (self, integer, value) {
+            (Sum::Empty, Some(e), _) => Sum::Integer(e, unsigned),
+            (Sum::Integer(sum, all_unsigned), Some(e), _) => {
+                Sum::Integer(sum.checked_add(e).ok_or(SUM_OVERFLOW)?, all_unsigned && unsigned)
+            }
+            (Sum::Decimal(sum, scale), Some(e), _) => {
+                Sum::Decimal(Sum::rescale(e, 0, scale)?.checked_add(sum).ok_or(SUM_OVERFLOW)?, scale)
+            }
+            (Sum::Empty, _, DataItem::Decimal(e)) => Sum::Decimal(e.mantissa as i128, e.scale),
+            (Sum::Integer(sum, _), _, DataItem::Decimal(e)) => Sum::Decimal(
+                Sum::rescale(sum, 0, e.scale)?.checked_add(e.mantissa as i128).ok_or(SUM_OVERFLOW)?,
+                e.scale,
+            ),
+            (Sum::Decimal(sum, scale), _, DataItem::Decimal(e)) => {
+                let new_scale = scale.max(e.scale);
+                let sum = Sum::rescale(sum, scale, new_scale)?;
+                let e = Sum::rescale(e.mantissa as i128, e.scale, new_scale)?;
+                Sum::Decimal(sum.checked_add(e).ok_or(SUM_OVERFLOW)?, new_scale)
+            }
+            (sum, _, value) => match value.as_f64() {
+                Some(e) => Sum::Float(sum.to_f64() + e),
+                None => sum,
+            },
+        })
+    }
+
+    /// # `rescale`
+    /// Returns the mantissa of a decimal with the given scale as if it had the new, larger or equal, scale
+    fn rescale(mantissa: i128, scale: u8, new_scale: u8) -> Result<i128, &'static str> {
+        10i128
+            .checked_pow((new_scale - scale) as u32)
+            .and_then(|factor| mantissa.checked_mul(factor))
+            .ok_or(SUM_OVERFLOW)
+    }
+
+    /// # `to_f64`
+    /// Returns the closest `f64` to the sum
+    fn to_f64(self) -> f64 {
+        match self {
+            Sum::Empty => 0.0,
+            Sum::Integer(sum, _) => sum as f64,
+            Sum::Decimal(sum, scale) => sum as f64 / 10f64.powi(scale as i32),
+            Sum::Float(sum) => sum,
+        }
+    }
+
+    /// # `value`
+    /// Returns the sum as a value. Whole numbers are a `ULong` if every value was one, otherwise a `Long`. `Err()` if it does not fit
+    fn value(self) -> Result<DataItem, &'static str> {
+        Ok(match self {
+            Sum::Empty => DataItem::Null,
+            Sum::Integer(sum, true) => DataItem::ULong(u64::try_from(sum).map_err(|_| SUM_OVERFLOW)?),
+            Sum::Integer(sum, false) => DataItem::Long(i64::try_from(sum).map_err(|_| SUM_OVERFLOW)?),
+            Sum::Decimal(sum, scale) => DataItem::Decimal(Decimal {
+                mantissa: i64::try_from(sum).map_err(|_| SUM_OVERFLOW)?,
+                scale,
+            }),
+            Sum::Float(sum) => DataItem::Double(sum),
+        })
+    }
+}
+
+/// # `Accumulator`
+/// Computes one aggregate over the rows of a group, a value at a time. `NULL` values are skipped, so `COUNT(Age)` counts the rows
+/// where `Age` is not `NULL` and the `SUM` of only `NULL`s is `NULL`. With `distinct` each value is only used the first time it is seen
+#[derive(Debug)]
+pub struct Accumulator {
+    aggregate: Aggregate,
+    seen: Option<Map<GroupKey, ()>>,
+    count: u64,
+    sum: Sum,
+    best: Option<DataItem>,
+}
+
+impl Accumulator {
+    /// # `new`
+    /// Creates an accumulator for the given aggregate that has seen no values yet
+    pub fn new(aggregate: Aggregate, distinct: bool) -> Accumulator {
+        Accumulator {
+            aggregate,
+            seen: distinct.then(Map::new),
+            count: 0,
+            sum: Sum::Empty,
+            best: None,
+        }
+    }
+
+    /// # `add`
+    /// Adds the value of a row. `Err()` if a sum gets too big for its type
+    pub fn add(&mut self, value: DataItem) -> Result<(), &'static str> {
+        if value.is_null() {
+            return Ok(());
+        }
+        if let Some(seen) = &mut self.seen {
+            if seen.insert(GroupKey(vec![value.clone()]), ()).is_err() {
+                return Ok(());
+            }
+        }
+
+        self.count += 1;
+        match self.aggregate {
+            Aggregate::Count => {}
+            Aggregate::Sum | Aggregate::Avg => self.sum = self.sum.add(&value)?,
+            Aggregate::Min | Aggregate::Max => {
+                let better = self.best.as_ref().is_none_or(|best| {
+                    let ordering = value.total_cmp(best);
+                    if self.aggregate == Aggregate::Min {
+                        ordering.is_lt()
+                    } else {
+                        ordering.is_gt()
+                    }
+                });
+                if better {
+                    self.best = Some(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// # `finish`
+    /// Returns the result of the aggregate over every value added. Only `COUNT` has a result other than `NULL` if no value was added
+    pub fn finish(self) -> Result<DataItem, &'static str> {
+        match self.aggregate {
+            Aggregate::Count => Ok(DataItem::ULong(self.count)),
+            Aggregate::Sum => self.sum.value(),
+            Aggregate::Avg if self.count == 0 => Ok(DataItem::Null),
+            Aggregate::Avg => Ok(DataItem::Double(self.sum.to_f64() / self.count as f64)),
+            Aggregate::Min | Aggregate::Max => Ok(self.best.unwrap_or(DataItem::Null)),
+        }
+    }
+}
+
+/// # `GroupKey`
+/// The values a group of rows share, used as the key of a `Map` of groups. Values are equal if `DataItem::total_cmp` says so,
+/// so every `NULL` falls in the same group and so does every NaN
+#[derive(Debug, Clone)]
+pub struct GroupKey(pub Vec<DataItem>);
+
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0.iter()).all(|(a, b)| a.total_cmp(b).is_eq())
+    }
+}
+
+impl Hashable for GroupKey {
+    /// # `hash_code`
+    /// Combines the hash codes of the values
+    fn hash_code(&self) -> usize {
+        self.0
+            .iter()
+            .fold(Wrapping(0usize), |hash, item| hash * Wrapping(31) + Wrapping(item.hash_code()))
+            .0
+    }
+
+    /// # `keyed_hash`
+    /// Combines the keyed hashes of the values
+    fn keyed_hash(&self, keys: HashKeys) -> usize {
+        self.0
+            .iter()
+            .fold(Wrapping(0usize), |hash, item| hash * Wrapping(31) + Wrapping(item.keyed_hash(keys)))
+            .0
+    }
+}
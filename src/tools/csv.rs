@@ -0,0 +1,92 @@
+/// # `NULL`
+/// How a missing value is written, both in .csv files and in queries. In double quotes it is the text `NULL` instead
+pub const NULL: &str = "NULL";
+
+/// # `parse_records`
+/// Splits the content of a .csv file into records of fields.
+/// Fields are separated by commas and records by new lines. A field in double quotes may hold commas, new lines and `""` for a double quote.
+/// Fields without quotes have their surrounding spaces removed. Empty lines are skipped. A field of only `NULL` without quotes is `None`.
+pub fn parse_records(content: &str) -> Result<Vec<Vec<Option<String>>>, &'static str> {
+    let mut records: Vec<Vec<Option<String>>> = Vec::new();
+    let mut record: Vec<Option<String>> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.trim().is_empty() && !quoted => {
+                // Opening quote, everything up to the closing quote is taken as is
+                field.clear();
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("Missing closing quote in .csv file"),
+                    }
+                }
+            }
+            ',' => {
+                record.push(finish_field(&mut field, &mut quoted));
+            }
+            '\n' => {
+                record.push(finish_field(&mut field, &mut quoted));
+                // A line with a single empty field is an empty line
+                if record.len() > 1 || record[0].as_deref() != Some("") {
+                    records.push(record);
+                }
+                record = Vec::new();
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            _ if quoted => {
+                // Only spaces may follow the closing quote
+                if !c.is_whitespace() {
+                    return Err("Unexpected character after closing quote in .csv file");
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+
+    record.push(finish_field(&mut field, &mut quoted));
+    if record.len() > 1 || record[0].as_deref() != Some("") {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// # `finish_field`
+/// Returns the field read so far, trimmed unless it was quoted, and prepares for the next field. `None` if the field is an unquoted `NULL`
+fn finish_field(field: &mut String, quoted: &mut bool) -> Option<String> {
+    let result = if *quoted {
+        Some(std::mem::take(field))
+    } else {
+        let trimmed = field.trim().to_string();
+        field.clear();
+        Some(trimmed).filter(|trimmed| trimmed != NULL)
+    };
+    *quoted = false;
+    result
+}
+
+/// # `quote`
+/// Returns the field the way it should be written in a .csv file. Fields that are empty, `NULL`, have surrounding spaces or hold commas,
+/// quotes or new lines are put in double quotes, others are written as they are.
+pub fn quote(field: &str) -> String {
+    let needs_quotes = field.is_empty()
+        || field == NULL
+        || field.trim() != field
+        || field.contains([',', '"', '\n', '\r']);
+
+    if needs_quotes {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
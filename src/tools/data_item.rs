@@ -1,11 +1,26 @@
+use super::{Date, DateTime, Decimal, Duration};
+use crate::hash_map::{ByteReader, Deserialize, HashKeys, Hashable, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
 /// # `DataType`
-/// An enum that represents a cell in the `Table`. 
+/// An enum that represents a cell in the `Table`.
 /// Able to represent a
 /// - Unsigned Integer (`u32`): `UInteger`
 /// - Signed Integer (`i32`): `Integer`
 /// - Floating Point Number (`f32`): `Float`
 /// - Boolean (`bool`): `Boolean`
 /// - Spaceless String ('String'): `Word`
+/// - Any String, spaces and commas included (`String`): `Text`
+/// - Signed 64-bit Integer (`i64`): `Long`
+/// - Unsigned 64-bit Integer (`u64`): `ULong`
+/// - 64-bit Floating Point Number (`f64`): `Double`
+/// - Calendar Date (`Date`): `Date`
+/// - Date and Time of Day (`DateTime`): `DateTime`
+/// - Amount of Time (`Duration`): `Duration`
+/// - Fixed-Point Number (`Decimal`): `Decimal`
+/// - Raw Bytes (`Vec<u8>`): `Bytes`
+/// - Missing value, only allowed in nullable columns: `Null`
 #[derive(Debug, Clone, PartialEq)]
 pub enum DataItem {
     UInteger(u32),
@@ -13,16 +28,335 @@ pub enum DataItem {
     Float(f32),
     Word(String),
     Boolean(bool),
+    Text(String),
+    Long(i64),
+    ULong(u64),
+    Double(f64),
+    Date(Date),
+    DateTime(DateTime),
+    Duration(Duration),
+    Decimal(Decimal),
+    Bytes(Vec<u8>),
+    Null,
+}
+
+/// # `parse_bytes`
+/// Parses bytes written in hexadecimal after `0x`, such as `0x00ff10`
+pub fn parse_bytes(text: &str) -> Result<Vec<u8>, &'static str> {
+    const ERROR: &str = "Error parsing value as Bytes";
+    let hex = text.trim().strip_prefix("0x").ok_or(ERROR)?;
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(ERROR);
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).map_err(|_| ERROR))
+        .collect()
 }
 
 impl DataItem {
-    pub fn to_string(&self) -> String {
+    /// # `is_null`
+    /// Returns `true` if this is a missing value
+    pub fn is_null(&self) -> bool {
+        matches!(self, DataItem::Null)
+    }
+
+    /// # `as_f64`
+    /// Returns the value of a number of any numeric type as an `f64`, `None` for anything else
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            DataItem::UInteger(e) => Some(*e as f64),
+            DataItem::Integer(e) => Some(*e as f64),
+            DataItem::Float(e) => Some(*e as f64),
+            DataItem::Long(e) => Some(*e as f64),
+            DataItem::ULong(e) => Some(*e as f64),
+            DataItem::Double(e) => Some(*e),
+            DataItem::Decimal(e) => Some(e.to_f64()),
+            _ => None,
+        }
+    }
+
+    /// # `compare`
+    /// Compares two values the way queries do. Numbers of different types are compared by value and `Word` and `Text` compare as strings.
+    /// `None` means the result is unknown: either side is `Null`, the types can not be compared or a float is NaN.
+    /// So `NULL` is neither equal, smaller nor bigger than anything, not even `NULL`
+    pub fn compare(&self, other: &DataItem) -> Option<Ordering> {
+        match (self, other) {
+            (DataItem::Null, _) | (_, DataItem::Null) => None,
+            (DataItem::UInteger(a), DataItem::UInteger(b)) => a.partial_cmp(b),
+            (DataItem::Integer(a), DataItem::Integer(b)) => a.partial_cmp(b),
+            (DataItem::Long(a), DataItem::Long(b)) => a.partial_cmp(b),
+            (DataItem::ULong(a), DataItem::ULong(b)) => a.partial_cmp(b),
+            (DataItem::Decimal(a), DataItem::Decimal(b)) => a.partial_cmp(b),
+            (DataItem::Word(a) | DataItem::Text(a), DataItem::Word(b) | DataItem::Text(b)) => a.partial_cmp(b),
+            (DataItem::Boolean(a), DataItem::Boolean(b)) => a.partial_cmp(b),
+            (DataItem::Date(a), DataItem::Date(b)) => a.partial_cmp(b),
+            (DataItem::DateTime(a), DataItem::DateTime(b)) => a.partial_cmp(b),
+            (DataItem::Duration(a), DataItem::Duration(b)) => a.partial_cmp(b),
+            (DataItem::Bytes(a), DataItem::Bytes(b)) => a.partial_cmp(b),
+            // Whole numbers and decimals are compared exactly, floats only with the exact value of the float
+            (a, b) => match (a.as_exact(), b.as_exact()) {
+                (Some((a, a_scale)), Some((b, b_scale))) => {
+                    let scale = a_scale.max(b_scale);
+                    let a = a * 10i128.pow((scale - a_scale) as u32);
+                    let b = b * 10i128.pow((scale - b_scale) as u32);
+                    Some(a.cmp(&b))
+                }
+                (Some((a, scale)), None) => compare_exact_float(a, scale, b.as_f64()?),
+                (None, Some((b, scale))) => compare_exact_float(b, scale, a.as_f64()?).map(Ordering::reverse),
+                (None, None) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+            },
+        }
+    }
+
+    /// # `as_exact`
+    /// Returns a whole number or decimal as the integer `mantissa` and `scale` of the number `mantissa / 10^scale`, `None` for floats
+    /// and anything else. Any such number fits an `i128` even after scaling it to the largest scale of a `Decimal`
+    fn as_exact(&self) -> Option<(i128, u8)> {
+        match self {
+            DataItem::UInteger(e) => Some((*e as i128, 0)),
+            DataItem::Integer(e) => Some((*e as i128, 0)),
+            DataItem::Long(e) => Some((*e as i128, 0)),
+            DataItem::ULong(e) => Some((*e as i128, 0)),
+            DataItem::Decimal(e) => Some((e.mantissa as i128, e.scale)),
+            _ => None,
+        }
+    }
+
+    /// # `equals`
+    /// Checks two values for equality the way queries do, `None` if that is unknown such as when either side is `Null`
+    pub fn equals(&self, other: &DataItem) -> Option<bool> {
+        self.compare(other).map(|ordering| ordering == Ordering::Equal)
+    }
+
+    /// # `total_cmp`
+    /// Orders any two values, used for sorting. Values that `compare` are ordered the same way, a NaN is bigger than every
+    /// other number and equal to another NaN. `Null` comes before everything else and other values that can not be compared
+    /// are ordered by their type: booleans, numbers, text, dates, dates with times, durations and then bytes
+    pub fn total_cmp(&self, other: &DataItem) -> Ordering {
+        self.compare(other).unwrap_or_else(|| match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => a.is_nan().cmp(&b.is_nan()),
+            _ => self.sort_rank().cmp(&other.sort_rank()),
+        })
+    }
+
+    /// # `sort_rank`
+    /// Returns the place of the type of the value among the others in `total_cmp`
+    fn sort_rank(&self) -> u8 {
+        match self {
+            DataItem::Null => 0,
+            DataItem::Boolean(_) => 1,
+            DataItem::UInteger(_)
+            | DataItem::Integer(_)
+            | DataItem::Float(_)
+            | DataItem::Long(_)
+            | DataItem::ULong(_)
+            | DataItem::Double(_)
+            | DataItem::Decimal(_) => 2,
+            DataItem::Word(_) | DataItem::Text(_) => 3,
+            DataItem::Date(_) => 4,
+            DataItem::DateTime(_) => 5,
+            DataItem::Duration(_) => 6,
+            DataItem::Bytes(_) => 7,
+        }
+    }
+
+    /// # `hash_text`
+    /// Returns the text hashed for this value. Numbers that `compare` as equal, such as `1` and `1.0`, give the same text
+    fn hash_text(&self) -> String {
+        match self.as_f64() {
+            // Also matches -0.0, which is equal to 0.0
+            Some(0.0) => 0f64.to_bits().to_string(),
+            Some(number) if number.is_nan() => f64::NAN.to_bits().to_string(),
+            Some(number) => number.to_bits().to_string(),
+            None => self.to_string(),
+        }
+    }
+}
+
+/// # `compare_exact_float`
+/// Compares the number `mantissa / 10^scale` with a float without rounding either of them, `None` if the float is NaN.
+/// The whole parts are compared first, then the fraction of the float, which is `m / 2^k`, by comparing
+/// `rest * 2^k` with `m * 10^scale` where `rest / 10^scale` is the fraction of the number
+fn compare_exact_float(mantissa: i128, scale: u8, float: f64) -> Option<Ordering> {
+    // Every exact number is smaller than 2^100, so such floats are bigger or smaller by their sign alone
+    if float.is_nan() {
+        return None;
+    }
+    if float.abs() >= 2f64.powi(100) {
+        return Some(if float > 0.0 { Ordering::Less } else { Ordering::Greater });
+    }
+
+    let whole = float.floor();
+    let fraction = float - whole;
+    let unit = 10i128.pow(scale as u32);
+    match mantissa.div_euclid(unit).cmp(&(whole as i128)) {
+        Ordering::Equal => {}
+        ordering => return Some(ordering),
+    }
+    let rest = mantissa.rem_euclid(unit) as u128;
+    if fraction == 0.0 || rest == 0 {
+        return Some(rest.cmp(&0).then(if fraction > 0.0 { Ordering::Less } else { Ordering::Equal }));
+    }
+
+    let bits = fraction.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as u32;
+    let (m, k) = match exponent {
+        0 => ((bits & ((1 << 52) - 1)) as u128, 1074),
+        _ => (((bits & ((1 << 52) - 1)) | (1 << 52)) as u128, 1075 - exponent),
+    };
+    // 10^scale is 2^scale * 5^scale, the powers of two are taken out of both sides so they fit a u128
+    let fives = m * 5u128.pow(scale as u32);
+    Some(if k >= scale as u32 {
+        let shift = k - scale as u32;
+        if shift >= 128 || rest.leading_zeros() < shift {
+            Ordering::Greater
+        } else {
+            (rest << shift).cmp(&fives)
+        }
+    } else {
+        rest.cmp(&(fives << (scale as u32 - k)))
+    })
+}
+
+impl Display for DataItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match &self {
-            DataItem::Boolean(e) => e.to_string(),
-            DataItem::UInteger(e) => e.to_string(),
-            DataItem::Integer(e) => e.to_string(),
-            DataItem::Float(e) => e.to_string(),
-            DataItem::Word(e) => e.to_string()
+            DataItem::Boolean(e) => e.fmt(f),
+            DataItem::UInteger(e) => e.fmt(f),
+            DataItem::Integer(e) => e.fmt(f),
+            DataItem::Float(e) => e.fmt(f),
+            DataItem::Word(e) => e.fmt(f),
+            DataItem::Text(e) => e.fmt(f),
+            DataItem::Long(e) => e.fmt(f),
+            DataItem::ULong(e) => e.fmt(f),
+            DataItem::Double(e) => e.fmt(f),
+            DataItem::Date(e) => e.fmt(f),
+            DataItem::DateTime(e) => e.fmt(f),
+            DataItem::Duration(e) => e.fmt(f),
+            DataItem::Decimal(e) => e.fmt(f),
+            DataItem::Bytes(e) => {
+                write!(f, "0x")?;
+                e.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            DataItem::Null => write!(f, "NULL"),
         }
     }
 }
+
+impl Hashable for DataItem {
+    /// # `hash_code`
+    /// Returns the hash code of the value, numbers are hashed by their value no matter their type
+    fn hash_code(&self) -> usize {
+        self.hash_text().hash_code()
+    }
+
+    /// # `keyed_hash`
+    /// Returns a SipHash of the value using the given keys
+    fn keyed_hash(&self, keys: HashKeys) -> usize {
+        self.hash_text().keyed_hash(keys)
+    }
+}
+
+impl Serialize for DataItem {
+    /// Stored as a single byte telling the variant followed by the value
+    fn serialize(&self, out: &mut Vec<u8>) {
+        match self {
+            DataItem::UInteger(e) => {
+                out.push(0);
+                e.serialize(out);
+            }
+            DataItem::Integer(e) => {
+                out.push(1);
+                e.serialize(out);
+            }
+            DataItem::Float(e) => {
+                out.push(2);
+                e.serialize(out);
+            }
+            DataItem::Word(e) => {
+                out.push(3);
+                e.serialize(out);
+            }
+            DataItem::Boolean(e) => {
+                out.push(4);
+                e.serialize(out);
+            }
+            DataItem::Text(e) => {
+                out.push(5);
+                e.serialize(out);
+            }
+            DataItem::Long(e) => {
+                out.push(6);
+                e.serialize(out);
+            }
+            DataItem::ULong(e) => {
+                out.push(7);
+                e.serialize(out);
+            }
+            DataItem::Double(e) => {
+                out.push(8);
+                e.serialize(out);
+            }
+            DataItem::Date(e) => {
+                out.push(9);
+                e.days.serialize(out);
+            }
+            DataItem::DateTime(e) => {
+                out.push(10);
+                e.seconds.serialize(out);
+            }
+            DataItem::Duration(e) => {
+                out.push(11);
+                e.seconds.serialize(out);
+            }
+            DataItem::Decimal(e) => {
+                out.push(12);
+                e.mantissa.serialize(out);
+                e.scale.serialize(out);
+            }
+            DataItem::Bytes(e) => {
+                out.push(13);
+                e.serialize(out);
+            }
+            DataItem::Null => out.push(14),
+        }
+    }
+}
+
+impl Deserialize for DataItem {
+    fn deserialize(reader: &mut ByteReader) -> Result<Self, &'static str> {
+        Ok(match reader.take(1)?[0] {
+            0 => DataItem::UInteger(u32::deserialize(reader)?),
+            1 => DataItem::Integer(i32::deserialize(reader)?),
+            2 => DataItem::Float(f32::deserialize(reader)?),
+            3 => DataItem::Word(String::deserialize(reader)?),
+            4 => DataItem::Boolean(bool::deserialize(reader)?),
+            5 => DataItem::Text(String::deserialize(reader)?),
+            6 => DataItem::Long(i64::deserialize(reader)?),
+            7 => DataItem::ULong(u64::deserialize(reader)?),
+            8 => DataItem::Double(f64::deserialize(reader)?),
+            9 => DataItem::Date(Date {
+                days: i32::deserialize(reader)?,
+            }),
+            10 => DataItem::DateTime(DateTime {
+                seconds: i64::deserialize(reader)?,
+            }),
+            11 => DataItem::Duration(Duration {
+                seconds: i64::deserialize(reader)?,
+            }),
+            12 => {
+                let mantissa = i64::deserialize(reader)?;
+                let scale = u8::deserialize(reader)?;
+                if scale > super::decimal::MAX_SCALE {
+                    return Err("Invalid Decimal scale");
+                }
+                DataItem::Decimal(Decimal { mantissa, scale })
+            }
+            13 => DataItem::Bytes(Vec::<u8>::deserialize(reader)?),
+            14 => DataItem::Null,
+            _ => return Err("Unknown DataItem type"),
+        })
+    }
+}
@@ -0,0 +1,495 @@
+use super::csv::quote;
+use super::table::DISK_EXTENSION;
+use super::{Alteration, Column, CompareOp, DataItem, Expr, GroupKey, Schema, Table};
+use crate::hash_map::Map;
+use std::fs::{read_dir, remove_file};
+use std::path::Path;
+
+/// # `JoinedRows`
+/// Rows made of a row of each table joined so far, with the names of those rows
+type JoinedRows = Vec<(Vec<String>, Vec<DataItem>)>;
+
+/// # `Database`
+/// A catalog of named tables, such as every table of a directory. Queries work on the table in use, chosen with `use_table`,
+/// while a `GET` with `JOIN` can read from any of them. New tables are created in the directory, or in the current directory
+/// if the database was not opened from one. `transaction` is `true` while a transaction spans every table
+#[derive(Debug, Default)]
+pub struct Database {
+    tables: Map<String, Table>,
+    current: Option<String>,
+    dir: Option<String>,
+    transaction: bool,
+}
+
+impl Database {
+    /// # `new`
+    /// Creates a database without any tables
+    pub fn new() -> Database {
+        Database::default()
+    }
+
+    /// # `open`
+    /// Opens every .csv and .hdb file in the directory as a table named after the file without its extension, such as
+    /// `students` for `students.csv`. No table is in use until one is chosen with `use_table`
+    pub fn open(dir: &str) -> Result<Database, String> {
+        let mut paths = read_dir(dir)
+            .map_err(|_| "Error while reading the directory")?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .map(|path| path.to_string_lossy().to_string())
+            .filter(|path| path.ends_with(".csv") || path.ends_with(DISK_EXTENSION))
+            .collect::<Vec<String>>();
+        paths.sort();
+
+        let mut database = Database::new();
+        for path in paths {
+            database.open_table(path, None)?;
+        }
+        database.current = None;
+        database.dir = Some(dir.to_string());
+        Ok(database)
+    }
+
+    /// # `open_table`
+    /// Opens the table at the path under the given name, or the name of its file without the extension, and puts it in use.
+    /// Returns the name of the table. `Err()` if the table can not be opened or a table with that name is already open
+    pub fn open_table(&mut self, path: String, name: Option<String>) -> Result<String, String> {
+        self.outside_transaction("opened")?;
+        let name = match name {
+            Some(name) => name,
+            None => Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .ok_or("Expected a path to a file")?,
+        };
+        if self.tables.contains_key(&name) {
+            return Err(format!("Table {} is already open", name));
+        }
+
+        let table = Table::new(path)?;
+        self.tables.insert(name.clone(), table)?;
+        self.current = Some(name.clone());
+        Ok(name)
+    }
+
+    /// # `use_table`
+    /// Puts the table with the given name in use. `Err()` if there is no such table
+    pub fn use_table(&mut self, name: &str) -> Result<(), String> {
+        if !self.tables.contains_key(&name.to_string()) {
+            return Err(format!("No such table: {}", name));
+        }
+        self.current = Some(name.to_string());
+        Ok(())
+    }
+
+    /// # `close`
+    /// Removes the table with the given name, or the table in use for `None`, from the database without saving it.
+    /// Returns the name of the table that was closed
+    pub fn close(&mut self, name: Option<&str>) -> Result<String, String> {
+        self.outside_transaction("closed")?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => self.current.clone().ok_or("No table is in use")?,
+        };
+        self.tables.remove(name.clone()).map_err(|_| format!("No such table: {}", name))?;
+        if self.current.as_ref() == Some(&name) {
+            self.current = None;
+        }
+        Ok(name)
+    }
+
+    /// # `create_table`
+    /// Creates an empty table with the columns written like in a header, such as `u:Age`, and saves it as `<name>.csv` in the
+    /// directory of the database. `Err()` if a table with that name is open or its file already exists
+    pub fn create_table(&mut self, name: &str, columns: &[String]) -> Result<(), String> {
+        self.outside_transaction("created")?;
+        if self.tables.contains_key(&name.to_string()) {
+            return Err(format!("Table {} is already open", name));
+        }
+        let schema = Schema::parse(columns)?;
+        let path = Path::new(self.dir.as_deref().unwrap_or(".")).join(format!("{}.csv", name));
+        let table = Table::create(path.to_string_lossy().to_string(), schema)?;
+        self.tables.insert(name.to_string(), table)?;
+        Ok(())
+    }
+
+    /// # `create_index`
+    /// Gives the column of the table with the given name a hash index, see `Table::create_index`
+    pub fn create_index(&mut self, name: &str, column: &str) -> Result<(), String> {
+        self.table(name).ok_or_else(|| format!("No such table: {}", name))?.create_index(column)
+    }
+
+    /// # `drop_table`
+    /// Removes the table from the database and deletes its file. `Err()` if a column of another open table references it
+    pub fn drop_table(&mut self, name: &str) -> Result<(), String> {
+        self.outside_transaction("dropped")?;
+        for (other, column, _) in self.referencing(name)? {
+            if other != name {
+                return Err(format!("Table {} is referenced by column {} of table {}", name, column, other));
+            }
+        }
+        let table = self.tables.remove(name.to_string()).map_err(|_| format!("No such table: {}", name))?;
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+        remove_file(table.path()).map_err(|_| format!("Error while deleting the file of table {}", name))
+    }
+
+    /// # `alter_table`
+    /// Changes the columns of the table with the given name
+    pub fn alter_table(&mut self, name: &str, alteration: Alteration) -> Result<(), String> {
+        // A new column referencing a table gives its default to every row, so the default must be a row of that table
+        if let Alteration::AddColumn(field) = &alteration {
+            let column = Column::parse(field)?;
+            if let (Some((referenced, _)), Some(default)) = (&column.reference, &column.default) {
+                if !default.is_null() && !self.has_row(referenced, &default.to_string())? {
+                    return Err(format!("Default {} of column {} is not a row of table {}", default, column.name, referenced));
+                }
+            }
+        }
+        let table = self.table(name).ok_or_else(|| format!("No such table: {}", name))?;
+        match alteration {
+            Alteration::AddColumn(field) => table.add_column(Column::parse(&field)?),
+            Alteration::DropColumn(column) => table.drop_column(&column),
+            Alteration::RenameColumn(column, new_name) => table.rename_column(&column, &new_name),
+            Alteration::ColumnType(column, kind) => table.alter_column_type(&column, kind),
+        }
+    }
+
+    /// # `begin`
+    /// Starts a transaction on every table, see `Table::begin`, so changes to any of them are undone together by `rollback`.
+    /// Until it ends tables can not be opened, closed, created or dropped
+    pub fn begin(&mut self) -> Result<(), String> {
+        if self.transaction {
+            return Err("A transaction is already open".to_string());
+        }
+        self.each_table(|table| Ok(table.begin()?))?;
+        self.transaction = true;
+        Ok(())
+    }
+
+    /// # `in_transaction`
+    /// Checks if a transaction is open
+    pub fn in_transaction(&self) -> bool {
+        self.transaction
+    }
+
+    /// # `commit`
+    /// Ends the transaction of every table and keeps the changes
+    pub fn commit(&mut self) -> Result<(), String> {
+        self.inside_transaction()?;
+        self.each_table(|table| Ok(table.commit()?))?;
+        self.transaction = false;
+        Ok(())
+    }
+
+    /// # `rollback`
+    /// Undoes the changes to every table since `begin`, or since the savepoint, see `Table::rollback`
+    pub fn rollback(&mut self, savepoint: Option<&str>) -> Result<(), String> {
+        self.inside_transaction()?;
+        self.each_table(|table| table.rollback(savepoint))?;
+        if savepoint.is_none() {
+            self.transaction = false;
+        }
+        Ok(())
+    }
+
+    /// # `savepoint`
+    /// Makes a savepoint in every table, see `Table::savepoint`
+    pub fn savepoint(&mut self, name: &str) -> Result<(), String> {
+        self.inside_transaction()?;
+        self.each_table(|table| Ok(table.savepoint(name)?))
+    }
+
+    /// # `release`
+    /// Forgets a savepoint in every table, see `Table::release`
+    pub fn release(&mut self, name: &str) -> Result<(), String> {
+        self.inside_transaction()?;
+        self.each_table(|table| table.release(name))
+    }
+
+    /// # `each_table`
+    /// Runs the function on every table in the order they were opened. `Err()` as soon as it fails, which for a savepoint that
+    /// does not exist happens on the first table since every table has the same savepoints
+    fn each_table(&mut self, mut function: impl FnMut(&mut Table) -> Result<(), String>) -> Result<(), String> {
+        for name in self.names() {
+            if let Some(table) = self.table(&name) {
+                function(table)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// # `inside_transaction`
+    /// `Err()` if no transaction is open
+    fn inside_transaction(&self) -> Result<(), String> {
+        match self.transaction {
+            true => Ok(()),
+            false => Err("No transaction is open, start one with BEGIN".to_string()),
+        }
+    }
+
+    /// # `outside_transaction`
+    /// `Err()` saying tables can not be opened, closed or the like while a transaction is open
+    fn outside_transaction(&self, what: &str) -> Result<(), String> {
+        match self.transaction {
+            true => Err(format!("Tables can not be {} inside a transaction, COMMIT or ROLLBACK first", what)),
+            false => Ok(()),
+        }
+    }
+
+    /// # `names`
+    /// Returns the names of the tables in the order they were opened
+    pub fn names(&mut self) -> Vec<String> {
+        self.tables.keys().clone()
+    }
+
+    /// # `table`
+    /// Returns the table with the given name, `None` if there is no such table
+    pub fn table(&mut self, name: &str) -> Option<&mut Table> {
+        self.tables.get_mut(&name.to_string())
+    }
+
+    /// # `current`
+    /// Returns the table in use. `Err()` if no table is in use
+    pub fn current(&mut self) -> Result<&mut Table, String> {
+        let name = self.current.clone().ok_or("No table is in use, choose one with USE <table>")?;
+        self.tables.get_mut(&name).ok_or_else(|| format!("No such table: {}", name))
+    }
+
+    /// # `change`
+    /// Runs a change such as an `INSERT` or `SET` on the table in use, then looks up every value it wrote to a column referencing
+    /// another table by row name in that table. The change is undone if such a value is not a row of the table it references,
+    /// or if that table is not open. Tables without such columns are changed directly
+    pub fn change<T>(&mut self, change: impl FnOnce(&mut Table) -> Result<T, String>) -> Result<T, String> {
+        let table = self.current()?;
+        if table.schema().columns().iter().all(|column| column.reference.is_none()) {
+            return change(table);
+        }
+
+        // Outside of a transaction the change gets one of its own, so it can be undone as well
+        let own_transaction = !table.in_transaction();
+        if own_transaction {
+            table.begin()?;
+        }
+        let start = table.changes().unwrap_or_default();
+        table.collect_references();
+        let result = change(table);
+        let written = self.current()?.take_references();
+        let result = result.and_then(|changed| self.check_references(&written).map(|_| changed));
+
+        let table = self.current()?;
+        match (&result, own_transaction) {
+            (Ok(_), true) => table.commit()?,
+            (Err(_), true) => table.rollback(None)?,
+            (Err(_), false) => table.undo(start)?,
+            (Ok(_), false) => {}
+        }
+        result
+    }
+
+    /// # `check_references`
+    /// `Err()` if a value written to a column of the table in use, next to the index of the column, is not a row of the table
+    /// the column references
+    fn check_references(&mut self, written: &[(usize, DataItem)]) -> Result<(), String> {
+        let columns = self.current()?.schema().columns().to_vec();
+        for (idx, value) in written {
+            let Some((table, _)) = &columns[*idx].reference else { continue };
+            if !self.has_row(table, &value.to_string())? {
+                return Err(format!("Value {} of column {} is not a row of table {}", value, columns[*idx].name, table));
+            }
+        }
+        Ok(())
+    }
+
+    /// # `delete`
+    /// Deletes the rows of the table in use like `Table::delete`, along with the rows of open tables that reference them through
+    /// a column with `CASCADE`, and the rows referencing those in turn. `Err()` without deleting anything if a deleted row is
+    /// referenced through a column without `CASCADE`. Returns how many rows were deleted from each table, the table in use first
+    pub fn delete(&mut self, rows: &[String], filter: Option<&Expr>) -> Result<Vec<(String, usize)>, String> {
+        let name = self.current.clone().ok_or("No table is in use, choose one with USE <table>")?;
+        let found = self
+            .current()?
+            .select(None, rows, filter, &[])?
+            .into_iter()
+            .filter_map(|(row_name, row)| row.map(|_| row_name))
+            .collect::<Vec<String>>();
+
+        // Which rows of which tables go is found before any is deleted, so a RESTRICT leaves every table as it was
+        let mut planned: Vec<(String, Vec<String>)> = vec![(name.clone(), found.clone())];
+        let mut pending: Vec<(String, Vec<String>)> = vec![(name, found)];
+        while let Some((parent, deleted)) = pending.pop() {
+            let mut deleted_names: Map<String, ()> = Map::new();
+            for row_name in deleted {
+                deleted_names.insert(row_name, ())?;
+            }
+            for (child, column, cascade) in self.referencing(&parent)? {
+                let table = self.table(&child).ok_or_else(|| format!("No such table: {}", child))?;
+                let idx = table.schema().index_of(&column).ok_or_else(|| format!("No such column: {}", column))?;
+                let mut cascaded: Vec<String> = Vec::new();
+                for (row_name, row) in table.select(None, &["*".to_string()], None, &[])? {
+                    let Some(row) = row else { continue };
+                    if row[idx].is_null() || !deleted_names.contains_key(&row[idx].to_string()) {
+                        continue;
+                    }
+                    if planned.iter().any(|(table, names)| *table == child && names.contains(&row_name)) {
+                        continue;
+                    }
+                    if !cascade {
+                        return Err(format!(
+                            "Row {} of table {} references row {} of table {} through column {}",
+                            row_name, child, row[idx], parent, column
+                        ));
+                    }
+                    cascaded.push(row_name);
+                }
+                if !cascaded.is_empty() {
+                    match planned.iter_mut().find(|(table, _)| *table == child) {
+                        Some((_, names)) => names.extend(cascaded.iter().cloned()),
+                        None => planned.push((child.clone(), cascaded.clone())),
+                    }
+                    pending.push((child, cascaded));
+                }
+            }
+        }
+
+        let mut counts: Vec<(String, usize)> = Vec::with_capacity(planned.len());
+        for (name, rows) in planned {
+            let table = self.table(&name).ok_or_else(|| format!("No such table: {}", name))?;
+            let count = if rows.is_empty() { 0 } else { table.delete(&rows, None)?.len() };
+            counts.push((name, count));
+        }
+        Ok(counts)
+    }
+
+    /// # `referencing`
+    /// Returns every column of an open table that references the table with the given name, as the name of its table, its own
+    /// name and whether it has `CASCADE`
+    fn referencing(&mut self, name: &str) -> Result<Vec<(String, String, bool)>, String> {
+        let mut columns: Vec<(String, String, bool)> = Vec::new();
+        for table_name in self.names() {
+            let table = self.table(&table_name).ok_or_else(|| format!("No such table: {}", table_name))?;
+            for column in table.schema().columns() {
+                if let Some((referenced, cascade)) = &column.reference {
+                    if referenced == name {
+                        columns.push((table_name.clone(), column.name.clone(), *cascade));
+                    }
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    /// # `has_row`
+    /// Checks if the table with the given name has a row with the given name. `Err()` if the table is not open
+    fn has_row(&mut self, name: &str, row_name: &str) -> Result<bool, String> {
+        let table = self.table(name).ok_or_else(|| format!("Referenced table {} is not open", name))?;
+        Ok(table.contains_row(row_name)?)
+    }
+
+    /// # `join`
+    /// Joins the tables of a `GET` into a table kept in memory with a row for every combination of rows for which the conditions
+    /// are true. Its columns are named `<alias>.<column>`, see `Schema::qualified`, and its rows are named after the rows they
+    /// are made of, separated by commas. Each table is joined with a hash join on the columns its condition requires to be equal
+    /// to columns of the tables before it, so matching rows are found without comparing every pair of rows
+    pub fn join(&mut self, joins: &[(String, Option<String>, Option<Expr>)]) -> Result<Table, String> {
+        let mut schemas: Vec<(String, Schema)> = Vec::with_capacity(joins.len());
+        let mut rows: JoinedRows = vec![(Vec::new(), Vec::new())];
+
+        for (name, alias, on) in joins {
+            let alias = alias.clone().unwrap_or_else(|| name.clone());
+            if schemas.iter().any(|(other, _)| *other == alias) {
+                return Err(format!("Table {} is joined twice, give it an alias", alias));
+            }
+            let table = self.table(name).ok_or_else(|| format!("No such table: {}", name))?;
+            let right = table
+                .select(None, &["*".to_string()], None, &[])?
+                .into_iter()
+                .filter_map(|(row_name, row)| row.map(|row| (row_name, row)))
+                .collect::<Vec<(String, Vec<DataItem>)>>();
+
+            let left_len = schemas.iter().map(|(_, schema)| schema.len()).sum::<usize>();
+            schemas.push((alias, table.schema().clone()));
+            let on = match on {
+                Some(on) if on.has_aggregate() => return Err("Aggregates can not be used in ON".to_string()),
+                Some(on) => Some(on.clone().bind_condition(&Schema::qualified(&schemas)?)?),
+                None => None,
+            };
+            rows = Database::hash_join(rows, right, on.as_ref(), left_len)?;
+        }
+
+        let rows = rows
+            .into_iter()
+            .map(|(names, row)| {
+                let name = names.iter().map(|name| quote(name)).collect::<Vec<String>>().join(",");
+                (name, row)
+            })
+            .collect();
+        Ok(Table::from_rows(Schema::qualified(&schemas)?, rows)?)
+    }
+
+    /// # `hash_join`
+    /// Joins rows of the tables joined so far with the rows of another table for which `on` is true, every pair if there is no `on`.
+    /// The rows of the other table are put in a `Map` by the values of the columns that must be equal, then each row
+    /// looks up the rows with its values. Rows with a `NULL` in such a column match nothing
+    fn hash_join(
+        left: JoinedRows,
+        right: Vec<(String, Vec<DataItem>)>,
+        on: Option<&Expr>,
+        left_len: usize,
+    ) -> Result<JoinedRows, &'static str> {
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        if let Some(on) = on {
+            Database::equal_columns(on, left_len, &mut pairs);
+        }
+
+        let mut buckets: Map<GroupKey, Vec<usize>> = Map::new();
+        for (idx, (_, row)) in right.iter().enumerate() {
+            let key = GroupKey(pairs.iter().map(|(_, column)| row[*column].clone()).collect());
+            if key.0.iter().any(DataItem::is_null) {
+                continue;
+            }
+            match buckets.get_mut(&key) {
+                Some(bucket) => bucket.push(idx),
+                None => buckets.insert(key, vec![idx])?,
+            }
+        }
+
+        let mut joined: JoinedRows = Vec::new();
+        for (names, row) in left {
+            let key = GroupKey(pairs.iter().map(|(column, _)| row[*column].clone()).collect());
+            let Some(bucket) = buckets.get_ref(&key) else { continue };
+            for idx in bucket {
+                let (right_name, right_row) = &right[*idx];
+                let mut combined = row.clone();
+                combined.extend(right_row.iter().cloned());
+                if on.is_some_and(|on| on.truth(&combined) != Some(true)) {
+                    continue;
+                }
+                let mut combined_names = names.clone();
+                combined_names.push(right_name.clone());
+                joined.push((combined_names, combined));
+            }
+        }
+        Ok(joined)
+    }
+
+    /// # `equal_columns`
+    /// Finds the pairs of columns that a bound condition requires to be equal, a column of the tables joined so far and a column of
+    /// the table that is joined, which is given from its own first column. Only comparisons joined by `AND` are used, the rest of
+    /// the condition is checked for every pair of rows with equal values
+    fn equal_columns(on: &Expr, left_len: usize, pairs: &mut Vec<(usize, usize)>) {
+        match on {
+            Expr::And(left, right) => {
+                Database::equal_columns(left, left_len, pairs);
+                Database::equal_columns(right, left_len, pairs);
+            }
+            Expr::Compare(left, CompareOp::Equal, right) => match (&**left, &**right) {
+                (Expr::Column(a), Expr::Column(b)) if *a < left_len && *b >= left_len => pairs.push((*a, *b - left_len)),
+                (Expr::Column(a), Expr::Column(b)) if *b < left_len && *a >= left_len => pairs.push((*b, *a - left_len)),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+
+/// # `MAX_SCALE`
+/// The most digits a `Decimal` can have after its decimal point
+pub const MAX_SCALE: u8 = 18;
+
+/// # `Decimal`
+/// A fixed-point number, stored as an integer `mantissa` and the amount of digits after the decimal point `scale`.
+/// `12.50` has the mantissa `1250` and the scale `2`. Unlike floats, decimal fractions such as prices are stored exactly.
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    pub mantissa: i64,
+    pub scale: u8,
+}
+
+impl Decimal {
+    /// # `parse`
+    /// Parses a decimal number such as `-12.50`. The scale is the amount of digits written after the decimal point
+    pub fn parse(text: &str) -> Result<Decimal, &'static str> {
+        const ERROR: &str = "Error parsing value as Decimal";
+        let text = text.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.bytes().chain(fraction.bytes()).all(|byte| byte.is_ascii_digit())
+            || fraction.len() > MAX_SCALE as usize
+        {
+            return Err(ERROR);
+        }
+
+        let mut mantissa: i64 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|mantissa| mantissa.checked_add((digit - b'0') as i64))
+                .ok_or(ERROR)?;
+        }
+
+        Ok(Decimal {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: fraction.len() as u8,
+        })
+    }
+
+    /// # `scaled`
+    /// Returns the mantissa as if the number had the given, larger or equal, scale. `i128` since that may not fit an `i64`
+    fn scaled(&self, scale: u8) -> i128 {
+        self.mantissa as i128 * 10i128.pow((scale - self.scale) as u32)
+    }
+
+    /// # `to_f64`
+    /// Returns the closest `f64` to this decimal. Dividing is only exact enough while the mantissa fits the 53 bits of an `f64`,
+    /// otherwise the written number is parsed, which rounds correctly. Equal numbers of any type so give the same `f64`
+    pub fn to_f64(&self) -> f64 {
+        if self.mantissa.unsigned_abs() <= 1 << 53 {
+            return self.mantissa as f64 / 10f64.powi(self.scale as i32);
+        }
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl PartialEq for Decimal {
+    /// Decimals are equal if they are the same number, no matter their scale. `1.50` equals `1.5`
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.scaled(scale).cmp(&other.scaled(scale))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let unit = 10u64.pow(self.scale as u32);
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            digits / unit,
+            digits % unit,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl Debug for Decimal {
+    /// Printed the way it is written, such as `12.50`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,450 @@
+use super::{Aggregate, ArithOp, ColumnType, DataItem, Function, Pattern, Schema};
+use std::cmp::Ordering;
+
+/// # `CompareOp`
+/// The operators that compare two values: `=`, `!=` (or `<>`), `<`, `<=`, `>` and `>=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl CompareOp {
+    /// # `holds`
+    /// Checks if the operator is true for two values that compare with the given `Ordering`
+    pub fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Equal => ordering.is_eq(),
+            CompareOp::NotEqual => ordering.is_ne(),
+            CompareOp::Less => ordering.is_lt(),
+            CompareOp::LessEqual => ordering.is_le(),
+            CompareOp::Greater => ordering.is_gt(),
+            CompareOp::GreaterEqual => ordering.is_ge(),
+        }
+    }
+}
+
+/// # `Expr`
+/// A condition of a `WHERE` clause, such as `Age > 15 AND Program = CDATE`, or a value computed from a row such as `Age * 2 + 1`.
+/// A parsed expression holds `Name`s which `bind` turns into `Column`s of a `Schema`, or into `Value`s if there is no such column.
+/// An `Identifier`, a name in double quotes, must be a column and a `Literal`, text in single quotes, is always a value.
+/// Conditions follow the three-valued logic of SQL, comparing with `NULL` is neither true nor false but unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Name(String),
+    Identifier(String),
+    Literal(String),
+    Column(usize),
+    Value(DataItem),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// `<expr> IS [NOT] NULL`, `true` if negated
+    IsNull(Box<Expr>, bool),
+    /// `<expr> [NOT] IN (<expr>, ...)`, `true` if negated
+    In(Box<Expr>, Vec<Expr>, bool),
+    /// `<expr> [NOT] BETWEEN <low> AND <high>`, `true` if negated
+    Between(Box<Expr>, Box<Expr>, Box<Expr>, bool),
+    /// `<expr> [NOT] LIKE <pattern>`, `true` if negated
+    Like(Box<Expr>, Pattern, bool),
+    /// `COUNT(*)` or `<aggregate>([DISTINCT] <expr>)`, `None` is `*` and `true` if distinct
+    Aggregate(Aggregate, Option<Box<Expr>>, bool),
+    /// `<expr> <op> <expr>` such as `Age * 2`
+    Arithmetic(Box<Expr>, ArithOp, Box<Expr>),
+    /// `CAST(<expr> AS <type>)`
+    Cast(Box<Expr>, ColumnType),
+    /// `<function>(<expr>, ...)` such as `UPPER(Program)`
+    Call(Function, Vec<Expr>),
+}
+
+/// # `literal`
+/// Returns the value of a bare value that is not compared with a column: a number if it is one, otherwise text
+fn literal(text: &str) -> DataItem {
+    if let Ok(number) = text.parse::<i64>() {
+        DataItem::Long(number)
+    } else if let Some(number) = text
+        .parse::<f64>()
+        .ok()
+        .filter(|_| text.bytes().any(|byte| byte.is_ascii_digit()))
+    {
+        DataItem::Double(number)
+    } else if text.contains(char::is_whitespace) {
+        DataItem::Text(text.to_string())
+    } else {
+        DataItem::Word(text.to_string())
+    }
+}
+
+impl Expr {
+    /// # `bind`
+    /// Resolves the names of the expression to the columns of the schema and checks its types.
+    /// Names that are not columns become values of the type of what they are compared with, so in `Age > 15` the 15 becomes a `UInteger`
+    /// if `Age` is one. `Err()` is returned if such a value can not be parsed or if two values of types that can not be compared meet
+    pub fn bind(self, schema: &Schema) -> Result<Expr, String> {
+        match self {
+            Expr::And(left, right) => Ok(Expr::And(
+                Box::new(left.bind_condition(schema)?),
+                Box::new(right.bind_condition(schema)?),
+            )),
+            Expr::Or(left, right) => Ok(Expr::Or(
+                Box::new(left.bind_condition(schema)?),
+                Box::new(right.bind_condition(schema)?),
+            )),
+            Expr::Not(expr) => Ok(Expr::Not(Box::new(expr.bind_condition(schema)?))),
+            Expr::Compare(left, op, right) => {
+                let left = left.bind_operand(schema)?;
+                let right = right.bind_operand(schema)?;
+                let (left_kind, right_kind) = (left.kind(schema), right.kind(schema));
+                let left = left.coerce(right_kind)?;
+                let right = right.coerce(left_kind)?;
+                Expr::check_comparable(&left, &right, schema)?;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+            }
+            Expr::IsNull(expr, negated) => Ok(Expr::IsNull(Box::new(expr.bind_operand(schema)?.coerce(None)?), negated)),
+            Expr::In(expr, list, negated) => {
+                let expr = expr.bind_operand(schema)?;
+                let kind = expr.kind(schema);
+                let expr = expr.coerce(None)?;
+                let list = list
+                    .into_iter()
+                    .map(|item| {
+                        let item = item.bind_operand(schema)?.coerce(kind)?;
+                        Expr::check_comparable(&expr, &item, schema)?;
+                        Ok(item)
+                    })
+                    .collect::<Result<Vec<Expr>, String>>()?;
+                Ok(Expr::In(Box::new(expr), list, negated))
+            }
+            Expr::Between(expr, low, high, negated) => {
+                let expr = expr.bind_operand(schema)?;
+                let kind = expr.kind(schema);
+                let expr = expr.coerce(None)?;
+                let low = low.bind_operand(schema)?.coerce(kind)?;
+                let high = high.bind_operand(schema)?.coerce(kind)?;
+                Expr::check_comparable(&expr, &low, schema)?;
+                Expr::check_comparable(&expr, &high, schema)?;
+                Ok(Expr::Between(Box::new(expr), Box::new(low), Box::new(high), negated))
+            }
+            Expr::Like(expr, pattern, negated) => {
+                let expr = expr.bind_operand(schema)?;
+                match expr.kind(schema) {
+                    Some(kind) if kind.is_text() => {}
+                    Some(kind) => return Err(format!("LIKE only works on Word and Text values, not {:?}", kind)),
+                    None => {}
+                }
+                Ok(Expr::Like(Box::new(expr.coerce(Some(ColumnType::Text))?), pattern, negated))
+            }
+            Expr::Aggregate(aggregate, arg, distinct) => {
+                let arg = match arg {
+                    Some(arg) if arg.has_aggregate() => return Err("Aggregates can not be nested".to_string()),
+                    Some(arg) => Some(Box::new(arg.bind_operand(schema)?.coerce(None)?)),
+                    None => None,
+                };
+                aggregate.check(arg.as_ref().and_then(|arg| arg.kind(schema)))?;
+                Ok(Expr::Aggregate(aggregate, arg, distinct))
+            }
+            Expr::Arithmetic(left, op, right) => {
+                let left = left.bind_operand(schema)?;
+                let right = right.bind_operand(schema)?;
+                let (left_kind, right_kind) = (left.kind(schema), right.kind(schema));
+                let left = left.coerce(right_kind)?;
+                let right = right.coerce(left_kind)?;
+                for kind in [left.kind(schema), right.kind(schema)].into_iter().flatten() {
+                    if !kind.is_numeric() {
+                        return Err(format!("Can not use {} on {:?} values", op.symbol(), kind));
+                    }
+                }
+                Ok(Expr::Arithmetic(Box::new(left), op, Box::new(right)))
+            }
+            Expr::Cast(expr, kind) => Ok(Expr::Cast(Box::new(expr.bind_operand(schema)?.coerce(None)?), kind)),
+            Expr::Call(function, args) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| arg.bind_operand(schema))
+                    .collect::<Result<Vec<Expr>, String>>()?;
+                // Values given to COALESCE become the type of the first column among them, as in COALESCE(Program, none)
+                let hint = match function {
+                    Function::Coalesce => args.iter().find_map(|arg| arg.kind(schema)),
+                    _ => None,
+                };
+                let args = args
+                    .into_iter()
+                    .map(|arg| arg.coerce(hint))
+                    .collect::<Result<Vec<Expr>, String>>()?;
+                function.check(&args.iter().map(|arg| arg.kind(schema)).collect::<Vec<Option<ColumnType>>>())?;
+                Ok(Expr::Call(function, args))
+            }
+            expr => expr.bind_operand(schema)?.coerce(None),
+        }
+    }
+
+    /// # `bind_condition`
+    /// Binds an expression that must be true or false, such as a whole `WHERE` clause or the sides of an `AND`
+    pub fn bind_condition(self, schema: &Schema) -> Result<Expr, String> {
+        let expr = self.bind(schema)?;
+        match expr.kind(schema) {
+            Some(ColumnType::Boolean) | None => Ok(expr),
+            Some(kind) => Err(format!("Expected a condition but found a {:?} value", kind)),
+        }
+    }
+
+    /// # `bind_column`
+    /// Binds an expression requested as a column of a result. There a bare name must be a column, since a misspelled column
+    /// would otherwise give its name as a value. Text values can still be requested in single quotes
+    pub fn bind_column(self, schema: &Schema) -> Result<Expr, String> {
+        match self {
+            Expr::Name(name) if schema.index_of(&name).is_none() && literal(&name).as_f64().is_none() => {
+                Err(format!("No such column: {}", name))
+            }
+            expr => expr.bind(schema),
+        }
+    }
+
+    /// # `bind_value`
+    /// Binds an expression whose value is stored in a column of the given type, as in `SET Age = Age + 1`. Names that are not
+    /// columns and text in single quotes become values of that type. `Err()` if the value can never be stored in such a column
+    pub fn bind_value(self, schema: &Schema, kind: ColumnType) -> Result<Expr, String> {
+        let expr = self.bind_operand(schema)?.coerce(Some(kind))?;
+        match expr.kind(schema) {
+            Some(found) if !found.comparable(&kind) => Err(format!("Can not store a {:?} value in a {:?} column", found, kind)),
+            _ => Ok(expr),
+        }
+    }
+
+    /// # `bind_operand`
+    /// Turns a name into its column if there is one. Other names and literals are left for `coerce`
+    fn bind_operand(self, schema: &Schema) -> Result<Expr, String> {
+        match self {
+            Expr::Name(name) => Ok(schema.index_of(&name).map_or(Expr::Name(name), Expr::Column)),
+            Expr::Identifier(name) => schema
+                .index_of(&name)
+                .map(Expr::Column)
+                .ok_or_else(|| format!("No such column: {}", name)),
+            Expr::Literal(_) | Expr::Column(_) | Expr::Value(_) => Ok(self),
+            expr => expr.bind(schema),
+        }
+    }
+
+    /// # `coerce`
+    /// Turns a name that is not a column into a value of the given type, or of the type it looks like if there is none.
+    /// `Err()` if it is not a value of the given type
+    fn coerce(self, kind: Option<ColumnType>) -> Result<Expr, String> {
+        match self {
+            Expr::Name(text) => Ok(Expr::Value(match kind {
+                // A number that does not fit the type, such as 1.5 or -1 for a UInteger, is still compared by value
+                Some(kind) => kind.parse(&text).or_else(|error| match literal(&text) {
+                    number @ (DataItem::Long(_) | DataItem::Double(_)) if kind.is_numeric() => Ok(number),
+                    _ => Err(error),
+                })?,
+                None => literal(&text),
+            })),
+            Expr::Literal(text) => Ok(Expr::Value(match kind {
+                Some(kind) => kind.parse(&text)?,
+                None => DataItem::Text(text),
+            })),
+            expr => Ok(expr),
+        }
+    }
+
+    /// # `kind`
+    /// Returns the type of the value of the expression, `None` if it is unknown such as for `NULL`
+    fn kind(&self, schema: &Schema) -> Option<ColumnType> {
+        match self {
+            Expr::Name(_) | Expr::Identifier(_) | Expr::Literal(_) => None,
+            Expr::Column(idx) => Some(schema.columns()[*idx].kind),
+            Expr::Value(value) => ColumnType::of(value),
+            Expr::Aggregate(aggregate, arg, _) => aggregate.kind(arg.as_ref().and_then(|arg| arg.kind(schema))),
+            Expr::Arithmetic(left, op, right) => op.kind(left.kind(schema), right.kind(schema)),
+            Expr::Cast(_, kind) => Some(*kind),
+            Expr::Call(function, args) => function.kind(&args.iter().map(|arg| arg.kind(schema)).collect::<Vec<Option<ColumnType>>>()),
+            _ => Some(ColumnType::Boolean),
+        }
+    }
+
+    /// # `check_comparable`
+    /// `Err()` if the values of the two expressions can never be compared, such as a `Date` and a `Boolean`
+    fn check_comparable(left: &Expr, right: &Expr, schema: &Schema) -> Result<(), String> {
+        match (left.kind(schema), right.kind(schema)) {
+            (Some(left), Some(right)) if !left.comparable(&right) => {
+                Err(format!("Can not compare {:?} with {:?}", left, right))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// # `has_aggregate`
+    /// Checks if an aggregate is used anywhere in the expression
+    pub fn has_aggregate(&self) -> bool {
+        match self {
+            Expr::Aggregate(..) => true,
+            Expr::Compare(left, _, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right)
+            | Expr::Arithmetic(left, _, right) => left.has_aggregate() || right.has_aggregate(),
+            Expr::Not(expr) | Expr::IsNull(expr, _) | Expr::Like(expr, _, _) | Expr::Cast(expr, _) => expr.has_aggregate(),
+            Expr::In(expr, list, _) => expr.has_aggregate() || list.iter().any(Expr::has_aggregate),
+            Expr::Call(_, args) => args.iter().any(Expr::has_aggregate),
+            Expr::Between(expr, low, high, _) => expr.has_aggregate() || low.has_aggregate() || high.has_aggregate(),
+            Expr::Name(_) | Expr::Identifier(_) | Expr::Literal(_) | Expr::Column(_) | Expr::Value(_) => false,
+        }
+    }
+
+    /// # `uses_column`
+    /// Checks if a bound expression reads the column with the given index anywhere
+    pub fn uses_column(&self, idx: usize) -> bool {
+        match self {
+            Expr::Column(column) => *column == idx,
+            Expr::Compare(left, _, right)
+            | Expr::And(left, right)
+            | Expr::Or(left, right)
+            | Expr::Arithmetic(left, _, right) => left.uses_column(idx) || right.uses_column(idx),
+            Expr::Not(expr) | Expr::IsNull(expr, _) | Expr::Like(expr, _, _) | Expr::Cast(expr, _) => expr.uses_column(idx),
+            Expr::In(expr, list, _) => expr.uses_column(idx) || list.iter().any(|expr| expr.uses_column(idx)),
+            Expr::Call(_, args) => args.iter().any(|expr| expr.uses_column(idx)),
+            Expr::Aggregate(_, argument, _) => argument.as_ref().is_some_and(|expr| expr.uses_column(idx)),
+            Expr::Between(expr, low, high, _) => expr.uses_column(idx) || low.uses_column(idx) || high.uses_column(idx),
+            Expr::Name(_) | Expr::Identifier(_) | Expr::Literal(_) | Expr::Value(_) => false,
+        }
+    }
+
+    /// # `split_aggregates`
+    /// Moves the aggregates out of a bound expression of a grouped query. Each different aggregate is pushed to `aggregates` once
+    /// as its function, argument and whether it is distinct. It is replaced by the column after the columns of the schema plus
+    /// its index, which is where the result of the aggregate is put in the row of a group.
+    /// `Err()` if a column is used outside of an aggregate while it is not one of the `grouped` columns
+    pub fn split_aggregates(
+        self,
+        schema: &Schema,
+        grouped: &[usize],
+        aggregates: &mut Vec<(Aggregate, Option<Expr>, bool)>,
+    ) -> Result<Expr, String> {
+        if let Expr::Aggregate(aggregate, arg, distinct) = self {
+            let found = (aggregate, arg.map(|arg| *arg), distinct);
+            let idx = match aggregates.iter().position(|other| *other == found) {
+                Some(idx) => idx,
+                None => {
+                    aggregates.push(found);
+                    aggregates.len() - 1
+                }
+            };
+            return Ok(Expr::Column(schema.len() + idx));
+        }
+
+        let mut split = |expr: Box<Expr>| expr.split_aggregates(schema, grouped, aggregates).map(Box::new);
+        Ok(match self {
+            Expr::Column(idx) if !grouped.contains(&idx) => {
+                return Err(format!(
+                    "Column {} must be in GROUP BY or inside an aggregate",
+                    schema.columns()[idx].name
+                ))
+            }
+            Expr::Compare(left, op, right) => Expr::Compare(split(left)?, op, split(right)?),
+            Expr::And(left, right) => Expr::And(split(left)?, split(right)?),
+            Expr::Or(left, right) => Expr::Or(split(left)?, split(right)?),
+            Expr::Not(expr) => Expr::Not(split(expr)?),
+            Expr::IsNull(expr, negated) => Expr::IsNull(split(expr)?, negated),
+            Expr::In(expr, list, negated) => Expr::In(
+                split(expr)?,
+                list.into_iter()
+                    .map(|item| split(Box::new(item)).map(|item| *item))
+                    .collect::<Result<Vec<Expr>, String>>()?,
+                negated,
+            ),
+            Expr::Between(expr, low, high, negated) => Expr::Between(split(expr)?, split(low)?, split(high)?, negated),
+            Expr::Like(expr, pattern, negated) => Expr::Like(split(expr)?, pattern, negated),
+            Expr::Arithmetic(left, op, right) => Expr::Arithmetic(split(left)?, op, split(right)?),
+            Expr::Cast(expr, kind) => Expr::Cast(split(expr)?, kind),
+            Expr::Call(function, args) => Expr::Call(
+                function,
+                args.into_iter()
+                    .map(|arg| split(Box::new(arg)).map(|arg| *arg))
+                    .collect::<Result<Vec<Expr>, String>>()?,
+            ),
+            expr => expr,
+        })
+    }
+
+    /// # `value`
+    /// Returns the value of the expression for the given row. Conditions give a `Boolean`, or `Null` if they are unknown
+    pub fn value(&self, row: &[DataItem]) -> DataItem {
+        match self {
+            Expr::Name(name) => literal(name),
+            Expr::Identifier(_) | Expr::Aggregate(..) => DataItem::Null,
+            Expr::Literal(text) => DataItem::Text(text.clone()),
+            Expr::Column(idx) => row.get(*idx).cloned().unwrap_or(DataItem::Null),
+            Expr::Value(value) => value.clone(),
+            Expr::Arithmetic(left, op, right) => op.apply(&left.value(row), &right.value(row)),
+            Expr::Cast(expr, kind) => kind.cast(expr.value(row)),
+            Expr::Call(function, args) => function.call(args.iter().map(|arg| arg.value(row)).collect()),
+            condition => condition.truth(row).map_or(DataItem::Null, DataItem::Boolean),
+        }
+    }
+
+    /// # `truth`
+    /// Evaluates the expression as a condition for the given row. `None` means unknown, which a `WHERE` treats as false
+    pub fn truth(&self, row: &[DataItem]) -> Option<bool> {
+        match self {
+            Expr::Name(_)
+            | Expr::Identifier(_)
+            | Expr::Literal(_)
+            | Expr::Column(_)
+            | Expr::Value(_)
+            | Expr::Aggregate(..)
+            | Expr::Arithmetic(..)
+            | Expr::Cast(..)
+            | Expr::Call(..) => match self.value(row) {
+                DataItem::Boolean(value) => Some(value),
+                _ => None,
+            },
+            Expr::Compare(left, op, right) => left.value(row).compare(&right.value(row)).map(|ordering| op.holds(ordering)),
+            Expr::And(left, right) => match (left.truth(row), right.truth(row)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Expr::Or(left, right) => match (left.truth(row), right.truth(row)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Expr::Not(expr) => expr.truth(row).map(|value| !value),
+            Expr::IsNull(expr, negated) => Some(expr.value(row).is_null() != *negated),
+            Expr::In(expr, list, negated) => {
+                let value = expr.value(row);
+                // Unknown if no item is equal but some comparison was unknown
+                let mut result = Some(false);
+                for item in list {
+                    match value.equals(&item.value(row)) {
+                        Some(true) => {
+                            result = Some(true);
+                            break;
+                        }
+                        Some(false) => {}
+                        None => result = None,
+                    }
+                }
+                result.map(|found| found != *negated)
+            }
+            Expr::Between(expr, low, high, negated) => {
+                let value = expr.value(row);
+                let above = value.compare(&low.value(row)).map(Ordering::is_ge);
+                let below = value.compare(&high.value(row)).map(Ordering::is_le);
+                let between = match (above, below) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                };
+                between.map(|between| between != *negated)
+            }
+            Expr::Like(expr, pattern, negated) => match expr.value(row) {
+                DataItem::Word(text) | DataItem::Text(text) => Some(pattern.is_match(&text) != *negated),
+                _ => None,
+            },
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use super::decimal::MAX_SCALE;
+use super::{ColumnType, DataItem, Decimal};
+
+/// # `ArithOp`
+/// The arithmetic operators `+`, `-`, `*`, `/` and `%`. Whole numbers are computed exactly and give a `Long`, or a `ULong` if
+/// the result only fits that, `/` of whole numbers drops the fraction. A decimal with a decimal or a whole number gives a
+/// `Decimal` except for `/`. Anything else, or a result that fits neither, gives a `Double`. Dividing by zero gives `NULL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+/// # `whole`
+/// Returns the value of a whole number of any type, `None` for anything else
+fn whole(value: &DataItem) -> Option<i128> {
+    match value {
+        DataItem::UInteger(e) => Some(*e as i128),
+        DataItem::Integer(e) => Some(*e as i128),
+        DataItem::Long(e) => Some(*e as i128),
+        DataItem::ULong(e) => Some(*e as i128),
+        _ => None,
+    }
+}
+
+/// # `whole_value`
+/// Returns a whole number as a `Long` if it fits, otherwise as a `ULong` or, if it fits neither, as a `Double`
+fn whole_value(number: i128) -> DataItem {
+    if let Ok(number) = i64::try_from(number) {
+        DataItem::Long(number)
+    } else if let Ok(number) = u64::try_from(number) {
+        DataItem::ULong(number)
+    } else {
+        DataItem::Double(number as f64)
+    }
+}
+
+/// # `decimal`
+/// Returns the mantissa and scale of a decimal or a whole number, `None` for anything else
+fn decimal(value: &DataItem) -> Option<(i128, u8)> {
+    match value {
+        DataItem::Decimal(e) => Some((e.mantissa as i128, e.scale)),
+        value => whole(value).map(|number| (number, 0)),
+    }
+}
+
+/// # `decimal_value`
+/// Returns a mantissa and scale as a `Decimal` if it fits one, `None` otherwise
+fn decimal_value(mantissa: i128, scale: u8) -> Option<DataItem> {
+    if scale > MAX_SCALE {
+        return None;
+    }
+    Some(DataItem::Decimal(Decimal {
+        mantissa: i64::try_from(mantissa).ok()?,
+        scale,
+    }))
+}
+
+/// # `rescale`
+/// Returns the mantissa of a number with the given scale as if it had the new, larger or equal, scale
+fn rescale(mantissa: i128, scale: u8, new_scale: u8) -> Option<i128> {
+    10i128.checked_pow((new_scale - scale) as u32)?.checked_mul(mantissa)
+}
+
+/// # `round_mantissa`
+/// Drops the given amount of digits from the end of a mantissa, rounding half away from zero
+fn round_mantissa(mantissa: i128, digits: u32) -> i128 {
+    let Some(divisor) = 10i128.checked_pow(digits) else {
+        return 0;
+    };
+    let (quotient, remainder) = (mantissa / divisor, mantissa % divisor);
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        quotient + mantissa.signum()
+    } else {
+        quotient
+    }
+}
+
+impl ArithOp {
+    /// # `symbol`
+    /// Returns the symbol of the operator in queries
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ArithOp::Add => "+",
+            ArithOp::Subtract => "-",
+            ArithOp::Multiply => "*",
+            ArithOp::Divide => "/",
+            ArithOp::Remainder => "%",
+        }
+    }
+
+    /// # `kind`
+    /// Returns the type of the result for values of the given types, `None` if either is unknown
+    pub fn kind(&self, left: Option<ColumnType>, right: Option<ColumnType>) -> Option<ColumnType> {
+        let (left, right) = (left?, right?);
+        let any = |kind: ColumnType| left == kind || right == kind;
+        Some(if any(ColumnType::Float) || any(ColumnType::Double) {
+            ColumnType::Double
+        } else if any(ColumnType::Decimal) {
+            if *self == ArithOp::Divide {
+                ColumnType::Double
+            } else {
+                ColumnType::Decimal
+            }
+        } else {
+            ColumnType::Long
+        })
+    }
+
+    /// # `apply`
+    /// Computes the operator for two values. `NULL` if either is `NULL` or not a number
+    pub fn apply(&self, left: &DataItem, right: &DataItem) -> DataItem {
+        if let (Some(a), Some(b)) = (whole(left), whole(right)) {
+            let result = match self {
+                ArithOp::Add => a.checked_add(b),
+                ArithOp::Subtract => a.checked_sub(b),
+                ArithOp::Multiply => a.checked_mul(b),
+                ArithOp::Divide | ArithOp::Remainder if b == 0 => return DataItem::Null,
+                ArithOp::Divide => Some(a / b),
+                ArithOp::Remainder => Some(a % b),
+            };
+            if let Some(result) = result {
+                return whole_value(result);
+            }
+        } else if let (Some((a, a_scale)), Some((b, b_scale))) = (decimal(left), decimal(right)) {
+            let scale = a_scale.max(b_scale);
+            let result = match self {
+                ArithOp::Multiply => a.checked_mul(b).and_then(|result| decimal_value(result, a_scale + b_scale)),
+                ArithOp::Divide => None,
+                ArithOp::Remainder if b == 0 => return DataItem::Null,
+                op => rescale(a, a_scale, scale)
+                    .zip(rescale(b, b_scale, scale))
+                    .and_then(|(a, b)| match op {
+                        ArithOp::Add => a.checked_add(b),
+                        ArithOp::Subtract => a.checked_sub(b),
+                        _ => Some(a % b),
+                    })
+                    .and_then(|result| decimal_value(result, scale)),
+            };
+            if let Some(result) = result {
+                return result;
+            }
+        }
+
+        let (Some(a), Some(b)) = (left.as_f64(), right.as_f64()) else {
+            return DataItem::Null;
+        };
+        match self {
+            ArithOp::Add => DataItem::Double(a + b),
+            ArithOp::Subtract => DataItem::Double(a - b),
+            ArithOp::Multiply => DataItem::Double(a * b),
+            ArithOp::Divide | ArithOp::Remainder if b == 0.0 => DataItem::Null,
+            ArithOp::Divide => DataItem::Double(a / b),
+            ArithOp::Remainder => DataItem::Double(a % b),
+        }
+    }
+}
+
+/// # `Function`
+/// The functions that compute a value from the values of a single row
+/// - `UPPER(x)`, `LOWER(x)`: Text in upper or lower case
+/// - `LENGTH(x)`: Amount of characters of text or amount of bytes
+/// - `ABS(x)`: A number without its sign
+/// - `ROUND(x[, digits])`: A float or decimal rounded half away from zero to the amount of digits after the point, 0 by default.
+///   A negative amount rounds to tens, hundreds and so on
+/// - `COALESCE(x, ...)`: The first value that is not `NULL`
+/// - `CONCAT(x, ...)`: The values written after each other as `Text`, skipping `NULL`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Upper,
+    Lower,
+    Length,
+    Abs,
+    Round,
+    Coalesce,
+    Concat,
+}
+
+impl Function {
+    /// # `from_name`
+    /// Returns the function with the given name in any case, `None` if there is no such function
+    pub fn from_name(name: &str) -> Option<Function> {
+        [
+            Function::Upper,
+            Function::Lower,
+            Function::Length,
+            Function::Abs,
+            Function::Round,
+            Function::Coalesce,
+            Function::Concat,
+        ]
+        .into_iter()
+        .find(|function| function.name().eq_ignore_ascii_case(name))
+    }
+
+    /// # `name`
+    /// Returns the name of the function as it is written in queries
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Upper => "UPPER",
+            Function::Lower => "LOWER",
+            Function::Length => "LENGTH",
+            Function::Abs => "ABS",
+            Function::Round => "ROUND",
+            Function::Coalesce => "COALESCE",
+            Function::Concat => "CONCAT",
+        }
+    }
+
+    /// # `check`
+    /// `Err()` if the function does not take the given amount of values or values of their types. Unknown types are allowed
+    pub fn check(&self, kinds: &[Option<ColumnType>]) -> Result<(), String> {
+        let (amount, most) = match self {
+            Function::Round => ("1 or 2 values", 2),
+            Function::Coalesce | Function::Concat => ("at least 1 value", usize::MAX),
+            _ => ("1 value", 1),
+        };
+        if kinds.is_empty() || kinds.len() > most {
+            return Err(format!("{} takes {} but {} were given", self.name(), amount, kinds.len()));
+        }
+
+        let expected: fn(&ColumnType) -> bool = match self {
+            Function::Upper | Function::Lower => ColumnType::is_text,
+            Function::Length => |kind| kind.is_text() || *kind == ColumnType::Bytes,
+            Function::Abs | Function::Round => ColumnType::is_numeric,
+            Function::Coalesce | Function::Concat => return Ok(()),
+        };
+        match kinds[0] {
+            Some(kind) if !expected(&kind) => Err(format!("{} does not work on {:?} values", self.name(), kind)),
+            _ => match kinds.get(1) {
+                Some(Some(kind)) if !kind.is_whole() => {
+                    Err(format!("The digits of ROUND must be a whole number, not {:?}", kind))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// # `kind`
+    /// Returns the type of the result for values of the given types, `None` if it is unknown
+    pub fn kind(&self, kinds: &[Option<ColumnType>]) -> Option<ColumnType> {
+        match self {
+            Function::Length => Some(ColumnType::ULong),
+            Function::Concat => Some(ColumnType::Text),
+            Function::Coalesce => kinds.iter().flatten().next().copied(),
+            _ => kinds.first().copied().flatten(),
+        }
+    }
+
+    /// # `call`
+    /// Computes the function for the given values. `NULL` if a value is `NULL` or of a type the function does not work on,
+    /// except for `COALESCE` and `CONCAT`
+    pub fn call(&self, mut values: Vec<DataItem>) -> DataItem {
+        if *self == Function::Coalesce {
+            return values.into_iter().find(|value| !value.is_null()).unwrap_or(DataItem::Null);
+        }
+        if *self == Function::Concat {
+            let text = values.iter().filter(|value| !value.is_null()).map(DataItem::to_string).collect::<String>();
+            return DataItem::Text(text);
+        }
+
+        let digits = values.get(1).map(whole);
+        let value = values.swap_remove(0);
+        match (self, value) {
+            (Function::Upper, DataItem::Word(e)) => DataItem::Word(e.to_uppercase()),
+            (Function::Upper, DataItem::Text(e)) => DataItem::Text(e.to_uppercase()),
+            (Function::Lower, DataItem::Word(e)) => DataItem::Word(e.to_lowercase()),
+            (Function::Lower, DataItem::Text(e)) => DataItem::Text(e.to_lowercase()),
+            (Function::Length, DataItem::Word(e) | DataItem::Text(e)) => DataItem::ULong(e.chars().count() as u64),
+            (Function::Length, DataItem::Bytes(e)) => DataItem::ULong(e.len() as u64),
+            (Function::Abs, DataItem::Float(e)) => DataItem::Float(e.abs()),
+            (Function::Abs, DataItem::Double(e)) => DataItem::Double(e.abs()),
+            (Function::Abs, DataItem::Integer(e)) => e.checked_abs().map_or(DataItem::Long((e as i64).abs()), DataItem::Integer),
+            (Function::Abs, DataItem::Long(e)) => e.checked_abs().map_or(DataItem::ULong(e.unsigned_abs()), DataItem::Long),
+            (Function::Abs, DataItem::Decimal(e)) => match e.mantissa.checked_abs() {
+                Some(mantissa) => DataItem::Decimal(Decimal { mantissa, scale: e.scale }),
+                None => DataItem::Double(e.to_f64().abs()),
+            },
+            (Function::Abs, value @ (DataItem::UInteger(_) | DataItem::ULong(_))) => value,
+            (Function::Round, value) => match digits {
+                Some(None) => DataItem::Null,
+                Some(Some(digits)) => Function::round(value, digits),
+                None => Function::round(value, 0),
+            },
+            _ => DataItem::Null,
+        }
+    }
+
+    /// # `round`
+    /// Rounds a number half away from zero to the given amount of digits after the point
+    fn round(value: DataItem, digits: i128) -> DataItem {
+        let digits = digits.clamp(-40, 40) as i32;
+        match value {
+            DataItem::Float(e) => DataItem::Float(((e as f64 * 10f64.powi(digits)).round() / 10f64.powi(digits)) as f32),
+            DataItem::Double(e) => DataItem::Double((e * 10f64.powi(digits)).round() / 10f64.powi(digits)),
+            value => {
+                let Some((mantissa, scale)) = decimal(&value) else {
+                    return DataItem::Null;
+                };
+                if digits >= scale as i32 {
+                    return value;
+                }
+                let rounded = round_mantissa(mantissa, (scale as i32 - digits) as u32);
+                // Rounding to tens and more leaves zeros before the point
+                let rounded = rescale(rounded, 0, (-digits).max(0) as u8).unwrap_or(0);
+                match value {
+                    DataItem::Decimal(_) => decimal_value(rounded, digits.max(0) as u8).unwrap_or(DataItem::Null),
+                    _ => whole_value(rounded),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/// # `TokenKind`
+/// What a `Token` of a query is
+/// - `Ident`: A bare word such as a keyword, a column or row name or a value like `CDATE` or `true`
+/// - `QuotedIdent`: A column or row name in double quotes, which may hold spaces, commas and keywords. `""` inside is a single `"`
+/// - `String`: A text value in single quotes. `''` or `\'` inside is a single `'`, and `\\`, `\n`, `\t` and `\r` are escapes
+/// - `Number`: A bare value starting with a digit, or a `.` and a digit. Dates, times and durations such as
+///   `2001-02-03` and `1:30:00` are numbers as well, the type of the column decides what they are. A `-` only stays in a number
+///   written like a date, and a `+` only after the `e` of an exponent such as `1e+5`. Signs in front of numbers are symbols
+/// - `Symbol`: One of `(`, `)`, `,`, `:`, `*`, `=`, `!=`, `<>`, `<`, `<=`, `>`, `>=`, `+`, `-`, `/` and `%`. `<>` is given as `!=`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    QuotedIdent(String),
+    String(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+/// # `Token`
+/// A piece of a query together with the byte position in the query where it starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: usize,
+}
+
+/// # `ParseError`
+/// An error while reading a query, with the byte position in the query where it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub pos: usize,
+}
+
+impl ParseError {
+    /// # `new`
+    /// Creates an error at the given position
+    pub fn new(message: impl Into<String>, pos: usize) -> ParseError {
+        ParseError {
+            message: message.into(),
+            pos,
+        }
+    }
+
+    /// # `describe`
+    /// Returns the message followed by the query with a `^` under the place of the error, such as
+    /// ```text
+    /// Expected OF at position 8
+    /// GET Age Program OF *
+    ///         ^
+    /// ```
+    pub fn describe(&self, query: &str) -> String {
+        let column = query.get(..self.pos).map_or(self.pos, |before| before.chars().count());
+        format!("{} at position {}\n{}\n{}^", self.message, self.pos, query, " ".repeat(column))
+    }
+}
+
+/// # `SYMBOLS`
+/// Characters that end a bare word, since they are symbols or start quoted text
+const SYMBOLS: &str = "\"'(),:*=<>!+-/%";
+
+/// # `tokenize`
+/// Splits a query into `Token`s, skipping whitespace and comments.
+/// Comments run from `--` to the end of the line, or from `/*` to `*/`
+pub fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '-' if text[pos + 1..].starts_with('-') => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '/' if text[pos + 1..].starts_with('*') => {
+                let end = text[pos + 2..]
+                    .find("*/")
+                    .ok_or_else(|| ParseError::new("Missing */ to close the comment", pos))?;
+                while chars.next_if(|(idx, _)| *idx < pos + 2 + end + 2).is_some() {}
+                continue;
+            }
+            '+' => TokenKind::Symbol("+"),
+            '-' => TokenKind::Symbol("-"),
+            '/' => TokenKind::Symbol("/"),
+            '%' => TokenKind::Symbol("%"),
+            '\'' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.next_if(|(_, c)| *c == '\'').is_some() => string.push('\''),
+                        Some((_, '\'')) => break,
+                        Some((escape, '\\')) => string.push(match chars.next() {
+                            Some((_, 'n')) => '\n',
+                            Some((_, 't')) => '\t',
+                            Some((_, 'r')) => '\r',
+                            Some((_, c @ ('\\' | '\'' | '"'))) => c,
+                            _ => return Err(ParseError::new("Unknown escape in text", escape)),
+                        }),
+                        Some((_, c)) => string.push(c),
+                        None => return Err(ParseError::new("Missing closing quote", pos)),
+                    }
+                }
+                TokenKind::String(string)
+            }
+            '"' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) if chars.next_if(|(_, c)| *c == '"').is_some() => quoted.push('"'),
+                        Some((_, '"')) => break,
+                        Some((_, c)) => quoted.push(c),
+                        None => return Err(ParseError::new("Missing closing quote", pos)),
+                    }
+                }
+                TokenKind::QuotedIdent(quoted)
+            }
+            '(' => TokenKind::Symbol("("),
+            ')' => TokenKind::Symbol(")"),
+            ',' => TokenKind::Symbol(","),
+            ':' => TokenKind::Symbol(":"),
+            '*' => TokenKind::Symbol("*"),
+            '=' => TokenKind::Symbol("="),
+            '!' if chars.next_if(|(_, c)| *c == '=').is_some() => TokenKind::Symbol("!="),
+            '<' if chars.next_if(|(_, c)| *c == '=').is_some() => TokenKind::Symbol("<="),
+            '<' if chars.next_if(|(_, c)| *c == '>').is_some() => TokenKind::Symbol("!="),
+            '<' => TokenKind::Symbol("<"),
+            '>' if chars.next_if(|(_, c)| *c == '=').is_some() => TokenKind::Symbol(">="),
+            '>' => TokenKind::Symbol(">"),
+            '!' => return Err(ParseError::new("Unexpected !", pos)),
+            c => {
+                let next_is_digit = text[pos + c.len_utf8()..].starts_with(|c: char| c.is_ascii_digit());
+                let number = c.is_ascii_digit() || c == '.' && next_is_digit;
+
+                // Numbers may hold a : such as in durations, in other words it separates the row name of an INSERT.
+                // A - followed by a digit stays in a date, and a + followed by a digit in an exponent
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(idx, c)| {
+                    let digit_next = text[idx + c.len_utf8()..].starts_with(|c: char| c.is_ascii_digit());
+                    let in_number = number
+                        && match c {
+                            ':' => true,
+                            '-' => digit_next && word.chars().all(|c| c.is_ascii_digit() || c == '-'),
+                            '+' => digit_next && word.ends_with(['e', 'E']),
+                            _ => false,
+                        };
+                    !c.is_whitespace() && (!SYMBOLS.contains(*c) || in_number)
+                }) {
+                    word.push(c);
+                }
+
+                if number {
+                    TokenKind::Number(word)
+                } else {
+                    TokenKind::Ident(word)
+                }
+            }
+        };
+        tokens.push(Token { kind, pos });
+    }
+
+    Ok(tokens)
+}
@@ -1,5 +1,30 @@
+mod aggregate;
+mod csv;
 mod data_item;
-mod table;
+mod database;
+mod decimal;
+mod expr;
+mod function;
+mod lexer;
+mod parser;
+mod pattern;
 mod query_handler;
+mod schema;
+mod table;
+mod temporal;
 
-pub use {data_item::DataItem, table::Table, query_handler::*};
+pub use {
+    aggregate::{Accumulator, Aggregate, GroupKey},
+    data_item::{parse_bytes, DataItem},
+    database::Database,
+    decimal::Decimal,
+    expr::{CompareOp, Expr},
+    function::{ArithOp, Function},
+    lexer::{tokenize, ParseError, Token, TokenKind},
+    parser::{Alteration, InsertValues, Parser, Statement},
+    pattern::Pattern,
+    query_handler::*,
+    schema::{Column, ColumnType, Schema},
+    table::Table,
+    temporal::{Date, DateTime, Duration},
+};
@@ -0,0 +1,895 @@
+use super::csv::parse_records;
+use super::lexer::{tokenize, ParseError, Token, TokenKind};
+use super::{Aggregate, ArithOp, ColumnType, CompareOp, DataItem, Expr, Function, Pattern};
+
+/// # `Columns`
+/// The columns of a `GET`, each an expression and its alias if it was given one
+type Columns = Vec<(Expr, Option<String>)>;
+
+/// # `Joins`
+/// The tables of a `GET` with `JOIN`, each its name, its alias if it was given one and the condition it is joined on.
+/// The first table has no condition
+type Joins = Vec<(String, Option<String>, Option<Expr>)>;
+
+/// # `Statement`
+/// A parsed query. Values are kept as text until the type of their column is known, `None` is `NULL`
+/// - `Get`: `GET <columns> OF <rows> [WHERE <condition>] [GROUP BY <column>, ...] [HAVING <condition>]
+///   [ORDER BY <column> [ASC|DESC], ...] [LIMIT <n>] [OFFSET <m>]`, `None` columns or rows is `*`. The columns may be expressions
+///   such as `Age * 2` or `COUNT(*)` with an optional alias after `AS`, as may what is ordered by. Each expression of `order` is
+///   `true` if it is sorted descending. `joins` is empty for the rows of the table in use, otherwise the columns come from the tables
+///   of `OF <table> [<alias>] JOIN <table> [<alias>] ON <condition> ...` and `rows` is `None`
+/// - `Set`: `SET <column>=<value>, ... OF <rows> [WHERE <condition>]` or `SET <column>=<value>, ... WHERE <condition>`, where each
+///   value is an expression such as `Age + 1`. `None` rows is `*`, which is also what is changed when only `WHERE` is given
+/// - `Insert`: `INSERT <row> : <value>, ...` or `INSERT <row> (<column>=<value>, ...)`
+/// - `Delete`: `DELETE <rows> [WHERE <condition>]` or `DELETE WHERE <condition>`, `None` rows is `*`
+/// - `Save`: `SAVE` or `SAVE AS <path>`
+/// - `Use`: `USE <table>`
+/// - `Open`: `OPEN <path> [AS <table>]`
+/// - `Close`: `CLOSE [<table>]`, `None` is the table in use
+/// - `CreateTable`: `CREATE TABLE <table> (<column>, ...)` where each column is written like in a header, such as `u:Age=18|min=0`
+/// - `CreateIndex`: `CREATE INDEX ON <table>(<column>)`
+/// - `DropTable`: `DROP TABLE <table>`
+/// - `AlterTable`: `ALTER TABLE <table>` followed by an `Alteration`
+/// - `Begin`: `BEGIN`
+/// - `Commit`: `COMMIT`
+/// - `Rollback`: `ROLLBACK [TO [SAVEPOINT] <savepoint>]`, `None` undoes the whole transaction
+/// - `Savepoint`: `SAVEPOINT <name>`
+/// - `Release`: `RELEASE [SAVEPOINT] <savepoint>`
+/// - `Abort`: `ABORT`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Get {
+        columns: Option<Columns>,
+        rows: Option<Vec<String>>,
+        joins: Joins,
+        filter: Option<Expr>,
+        group_by: Vec<String>,
+        having: Option<Expr>,
+        order: Vec<(Expr, bool)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    },
+    Set {
+        changes: Vec<(String, Expr)>,
+        rows: Option<Vec<String>>,
+        filter: Option<Expr>,
+    },
+    Insert {
+        row: String,
+        values: InsertValues,
+    },
+    Delete {
+        rows: Option<Vec<String>>,
+        filter: Option<Expr>,
+    },
+    Save {
+        path: Option<String>,
+    },
+    Use {
+        table: String,
+    },
+    Open {
+        path: String,
+        table: Option<String>,
+    },
+    Close {
+        table: Option<String>,
+    },
+    CreateTable {
+        table: String,
+        columns: Vec<String>,
+    },
+    CreateIndex {
+        table: String,
+        column: String,
+    },
+    DropTable {
+        table: String,
+    },
+    AlterTable {
+        table: String,
+        alteration: Alteration,
+    },
+    Begin,
+    Commit,
+    Rollback {
+        savepoint: Option<String>,
+    },
+    Savepoint {
+        name: String,
+    },
+    Release {
+        savepoint: String,
+    },
+    Abort,
+}
+
+/// # `Alteration`
+/// The change of an `ALTER TABLE`, the word `COLUMN` after the first keyword may be left out
+/// - `AddColumn`: `ADD COLUMN <column>` where the column is written like in a header, such as `u?:Height` or `w:Code=none`
+/// - `DropColumn`: `DROP COLUMN <name>`
+/// - `RenameColumn`: `RENAME COLUMN <name> TO <new name>`
+/// - `ColumnType`: `ALTER COLUMN <name> TYPE <type>` where the type is its code or name, such as `t` or `Text`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alteration {
+    AddColumn(String),
+    DropColumn(String),
+    RenameColumn(String, String),
+    ColumnType(String, ColumnType),
+}
+
+/// # `InsertValues`
+/// The values of an `INSERT`, either every column in order or some columns by name
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertValues {
+    Ordered(Vec<Option<String>>),
+    Named(Vec<(String, Option<String>)>),
+}
+
+/// # `KEYWORDS`
+/// Words with a meaning in queries, in any case. To be used as names they must be put in double quotes, as values in single quotes
+const KEYWORDS: [&str; 43] = [
+    "GET", "OF", "WHERE", "SET", "INSERT", "DELETE", "SAVE", "AS", "ABORT", "AND", "OR", "NOT", "IS", "NULL", "IN",
+    "BETWEEN", "LIKE", "TRUE", "FALSE", "ORDER", "BY", "ASC", "DESC", "LIMIT", "OFFSET", "GROUP", "HAVING", "DISTINCT",
+    "USE", "OPEN", "CLOSE", "JOIN", "ON", "CREATE", "DROP", "ALTER", "TABLE", "INDEX", "BEGIN", "COMMIT", "ROLLBACK",
+    "SAVEPOINT", "RELEASE",
+];
+
+/// # `Parser`
+/// Turns the `Token`s of a query into a `Statement` or an `Expr` by recursive descent
+pub struct Parser {
+    text: String,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Statement {
+    /// # `parse`
+    /// Parses a whole query
+    pub fn parse(text: &str) -> Result<Statement, ParseError> {
+        let mut parser = Parser::new(text)?;
+        let statement = parser.statement()?;
+        parser.finish()?;
+        Ok(statement)
+    }
+}
+
+impl Expr {
+    /// # `parse`
+    /// Parses the text of a condition such as `Age > 15 AND Program = CDATE`
+    pub fn parse(text: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser::new(text)?;
+        let expr = parser.expression()?;
+        parser.finish()?;
+        Ok(expr)
+    }
+}
+
+impl Parser {
+    /// # `new`
+    /// Splits the text into tokens, ready to be parsed
+    pub fn new(text: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            text: text.to_string(),
+            tokens: tokenize(text)?,
+            pos: 0,
+        })
+    }
+
+    /// # `finish`
+    /// `Err()` if there are tokens left
+    pub fn finish(&self) -> Result<(), ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => Err(ParseError::new("Unexpected text after the end of the query", token.pos)),
+            None => Ok(()),
+        }
+    }
+
+    /// # `position`
+    /// Returns the position of the current token, or the end of the query if there is none
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.text.len(), |token| token.pos)
+    }
+
+    /// # `error`
+    /// Returns an error at the current token
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError::new(message, self.position()))
+    }
+
+    /// # `peek_keyword`
+    /// Checks if the token `ahead` tokens from the current one is the given keyword, in any case
+    fn peek_keyword(&self, ahead: usize, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos + ahead), Some(Token { kind: TokenKind::Ident(word), .. }) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// # `keyword`
+    /// Moves past the current token if it is the given keyword, returns whether it was
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(0, keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// # `symbol`
+    /// Moves past the current token if it is the given symbol, returns whether it was
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token { kind: TokenKind::Symbol(found), .. }) if *found == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// # `expect`
+    /// Moves past the current token, `Err()` if it is not the given keyword or symbol
+    fn expect(&mut self, expected: &str, context: &str) -> Result<(), ParseError> {
+        if self.keyword(expected) || self.symbol(expected) {
+            Ok(())
+        } else {
+            self.error(format!("Expected {} {}", expected, context))
+        }
+    }
+
+    /// # `is_keyword`
+    /// Checks if the word is a keyword, in any case
+    fn is_keyword(word: &str) -> bool {
+        KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+    }
+
+    /// # `name`
+    /// Parses the name of a column or row, which is a bare word that is not a keyword, a number or a name in double quotes
+    fn name(&mut self, what: &str) -> Result<String, ParseError> {
+        match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::Ident(word)) if Parser::is_keyword(word) => {
+                self.error(format!("Expected {} but found the keyword {}, put it in double quotes to use it as a name", what, word))
+            }
+            Some(TokenKind::Ident(word) | TokenKind::QuotedIdent(word) | TokenKind::Number(word)) => {
+                let word = word.clone();
+                self.pos += 1;
+                Ok(word)
+            }
+            Some(TokenKind::String(_)) => self.error(format!("Expected {}, names are put in double quotes", what)),
+            _ => self.error(format!("Expected {}", what)),
+        }
+    }
+
+    /// # `names`
+    /// Parses `*` as `None` or names separated by commas
+    fn names(&mut self, what: &str) -> Result<Option<Vec<String>>, ParseError> {
+        if self.symbol("*") {
+            return Ok(None);
+        }
+        let mut names = vec![self.name(what)?];
+        while self.symbol(",") {
+            names.push(self.name(what)?);
+        }
+        Ok(Some(names))
+    }
+
+    /// # `source`
+    /// Parses a table of a join and its optional alias, as in `students s` or `students AS s`
+    fn source(&mut self) -> Result<(String, Option<String>), ParseError> {
+        let table = self.name("a table name")?;
+        let named = match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::Ident(word)) => !Parser::is_keyword(word),
+            Some(TokenKind::QuotedIdent(_)) => true,
+            _ => false,
+        };
+        let alias = if self.keyword("AS") || named {
+            Some(self.name("an alias")?)
+        } else {
+            None
+        };
+        Ok((table, alias))
+    }
+
+    /// # `joins`
+    /// Parses `JOIN <table> [<alias>] ON <condition>` after the first table of a join
+    fn joins(&mut self, (table, alias): (String, Option<String>)) -> Result<Joins, ParseError> {
+        let mut joins = vec![(table, alias, None)];
+        while self.keyword("JOIN") {
+            let (table, alias) = self.source()?;
+            self.expect("ON", "after the table of JOIN")?;
+            joins.push((table, alias, Some(self.expression()?)));
+        }
+        Ok(joins)
+    }
+
+    /// # `fields`
+    /// Parses columns written like in a header, up to the token at `end` or to the end of the query for `None`. They are read from
+    /// the text of the query rather than its tokens and split like a .csv record, so `"t:Full Name"` is one column
+    fn fields(&mut self, end: Option<usize>) -> Result<Vec<String>, ParseError> {
+        let start = self.position();
+        let stop = end.map_or(self.text.len(), |end| self.tokens[end].pos);
+        let fields = parse_records(&self.text[start..stop])
+            .map_err(|error| ParseError::new(error, start))?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if fields.is_empty() {
+            return self.error("Expected a column written like in a header, such as u:Age");
+        }
+        self.pos = end.unwrap_or(self.tokens.len());
+        Ok(fields
+            .into_iter()
+            .map(|field| field.unwrap_or_else(|| "NULL".to_string()))
+            .collect())
+    }
+
+    /// # `alteration`
+    /// Parses the change of an `ALTER TABLE`
+    fn alteration(&mut self) -> Result<Alteration, ParseError> {
+        if self.keyword("ADD") {
+            self.keyword("COLUMN");
+            let mut fields = self.fields(None)?;
+            if fields.len() > 1 {
+                return Err(ParseError::new("Only one column can be added at a time", self.text.len()));
+            }
+            Ok(Alteration::AddColumn(fields.remove(0)))
+        } else if self.keyword("DROP") {
+            self.keyword("COLUMN");
+            Ok(Alteration::DropColumn(self.name("a column name")?))
+        } else if self.keyword("RENAME") {
+            self.keyword("COLUMN");
+            let column = self.name("a column name")?;
+            self.expect("TO", "after the column name")?;
+            Ok(Alteration::RenameColumn(column, self.name("the new column name")?))
+        } else if self.keyword("ALTER") {
+            self.keyword("COLUMN");
+            let column = self.name("a column name")?;
+            self.expect("TYPE", "after the column name")?;
+            let position = self.position();
+            let kind = self.name("a type")?;
+            let kind = ColumnType::from_name(&kind)
+                .ok_or_else(|| ParseError::new(format!("No such type: {}", kind), position))?;
+            Ok(Alteration::ColumnType(column, kind))
+        } else {
+            self.error("Expected ADD, DROP, RENAME or ALTER after the table name")
+        }
+    }
+
+    /// # `count`
+    /// Parses a whole number that is not negative, such as the amount of rows after `LIMIT`
+    fn count(&mut self, context: &str) -> Result<usize, ParseError> {
+        match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::Number(number)) => match number.parse::<usize>() {
+                Ok(count) => {
+                    self.pos += 1;
+                    Ok(count)
+                }
+                Err(_) => self.error(format!("Expected a whole number that is not negative {}", context)),
+            },
+            _ => self.error(format!("Expected a number {}", context)),
+        }
+    }
+
+    /// # `columns`
+    /// Parses `*` as `None` or the columns of a `GET` separated by commas, which are expressions optionally followed by `AS <alias>`
+    fn columns(&mut self) -> Result<Option<Columns>, ParseError> {
+        if self.symbol("*") {
+            return Ok(None);
+        }
+        let mut columns: Columns = Vec::new();
+        loop {
+            let expr = self.expression()?;
+            let alias = if self.keyword("AS") {
+                Some(self.name("an alias after AS")?)
+            } else {
+                None
+            };
+            columns.push((expr, alias));
+            if !self.symbol(",") {
+                return Ok(Some(columns));
+            }
+        }
+    }
+
+    /// # `order`
+    /// Parses the expressions of an `ORDER BY`, each optionally followed by `ASC` or `DESC`
+    fn order(&mut self) -> Result<Vec<(Expr, bool)>, ParseError> {
+        let mut order: Vec<(Expr, bool)> = Vec::new();
+        loop {
+            let column = self.expression()?;
+            let descending = self.keyword("DESC");
+            if !descending {
+                self.keyword("ASC");
+            }
+            order.push((column, descending));
+            if !self.symbol(",") {
+                return Ok(order);
+            }
+        }
+    }
+
+    /// # `signed_number`
+    /// Parses a `+` or `-` followed by a number into the number with its sign, such as `-5` or `-1:30:00`.
+    /// `None` without moving on if the current tokens are not a sign and a number
+    fn signed_number(&mut self) -> Option<String> {
+        let sign = match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::Symbol(sign @ ("+" | "-"))) => *sign,
+            _ => return None,
+        };
+        match self.tokens.get(self.pos + 1).map(|token| &token.kind) {
+            Some(TokenKind::Number(number)) => {
+                let number = format!("{}{}", sign, number);
+                self.pos += 2;
+                Some(number)
+            }
+            _ => None,
+        }
+    }
+
+    /// # `value`
+    /// Parses a value to store, which is a bare word, a number with an optional sign or text in single quotes. `NULL` is `None`
+    fn value(&mut self) -> Result<Option<String>, ParseError> {
+        if self.keyword("NULL") {
+            return Ok(None);
+        }
+        for boolean in ["true", "false"] {
+            if self.keyword(boolean) {
+                return Ok(Some(boolean.to_string()));
+            }
+        }
+
+        match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::String(text)) => {
+                let text = text.clone();
+                self.pos += 1;
+                Ok(Some(text))
+            }
+            Some(TokenKind::QuotedIdent(_)) => self.error("Expected a value, text is put in single quotes"),
+            _ => match self.signed_number() {
+                Some(number) => Ok(Some(number)),
+                None => self.name("a value").map(Some),
+            },
+        }
+    }
+
+    /// # `bare_text`
+    /// Parses text in single quotes or a bare word that may hold symbols, such as the path `tables/a.csv` or the pattern `C%`.
+    /// The word is read from the text of the query up to whitespace, a `,` or a `)`
+    fn bare_text(&mut self, what: &str) -> Result<String, ParseError> {
+        match self.tokens.get(self.pos).map(|token| &token.kind) {
+            Some(TokenKind::String(text)) => {
+                let text = text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            Some(TokenKind::Ident(_) | TokenKind::Number(_) | TokenKind::Symbol("/" | "%" | "+" | "-")) => {
+                let start = self.position();
+                let end = self.text[start..]
+                    .find(|c: char| c.is_whitespace() || c == ',' || c == ')')
+                    .map_or(self.text.len(), |end| start + end);
+                while self.tokens.get(self.pos).is_some_and(|token| token.pos < end) {
+                    self.pos += 1;
+                }
+                Ok(self.text[start..end].to_string())
+            }
+            _ => self.error(format!("Expected {}", what)),
+        }
+    }
+
+    /// # `assignments`
+    /// Parses `<column>=<value>` pairs separated by commas
+    fn assignments(&mut self) -> Result<Vec<(String, Option<String>)>, ParseError> {
+        let mut assignments: Vec<(String, Option<String>)> = Vec::new();
+        loop {
+            let column = self.name("a column name")?;
+            self.expect("=", "after the column name")?;
+            assignments.push((column, self.value()?));
+            if !self.symbol(",") {
+                return Ok(assignments);
+            }
+        }
+    }
+
+    /// # `updates`
+    /// Parses `<column>=<expression>` pairs separated by commas
+    fn updates(&mut self) -> Result<Vec<(String, Expr)>, ParseError> {
+        let mut updates: Vec<(String, Expr)> = Vec::new();
+        loop {
+            let column = self.name("a column name")?;
+            self.expect("=", "after the column name")?;
+            updates.push((column, self.expression()?));
+            if !self.symbol(",") {
+                return Ok(updates);
+            }
+        }
+    }
+
+    /// # `statement`
+    /// Parses a query, which starts with the keyword of its command
+    pub fn statement(&mut self) -> Result<Statement, ParseError> {
+        if self.keyword("GET") {
+            let columns = self.columns()?;
+            self.expect("OF", "after the columns of GET")?;
+            // A table followed by JOIN starts a join, anything else are the rows of the table in use
+            let start = self.pos;
+            let joins = match self.source() {
+                Ok(source) if self.peek_keyword(0, "JOIN") => self.joins(source)?,
+                _ => {
+                    self.pos = start;
+                    Vec::new()
+                }
+            };
+            let rows = if joins.is_empty() {
+                self.names("a row name")?
+            } else {
+                None
+            };
+            let filter = if self.keyword("WHERE") {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            let group_by = if self.keyword("GROUP") {
+                self.expect("BY", "after GROUP")?;
+                self.names("a column name")?
+                    .ok_or_else(|| ParseError::new("Expected the columns to group by, not *", self.position()))?
+            } else {
+                Vec::new()
+            };
+            let having = if self.keyword("HAVING") {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            let order = if self.keyword("ORDER") {
+                self.expect("BY", "after ORDER")?;
+                self.order()?
+            } else {
+                Vec::new()
+            };
+            let limit = if self.keyword("LIMIT") {
+                Some(self.count("after LIMIT")?)
+            } else {
+                None
+            };
+            let offset = if self.keyword("OFFSET") {
+                Some(self.count("after OFFSET")?)
+            } else {
+                None
+            };
+            Ok(Statement::Get {
+                columns,
+                rows,
+                joins,
+                filter,
+                group_by,
+                having,
+                order,
+                limit,
+                offset,
+            })
+        } else if self.keyword("SET") {
+            let changes = self.updates()?;
+            let rows = if self.keyword("OF") {
+                self.names("a row name")?
+            } else if self.peek_keyword(0, "WHERE") {
+                None
+            } else {
+                return self.error("Expected OF or WHERE after the columns of SET");
+            };
+            let filter = if self.keyword("WHERE") {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            Ok(Statement::Set { changes, rows, filter })
+        } else if self.keyword("INSERT") {
+            let row = self.name("a row name")?;
+            let values = if self.symbol("(") {
+                let values = self.assignments()?;
+                self.expect(")", "after the columns of INSERT")?;
+                InsertValues::Named(values)
+            } else {
+                self.expect(":", "or ( after the row name of INSERT")?;
+                let mut values = vec![self.value()?];
+                while self.symbol(",") {
+                    values.push(self.value()?);
+                }
+                InsertValues::Ordered(values)
+            };
+            Ok(Statement::Insert { row, values })
+        } else if self.keyword("DELETE") {
+            let rows = if self.peek_keyword(0, "WHERE") {
+                None
+            } else {
+                self.names("a row name")?
+            };
+            let filter = if self.keyword("WHERE") {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            Ok(Statement::Delete { rows, filter })
+        } else if self.keyword("SAVE") {
+            let path = if self.keyword("AS") {
+                Some(self.bare_text("a path")?)
+            } else {
+                None
+            };
+            Ok(Statement::Save { path })
+        } else if self.keyword("USE") {
+            Ok(Statement::Use {
+                table: self.name("a table name")?,
+            })
+        } else if self.keyword("OPEN") {
+            let path = self.bare_text("a path")?;
+            let table = if self.keyword("AS") {
+                Some(self.name("a table name")?)
+            } else {
+                None
+            };
+            Ok(Statement::Open { path, table })
+        } else if self.keyword("CLOSE") {
+            let table = if self.tokens.get(self.pos).is_some() {
+                Some(self.name("a table name")?)
+            } else {
+                None
+            };
+            Ok(Statement::Close { table })
+        } else if self.keyword("CREATE") {
+            if self.keyword("INDEX") {
+                self.expect("ON", "after CREATE INDEX")?;
+                let table = self.name("a table name")?;
+                self.expect("(", "after the table name")?;
+                let column = self.name("a column name")?;
+                self.expect(")", "after the column name")?;
+                return Ok(Statement::CreateIndex { table, column });
+            }
+            self.expect("TABLE", "or INDEX after CREATE")?;
+            let table = self.name("a table name")?;
+            self.expect("(", "before the columns of the table")?;
+            // The columns end at the last ), since a regex may hold more of them
+            let end = (self.pos..self.tokens.len())
+                .rev()
+                .find(|idx| matches!(self.tokens[*idx].kind, TokenKind::Symbol(")")));
+            let Some(end) = end else {
+                return Err(ParseError::new("Expected ) after the columns of the table", self.text.len()));
+            };
+            let columns = self.fields(Some(end))?;
+            self.pos += 1;
+            Ok(Statement::CreateTable { table, columns })
+        } else if self.keyword("DROP") {
+            self.expect("TABLE", "after DROP")?;
+            Ok(Statement::DropTable {
+                table: self.name("a table name")?,
+            })
+        } else if self.keyword("ALTER") {
+            self.expect("TABLE", "after ALTER")?;
+            let table = self.name("a table name")?;
+            Ok(Statement::AlterTable {
+                table,
+                alteration: self.alteration()?,
+            })
+        } else if self.keyword("BEGIN") {
+            Ok(Statement::Begin)
+        } else if self.keyword("COMMIT") {
+            Ok(Statement::Commit)
+        } else if self.keyword("ROLLBACK") {
+            let savepoint = if self.keyword("TO") {
+                self.keyword("SAVEPOINT");
+                Some(self.name("a savepoint name")?)
+            } else {
+                None
+            };
+            Ok(Statement::Rollback { savepoint })
+        } else if self.keyword("SAVEPOINT") {
+            Ok(Statement::Savepoint {
+                name: self.name("a savepoint name")?,
+            })
+        } else if self.keyword("RELEASE") {
+            self.keyword("SAVEPOINT");
+            Ok(Statement::Release {
+                savepoint: self.name("a savepoint name")?,
+            })
+        } else if self.keyword("ABORT") {
+            Ok(Statement::Abort)
+        } else if self.tokens.is_empty() {
+            self.error("Empty query")
+        } else {
+            self.error("No such command")
+        }
+    }
+
+    /// # `expression`
+    /// Parses a condition or a value. From loosest to tightest binding: `OR`, `AND`, `NOT`, comparisons, `+` and `-`, then `*`, `/` and `%`
+    pub fn expression(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.not()?;
+        while self.keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        if self.keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.predicate()
+    }
+
+    /// # `predicate`
+    /// Parses a value optionally followed by a comparison, `IS [NOT] NULL`, `IN`, `BETWEEN` or `LIKE`
+    fn predicate(&mut self) -> Result<Expr, ParseError> {
+        let left = Box::new(self.additive()?);
+
+        for (symbol, op) in [
+            ("=", CompareOp::Equal),
+            ("!=", CompareOp::NotEqual),
+            ("<", CompareOp::Less),
+            ("<=", CompareOp::LessEqual),
+            (">", CompareOp::Greater),
+            (">=", CompareOp::GreaterEqual),
+        ] {
+            if self.symbol(symbol) {
+                return Ok(Expr::Compare(left, op, Box::new(self.additive()?)));
+            }
+        }
+
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            self.expect("NULL", "after IS")?;
+            return Ok(Expr::IsNull(left, negated));
+        }
+
+        let negated = ["IN", "BETWEEN", "LIKE"]
+            .iter()
+            .any(|keyword| self.peek_keyword(0, "NOT") && self.peek_keyword(1, keyword))
+            && self.keyword("NOT");
+
+        if self.keyword("IN") {
+            self.expect("(", "after IN")?;
+            let mut list = vec![self.additive()?];
+            while self.symbol(",") {
+                list.push(self.additive()?);
+            }
+            self.expect(")", "after the values of IN")?;
+            Ok(Expr::In(left, list, negated))
+        } else if self.keyword("BETWEEN") {
+            let low = self.additive()?;
+            self.expect("AND", "between the values of BETWEEN")?;
+            let high = self.additive()?;
+            Ok(Expr::Between(left, Box::new(low), Box::new(high), negated))
+        } else if self.keyword("LIKE") {
+            let pattern = self.bare_text("a pattern after LIKE")?;
+            Ok(Expr::Like(left, Pattern::like(&pattern), negated))
+        } else {
+            Ok(*left)
+        }
+    }
+
+    /// # `additive`
+    /// Parses values joined by `+` or `-`
+    fn additive(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op = if self.symbol("+") {
+                ArithOp::Add
+            } else if self.symbol("-") {
+                ArithOp::Subtract
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Arithmetic(Box::new(expr), op, Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.operand()?;
+        loop {
+            let op = if self.symbol("*") {
+                ArithOp::Multiply
+            } else if self.symbol("/") {
+                ArithOp::Divide
+            } else if self.symbol("%") {
+                ArithOp::Remainder
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Arithmetic(Box::new(expr), op, Box::new(self.operand()?));
+        }
+    }
+
+    /// # `arguments`
+    /// Parses the values given to a function after its `(`, up to and including the closing `)`
+    fn arguments(&mut self, function: Function) -> Result<Vec<Expr>, ParseError> {
+        let mut args: Vec<Expr> = Vec::new();
+        if !self.symbol(")") {
+            args.push(self.expression()?);
+            while self.symbol(",") {
+                args.push(self.expression()?);
+            }
+            self.expect(")", &format!("to close {}(", function.name()))?;
+        }
+        Ok(args)
+    }
+
+    /// # `cast`
+    /// Parses the rest of a cast after `CAST(`: `<expr> AS <type>)` where the type is a code of a header such as `u` or a name such as `UInteger`
+    fn cast(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.expression()?;
+        self.expect("AS", "after the value of CAST")?;
+        let pos = self.position();
+        let name = self.name("a type")?;
+        let kind = ColumnType::from_name(&name).ok_or_else(|| ParseError::new(format!("No such type: {}", name), pos))?;
+        self.expect(")", "to close CAST(")?;
+        Ok(Expr::Cast(Box::new(expr), kind))
+    }
+
+    /// # `aggregate`
+    /// Parses the rest of an aggregate after its name and `(`: `*` for `COUNT` or an expression that may start with `DISTINCT`
+    fn aggregate(&mut self, aggregate: Aggregate) -> Result<Expr, ParseError> {
+        let expr = if aggregate == Aggregate::Count && self.symbol("*") {
+            Expr::Aggregate(aggregate, None, false)
+        } else {
+            let distinct = self.keyword("DISTINCT");
+            Expr::Aggregate(aggregate, Some(Box::new(self.expression()?)), distinct)
+        };
+        self.expect(")", &format!("to close {}(", aggregate.name()))?;
+        Ok(expr)
+    }
+
+    /// # `operand`
+    /// Parses a name, a value, a call of a function, cast or aggregate, or an expression in parentheses
+    fn operand(&mut self) -> Result<Expr, ParseError> {
+        if self.symbol("(") {
+            let expr = self.expression()?;
+            self.expect(")", "to close the (")?;
+            return Ok(expr);
+        }
+
+        // A name followed by ( is a call, names of aggregates are not keywords so they can still be used as column names
+        if let (Some(Token { kind: TokenKind::Ident(name), .. }), Some(Token { kind: TokenKind::Symbol("("), .. })) =
+            (self.tokens.get(self.pos), self.tokens.get(self.pos + 1))
+        {
+            let name = name.clone();
+            if let Some(aggregate) = Aggregate::from_name(&name) {
+                self.pos += 2;
+                return self.aggregate(aggregate);
+            } else if let Some(function) = Function::from_name(&name) {
+                self.pos += 2;
+                return Ok(Expr::Call(function, self.arguments(function)?));
+            } else if name.eq_ignore_ascii_case("CAST") {
+                self.pos += 2;
+                return self.cast();
+            }
+            return self.error(format!("No such function: {}", name));
+        }
+
+        if self.keyword("NULL") {
+            Ok(Expr::Value(DataItem::Null))
+        } else if self.keyword("TRUE") {
+            Ok(Expr::Value(DataItem::Boolean(true)))
+        } else if self.keyword("FALSE") {
+            Ok(Expr::Value(DataItem::Boolean(false)))
+        } else {
+            match self.tokens.get(self.pos).map(|token| token.kind.clone()) {
+                Some(TokenKind::QuotedIdent(name)) => {
+                    self.pos += 1;
+                    Ok(Expr::Identifier(name))
+                }
+                Some(TokenKind::String(text)) => {
+                    self.pos += 1;
+                    Ok(Expr::Literal(text))
+                }
+                _ => match self.signed_number() {
+                    Some(number) => Ok(Expr::Name(number)),
+                    None => self.name("a column name or a value").map(Expr::Name),
+                },
+            }
+        }
+    }
+}
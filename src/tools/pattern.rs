@@ -0,0 +1,236 @@
+/// # `Pattern`
+/// A small regular expression that must match the whole text. Supported are:
+/// - Any other character matches itself, `\` makes the next character match itself such as `\.`
+/// - `.` matches any character
+/// - `[abc]`, `[a-z]` match one of the characters in the brackets, `[^abc]` any character that is not
+/// - `\d` a digit, `\w` a letter, digit or `_` and `\s` a whitespace
+/// - `*`, `+` and `?` after any of the above repeat it any amount of times, at least once or at most once
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    source: String,
+    tokens: Vec<(Atom, Repeat)>,
+}
+
+/// # `Atom`
+/// A part of a `Pattern` that matches a single character
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Any,
+    Char(char),
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+/// # `Repeat`
+/// How many times an `Atom` may match
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Repeat {
+    Once,
+    Optional,
+    Many,
+    AtLeastOnce,
+}
+
+impl Atom {
+    /// # `matches`
+    /// Checks if the character matches this atom
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Char(expected) => c == *expected,
+            Atom::Class { negated, ranges } => {
+                ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated
+            }
+        }
+    }
+
+    /// # `escaped`
+    /// Returns the atom for a character that followed a `\`
+    fn escaped(c: char) -> Atom {
+        match c {
+            'd' => Atom::Class { negated: false, ranges: vec![('0', '9')] },
+            'w' => Atom::Class {
+                negated: false,
+                ranges: vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+            },
+            's' => Atom::Class {
+                negated: false,
+                ranges: vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')],
+            },
+            c => Atom::Char(c),
+        }
+    }
+}
+
+impl Pattern {
+    /// # `new`
+    /// Parses the pattern. `Err()` is returned for an unclosed `[`, a `\` at the end or a repetition with nothing to repeat
+    pub fn new(source: &str) -> Result<Pattern, &'static str> {
+        let mut tokens: Vec<(Atom, Repeat)> = Vec::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '.' => Atom::Any,
+                '\\' => Atom::escaped(chars.next().ok_or("Pattern ends with \\")?),
+                '[' => {
+                    let negated = chars.next_if_eq(&'^').is_some();
+                    let mut ranges: Vec<(char, char)> = Vec::new();
+                    loop {
+                        let low = match chars.next() {
+                            Some(']') if !ranges.is_empty() => break,
+                            Some('\\') => chars.next().ok_or("Pattern ends with \\")?,
+                            Some(c) => c,
+                            None => return Err("Missing ] in pattern"),
+                        };
+                        // A - between two characters is a range, at the end it is just a -
+                        let high = match (chars.next_if_eq(&'-'), chars.peek()) {
+                            (Some(_), Some(&']')) => {
+                                ranges.push(('-', '-'));
+                                low
+                            }
+                            (Some(_), Some(_)) => chars.next().unwrap(),
+                            _ => low,
+                        };
+                        if high < low {
+                            return Err("Invalid range in pattern");
+                        }
+                        ranges.push((low, high));
+                    }
+                    Atom::Class { negated, ranges }
+                }
+                '*' | '+' | '?' => return Err("Nothing to repeat in pattern"),
+                c => Atom::Char(c),
+            };
+
+            let repeat = match chars.next_if(|c| matches!(c, '*' | '+' | '?')) {
+                Some('*') => Repeat::Many,
+                Some('+') => Repeat::AtLeastOnce,
+                Some('?') => Repeat::Optional,
+                _ => Repeat::Once,
+            };
+            tokens.push((atom, repeat));
+        }
+
+        Ok(Pattern {
+            source: source.to_string(),
+            tokens,
+        })
+    }
+
+    /// # `like`
+    /// Creates the pattern of a `LIKE` condition, where `%` matches any amount of characters and `_` a single character.
+    /// Any other character matches itself, `\` makes the next `%`, `_` or `\` match itself
+    pub fn like(source: &str) -> Pattern {
+        let mut tokens: Vec<(Atom, Repeat)> = Vec::new();
+        let mut chars = source.chars();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '%' => (Atom::Any, Repeat::Many),
+                '_' => (Atom::Any, Repeat::Once),
+                '\\' => (Atom::Char(chars.next().unwrap_or('\\')), Repeat::Once),
+                c => (Atom::Char(c), Repeat::Once),
+            });
+        }
+
+        Pattern {
+            source: source.to_string(),
+            tokens,
+        }
+    }
+
+    /// # `source`
+    /// Returns the pattern as it was written
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// # `is_match`
+    /// Checks if the pattern matches the whole text. Patterns made of single characters and `.*`, which are all `LIKE` patterns,
+    /// are matched by `match_wildcards`, any other by `match_states`. Both take time proportional to the text times the pattern
+    pub fn is_match(&self, text: &str) -> bool {
+        let text = text.chars().collect::<Vec<char>>();
+        let wildcards = self
+            .tokens
+            .iter()
+            .all(|token| matches!(token, (_, Repeat::Once) | (Atom::Any, Repeat::Many)));
+        if wildcards {
+            Pattern::match_wildcards(&self.tokens, &text)
+        } else {
+            Pattern::match_states(&self.tokens, &text)
+        }
+    }
+
+    /// # `match_wildcards`
+    /// Checks if tokens that are single characters or `.*` match the whole text. The tokens after the last `.*` are matched
+    /// as early as possible, and on a mismatch that `.*` takes one more character and they are matched again from there.
+    /// Earlier `.*` never have to take more, as whatever a later one skips could have been skipped by it instead
+    fn match_wildcards(tokens: &[(Atom, Repeat)], text: &[char]) -> bool {
+        let (mut token, mut position) = (0, 0);
+        // The token after the last `.*` and the position in the text it is matched from
+        let mut wildcard: Option<(usize, usize)> = None;
+
+        while position < text.len() {
+            match tokens.get(token) {
+                Some((Atom::Any, Repeat::Many)) => {
+                    token += 1;
+                    wildcard = Some((token, position));
+                }
+                Some((atom, _)) if atom.matches(text[position]) => {
+                    token += 1;
+                    position += 1;
+                }
+                _ => match wildcard {
+                    Some((after, from)) => {
+                        token = after;
+                        position = from + 1;
+                        wildcard = Some((after, from + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+        tokens[token..].iter().all(|(_, repeat)| *repeat == Repeat::Many)
+    }
+
+    /// # `match_states`
+    /// Checks if the tokens match the whole text by following every way of matching at once. A state is the index of the next
+    /// token to match, each character moves every state whose token matches it on, and states that are left behind are dropped
+    fn match_states(tokens: &[(Atom, Repeat)], text: &[char]) -> bool {
+        let mut states = vec![false; tokens.len() + 1];
+        states[0] = true;
+        Pattern::skip_optional(tokens, &mut states);
+
+        for c in text {
+            let mut next = vec![false; tokens.len() + 1];
+            for (idx, (atom, repeat)) in tokens.iter().enumerate() {
+                if !states[idx] || !atom.matches(*c) {
+                    continue;
+                }
+                match repeat {
+                    Repeat::Once | Repeat::Optional => next[idx + 1] = true,
+                    Repeat::Many => next[idx] = true,
+                    Repeat::AtLeastOnce => {
+                        next[idx] = true;
+                        next[idx + 1] = true;
+                    }
+                }
+            }
+            Pattern::skip_optional(tokens, &mut next);
+            if !next.contains(&true) {
+                return false;
+            }
+            states = next;
+        }
+        states[tokens.len()]
+    }
+
+    /// # `skip_optional`
+    /// Adds the states reached by matching tokens repeated with `*` or `?` zero times
+    fn skip_optional(tokens: &[(Atom, Repeat)], states: &mut [bool]) {
+        for (idx, (_, repeat)) in tokens.iter().enumerate() {
+            if states[idx] && matches!(repeat, Repeat::Optional | Repeat::Many) {
+                states[idx + 1] = true;
+            }
+        }
+    }
+}
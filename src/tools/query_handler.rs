@@ -1,92 +1,293 @@
-use super::Table;
+use super::{Database, Expr, InsertValues, Statement, Table};
+
+/// # `resolve_aliases`
+/// Replaces names in `ORDER BY` that are aliases of columns of the `GET` by the expression of that column
+fn resolve_aliases(order: Vec<(Expr, bool)>, columns: &[(Expr, Option<String>)]) -> Vec<(Expr, bool)> {
+    order
+        .into_iter()
+        .map(|(expr, descending)| {
+            let alias = match &expr {
+                Expr::Name(name) | Expr::Identifier(name) => {
+                    columns.iter().find(|(_, alias)| alias.as_ref() == Some(name))
+                }
+                _ => None,
+            };
+            (alias.map_or(expr, |(column, _)| column.clone()), descending)
+        })
+        .collect()
+}
+
+/// # `page`
+/// Skips the first `offset` results and returns at most `limit` of the rest
+fn page<T>(result: Vec<T>, limit: Option<usize>, offset: Option<usize>) -> Vec<T> {
+    result
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// # `Transactional`
+/// What transaction statements work on: a `Table` alone, or every table of a `Database` together
+trait Transactional {
+    fn begin(&mut self) -> Result<(), String>;
+    fn commit(&mut self) -> Result<(), String>;
+    fn rollback(&mut self, savepoint: Option<&str>) -> Result<(), String>;
+    fn savepoint(&mut self, name: &str) -> Result<(), String>;
+    fn release(&mut self, name: &str) -> Result<(), String>;
+    fn in_transaction(&self) -> bool;
+}
+
+impl Transactional for Table {
+    fn begin(&mut self) -> Result<(), String> {
+        Ok(Table::begin(self)?)
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        Ok(Table::commit(self)?)
+    }
+
+    fn rollback(&mut self, savepoint: Option<&str>) -> Result<(), String> {
+        Table::rollback(self, savepoint)
+    }
+
+    fn savepoint(&mut self, name: &str) -> Result<(), String> {
+        Ok(Table::savepoint(self, name)?)
+    }
+
+    fn release(&mut self, name: &str) -> Result<(), String> {
+        Table::release(self, name)
+    }
+
+    fn in_transaction(&self) -> bool {
+        Table::in_transaction(self)
+    }
+}
+
+impl Transactional for Database {
+    fn begin(&mut self) -> Result<(), String> {
+        Database::begin(self)
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        Database::commit(self)
+    }
+
+    fn rollback(&mut self, savepoint: Option<&str>) -> Result<(), String> {
+        Database::rollback(self, savepoint)
+    }
+
+    fn savepoint(&mut self, name: &str) -> Result<(), String> {
+        Database::savepoint(self, name)
+    }
+
+    fn release(&mut self, name: &str) -> Result<(), String> {
+        Database::release(self, name)
+    }
+
+    fn in_transaction(&self) -> bool {
+        Database::in_transaction(self)
+    }
+}
+
+/// # `transaction`
+/// Runs `BEGIN`, `COMMIT`, `ROLLBACK`, `SAVEPOINT`, `RELEASE` or `ABORT` on a table or database. `ABORT` quits,
+/// after undoing an open transaction since changes to disk tables are already in their files
+fn transaction(target: &mut impl Transactional, statement: Statement) -> Result<(), String> {
+    match statement {
+        Statement::Begin => {
+            target.begin()?;
+            println!("Transaction started");
+        }
+        Statement::Commit => {
+            target.commit()?;
+            println!("Transaction committed");
+        }
+        Statement::Rollback { savepoint } => {
+            target.rollback(savepoint.as_deref())?;
+            match savepoint {
+                Some(savepoint) => println!("Rolled back to savepoint {}", savepoint),
+                None => println!("Transaction rolled back"),
+            }
+        }
+        Statement::Savepoint { name } => {
+            target.savepoint(&name)?;
+            println!("Savepoint {} made", name);
+        }
+        Statement::Release { savepoint } => {
+            target.release(&savepoint)?;
+            println!("Savepoint {} released", savepoint);
+        }
+        Statement::Abort => {
+            if target.in_transaction() {
+                target.rollback(None)?;
+            }
+            println!("Quitted without saving table");
+            std::process::exit(0);
+        }
+        _ => return Err("Not a transaction statement".to_string()),
+    }
+    Ok(())
+}
 
 /// # `query`
 /// Takes a given `Table` and the query to parse as `String`
-pub fn query(table: &mut Table, query: &String) -> Result<(), &'static str> {
-    // === GET ===
-    if query.starts_with("GET") {
-        // get everything after the word GET and prepare it for parsing
-        let args = query[3..]
-            .split("OF")
-            .map(|arg| {
-                arg.split(',')
-                    .map(|subarg| subarg.to_string().replace(" ", ""))
-                    .collect::<Vec<String>>()
-            })
-            .collect::<Vec<Vec<String>>>();
-
-        if args.len() < 2 {
-            return Err("The given query is incorrect");
-        }
-        // Search the table for results then print it
-        let result = table.get(&args[0], &args[1]);
-        println!("{:?}", result);
-        return Ok(());
-
-    // === SET ===
-    } else if query.starts_with("SET") {
-        // Get everything after SET and prepare it for parsing
-        let mut args = query[3..]
-            .split_terminator("OF")
-            .map(|data| data.replace(" ", ""));
-
-        // Get list of columns to change
-        let to_change: Vec<(String, String)> = if let Some(to_change) = args.next() {
-            to_change
-                .split_terminator(',')
-                .map(|data| {
-                    let data_pair: Vec<&str> = data.split('=').collect();
-                    (data_pair[0].to_string(), data_pair[1].to_string())
-                })
-                .collect()
-        } else {
-            return Err("Error parsing column part of SET command");
-        };
-
-        // Get row name
-        if let Some(row) = args.next() {
-            // Change value in table
-            table.set(row, to_change)?;
-        } else {
-            return Err("Error parsing row name part of  SET command");
-        }
-        return Ok(());
-    // === DELETE ===
-    } else if query.starts_with("DELETE") {
-        table.remove_row(&query[6..].replace(" ", "").to_string())?;
-        return Ok(());
-
-    // === INSERT ===
-    } else if query.starts_with("INSERT") {
-        // Prepare data after INSERT
-        let mut args = query[6..]
-            .split_terminator(':')
-            .map(|data| data.replace(" ", ""));
-
-        let row_name = if let Some(row_name) = args.next() {
-            row_name.to_string()
-        } else {
-            return Err("Error parsing row name of INSERT command");
-        };
-        let content: Vec<String> = if let Some(content) = args.next() {
-            content
-                .split_terminator(',')
-                .map(|data| data.to_string())
-                .collect()
-        } else {
-            return Err("Error parsing column part of INSERT command");
-        };
-
-        table.new_row(row_name, content)?;
-        return Ok(());
-    } else if query.starts_with("SAVE") {
-        table.save()?;
-        println!("Saved!");
-        return Ok(());
-    } else if query.starts_with("ABORT") {
-        println!("Quitted without saving table");
-        std::process::exit(0);
+/// Keywords may be in any case. Names may be put in double quotes and text values in single quotes to hold spaces, commas
+/// or keywords, such as `"Of Course"` and `'Hello, world'`. An unquoted `NULL` as a value is a missing value. If the query can not be parsed the error shows where
+pub fn query(table: &mut Table, query: &str) -> Result<(), String> {
+    execute(table, Statement::parse(query).map_err(|error| error.describe(query))?)
+}
+
+/// # `query_database`
+/// Takes a given `Database` and the query to parse as `String`. `USE`, `OPEN` and `CLOSE` choose the tables of the database
+/// and a `GET` with `JOIN` reads from its tables, every other query works on the table in use like `query`. `INSERT`, `SET`
+/// and `DELETE` also keep the references between the tables intact
+pub fn query_database(database: &mut Database, query: &str) -> Result<(), String> {
+    let mut statement = Statement::parse(query).map_err(|error| error.describe(query))?;
+
+    match statement {
+        Statement::Use { table } => {
+            database.use_table(&table)?;
+            println!("Using {}", table);
+        }
+        Statement::Open { path, table } => {
+            let table = database.open_table(path, table)?;
+            println!("Table {} opened!", table);
+        }
+        Statement::Close { table } => {
+            let table = database.close(table.as_deref())?;
+            println!("Table {} closed", table);
+        }
+        Statement::CreateTable { table, columns } => {
+            database.create_table(&table, &columns)?;
+            println!("Table {} created!", table);
+        }
+        Statement::CreateIndex { table, column } => {
+            database.create_index(&table, &column)?;
+            println!("Index on {}({}) created!", table, column);
+        }
+        Statement::DropTable { table } => {
+            database.drop_table(&table)?;
+            println!("Table {} dropped", table);
+        }
+        Statement::AlterTable { table, alteration } => {
+            database.alter_table(&table, alteration)?;
+            println!("Table {} altered", table);
+        }
+        Statement::Get { ref mut joins, .. } if !joins.is_empty() => {
+            let mut joined = database.join(&std::mem::take(joins))?;
+            execute(&mut joined, statement)?;
+        }
+        statement @ (Statement::Begin
+        | Statement::Commit
+        | Statement::Rollback { .. }
+        | Statement::Savepoint { .. }
+        | Statement::Release { .. }
+        | Statement::Abort) => transaction(database, statement)?,
+        Statement::Delete { rows, filter } => {
+            let all = vec!["*".to_string()];
+            let counts = database.delete(rows.as_deref().unwrap_or(&all), filter.as_ref())?;
+            for (idx, (table, count)) in counts.into_iter().enumerate() {
+                let rows = if count == 1 { "row" } else { "rows" };
+                match idx {
+                    0 => println!("{} {} deleted", count, rows),
+                    _ => println!("{} {} of {} deleted by CASCADE", count, rows, table),
+                }
+            }
+        }
+        statement @ (Statement::Insert { .. } | Statement::Set { .. }) => database.change(|table| execute(table, statement))?,
+        statement => execute(database.current()?, statement)?,
+    }
+
+    Ok(())
+}
+
+/// # `execute`
+/// Runs a parsed query on the table
+fn execute(table: &mut Table, statement: Statement) -> Result<(), String> {
+    let all = || vec!["*".to_string()];
+
+    match statement {
+        // === GET ===
+        Statement::Get { joins, .. } if !joins.is_empty() => {
+            return Err("JOIN can only be used on a Database".to_string())
+        }
+        Statement::Get {
+            columns,
+            rows,
+            joins: _,
+            filter,
+            group_by,
+            having,
+            order,
+            limit,
+            offset,
+        } => {
+            let order = resolve_aliases(order, columns.as_deref().unwrap_or_default());
+            let columns = columns.map(|columns| columns.into_iter().map(|(expr, _)| expr).collect::<Vec<Expr>>());
+            let aggregated = !group_by.is_empty()
+                || having.is_some()
+                || columns.iter().flatten().chain(order.iter().map(|(expr, _)| expr)).any(Expr::has_aggregate);
+
+            // Search the table for results then print the requested part of it
+            if aggregated {
+                let columns = columns.ok_or("Columns must be listed instead of * when using aggregates or GROUP BY")?;
+                let result = table.aggregate(
+                    &columns,
+                    &rows.unwrap_or_else(all),
+                    filter.as_ref(),
+                    &group_by,
+                    having.as_ref(),
+                    &order,
+                )?;
+                println!("{:?}", page(result, limit, offset));
+            } else {
+                let result = table.select(columns.as_deref(), &rows.unwrap_or_else(all), filter.as_ref(), &order)?;
+                println!("{:?}", page(result, limit, offset));
+            }
+        }
+
+        // === SET ===
+        Statement::Set { changes, rows, filter } => {
+            let count = table.update(changes, &rows.unwrap_or_else(all), filter.as_ref())?;
+            println!("{} {} updated", count, if count == 1 { "row" } else { "rows" });
+        }
+
+        // === DELETE ===
+        Statement::Delete { rows, filter } => {
+            let count = table.delete(&rows.unwrap_or_else(all), filter.as_ref())?.len();
+            println!("{} {} deleted", count, if count == 1 { "row" } else { "rows" });
+        }
+
+        // === INSERT ===
+        Statement::Insert { row, values } => match values {
+            InsertValues::Ordered(values) => table.new_row(row, values)?,
+            InsertValues::Named(values) => table.new_row_named(row, values)?,
+        },
+
+        Statement::Save { path } => {
+            match path {
+                Some(path) => table.save_as(path)?,
+                None => table.save()?,
+            }
+            println!("Saved!");
+        }
+        Statement::Use { .. }
+        | Statement::Open { .. }
+        | Statement::Close { .. }
+        | Statement::CreateTable { .. }
+        | Statement::CreateIndex { .. }
+        | Statement::DropTable { .. }
+        | Statement::AlterTable { .. } => {
+            return Err("USE, OPEN, CLOSE, CREATE, DROP and ALTER can only be used on a Database".to_string())
+        }
+        statement @ (Statement::Begin
+        | Statement::Commit
+        | Statement::Rollback { .. }
+        | Statement::Savepoint { .. }
+        | Statement::Release { .. }
+        | Statement::Abort) => transaction(table, statement)?,
     }
 
-    Err("No such command")
+    Ok(())
 }
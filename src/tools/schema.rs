@@ -0,0 +1,573 @@
+use super::csv::NULL;
+use super::{parse_bytes, DataItem, Date, DateTime, Decimal, Duration, Expr, Pattern};
+use crate::hash_map::Map;
+use std::fmt::{Display, Formatter};
+
+/// # `ColumnType`
+/// The type of the values stored in a column, written in the header as the character(s) before the `:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Word,
+    Boolean,
+    UInteger,
+    Integer,
+    Float,
+    Text,
+    Long,
+    ULong,
+    Double,
+    Date,
+    DateTime,
+    Duration,
+    Decimal,
+    Bytes,
+}
+
+impl ColumnType {
+    /// # `from_code`
+    /// Returns the type written as the given code in a header, such as `u` for `UInteger`
+    pub fn from_code(code: &str) -> Option<ColumnType> {
+        Some(match code {
+            "w" => ColumnType::Word,
+            "b" => ColumnType::Boolean,
+            "u" => ColumnType::UInteger,
+            "i" => ColumnType::Integer,
+            "f" => ColumnType::Float,
+            "t" => ColumnType::Text,
+            "i64" => ColumnType::Long,
+            "u64" => ColumnType::ULong,
+            "f64" => ColumnType::Double,
+            "d" => ColumnType::Date,
+            "dt" => ColumnType::DateTime,
+            "dur" => ColumnType::Duration,
+            "dec" => ColumnType::Decimal,
+            "x" => ColumnType::Bytes,
+            _ => return None,
+        })
+    }
+
+    /// # `from_name`
+    /// Returns the type with the given code or name in any case, such as `u` or `UInteger`
+    pub fn from_name(name: &str) -> Option<ColumnType> {
+        ColumnType::from_code(&name.to_lowercase()).or_else(|| {
+            [
+                ColumnType::Word,
+                ColumnType::Boolean,
+                ColumnType::UInteger,
+                ColumnType::Integer,
+                ColumnType::Float,
+                ColumnType::Text,
+                ColumnType::Long,
+                ColumnType::ULong,
+                ColumnType::Double,
+                ColumnType::Date,
+                ColumnType::DateTime,
+                ColumnType::Duration,
+                ColumnType::Decimal,
+                ColumnType::Bytes,
+            ]
+            .into_iter()
+            .find(|kind| format!("{:?}", kind).eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// # `code`
+    /// Returns the code of this type in a header
+    pub fn code(&self) -> &'static str {
+        match self {
+            ColumnType::Word => "w",
+            ColumnType::Boolean => "b",
+            ColumnType::UInteger => "u",
+            ColumnType::Integer => "i",
+            ColumnType::Float => "f",
+            ColumnType::Text => "t",
+            ColumnType::Long => "i64",
+            ColumnType::ULong => "u64",
+            ColumnType::Double => "f64",
+            ColumnType::Date => "d",
+            ColumnType::DateTime => "dt",
+            ColumnType::Duration => "dur",
+            ColumnType::Decimal => "dec",
+            ColumnType::Bytes => "x",
+        }
+    }
+
+    /// # `of`
+    /// Returns the type of the value, `None` for `DataItem::Null` which has no type
+    pub fn of(value: &DataItem) -> Option<ColumnType> {
+        Some(match value {
+            DataItem::Word(_) => ColumnType::Word,
+            DataItem::Boolean(_) => ColumnType::Boolean,
+            DataItem::UInteger(_) => ColumnType::UInteger,
+            DataItem::Integer(_) => ColumnType::Integer,
+            DataItem::Float(_) => ColumnType::Float,
+            DataItem::Text(_) => ColumnType::Text,
+            DataItem::Long(_) => ColumnType::Long,
+            DataItem::ULong(_) => ColumnType::ULong,
+            DataItem::Double(_) => ColumnType::Double,
+            DataItem::Date(_) => ColumnType::Date,
+            DataItem::DateTime(_) => ColumnType::DateTime,
+            DataItem::Duration(_) => ColumnType::Duration,
+            DataItem::Decimal(_) => ColumnType::Decimal,
+            DataItem::Bytes(_) => ColumnType::Bytes,
+            DataItem::Null => return None,
+        })
+    }
+
+    /// # `is_numeric`
+    /// Returns `true` for the integer, float and decimal types
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::UInteger
+                | ColumnType::Integer
+                | ColumnType::Float
+                | ColumnType::Long
+                | ColumnType::ULong
+                | ColumnType::Double
+                | ColumnType::Decimal
+        )
+    }
+
+    /// # `is_whole`
+    /// Returns `true` for the integer types
+    pub fn is_whole(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::UInteger | ColumnType::Integer | ColumnType::Long | ColumnType::ULong
+        )
+    }
+
+    /// # `is_text`
+    /// Returns `true` for `Word` and `Text`
+    pub fn is_text(&self) -> bool {
+        matches!(self, ColumnType::Word | ColumnType::Text)
+    }
+
+    /// # `comparable`
+    /// Checks if values of the two types can be compared, which is the case for the same type, two numeric types or two text types
+    pub fn comparable(&self, other: &ColumnType) -> bool {
+        self == other || self.is_numeric() && other.is_numeric() || self.is_text() && other.is_text()
+    }
+
+    /// # `parse`
+    /// Parses text written in a query or a .csv file into a value of this type
+    pub fn parse(&self, text: &str) -> Result<DataItem, &'static str> {
+        Ok(match self {
+            ColumnType::Boolean => {
+                DataItem::Boolean(text.parse::<bool>().map_err(|_| "Error parsing value as Boolean")?)
+            }
+            ColumnType::Float => DataItem::Float(text.parse::<f32>().map_err(|_| "Error parsing value as Float")?),
+            ColumnType::UInteger => {
+                DataItem::UInteger(text.parse::<u32>().map_err(|_| "Error parsing value as UInteger")?)
+            }
+            ColumnType::Integer => {
+                DataItem::Integer(text.parse::<i32>().map_err(|_| "Error parsing value as Integer")?)
+            }
+            ColumnType::Word => {
+                if text.contains(char::is_whitespace) {
+                    return Err("Error parsing value as Word");
+                }
+                DataItem::Word(text.to_string())
+            }
+            ColumnType::Text => DataItem::Text(text.to_string()),
+            ColumnType::Long => DataItem::Long(text.parse::<i64>().map_err(|_| "Error parsing value as Long")?),
+            ColumnType::ULong => DataItem::ULong(text.parse::<u64>().map_err(|_| "Error parsing value as ULong")?),
+            ColumnType::Double => DataItem::Double(text.parse::<f64>().map_err(|_| "Error parsing value as Double")?),
+            ColumnType::Date => DataItem::Date(Date::parse(text)?),
+            ColumnType::DateTime => DataItem::DateTime(DateTime::parse(text)?),
+            ColumnType::Duration => DataItem::Duration(Duration::parse(text)?),
+            ColumnType::Decimal => DataItem::Decimal(Decimal::parse(text)?),
+            ColumnType::Bytes => DataItem::Bytes(parse_bytes(text)?),
+        })
+    }
+
+    /// # `cast`
+    /// Converts a value into a value of this type. Numbers become whole numbers by dropping their fraction and booleans are
+    /// `1` and `0` as numbers. Other values are written as text and parsed as this type. `Null` if the value does not fit this type
+    pub fn cast(&self, value: DataItem) -> DataItem {
+        let text = match (self, &value) {
+            (_, DataItem::Null) => return DataItem::Null,
+            (kind, value) if ColumnType::of(value) == Some(*kind) => return value.clone(),
+            (kind, DataItem::Boolean(e)) if kind.is_numeric() => (*e as u8).to_string(),
+            (ColumnType::Boolean, value) if value.as_f64().is_some() => (value.as_f64() != Some(0.0)).to_string(),
+            (kind, DataItem::Decimal(e)) if kind.is_whole() => (e.mantissa / 10i64.pow(e.scale as u32)).to_string(),
+            (kind, DataItem::Float(_) | DataItem::Double(_)) if kind.is_whole() => match value.as_f64() {
+                Some(number) if number.is_finite() => format!("{}", number.trunc() as i128),
+                _ => return DataItem::Null,
+            },
+            (_, value) => value.to_string(),
+        };
+        self.parse(&text).unwrap_or(DataItem::Null)
+    }
+}
+
+/// # `Column`
+/// A column of a `Schema`: its name, type and the constraints its values must meet
+/// - `nullable`: May hold `DataItem::Null`
+/// - `default`: Value given to the column when a row leaves it out
+/// - `unique`: No two rows may have the same value, missing values excluded
+/// - `min`, `max`: Smallest and biggest allowed value
+/// - `pattern`: A `Pattern` that `Word` and `Text` values must match
+/// - `check`: The text of a condition every row must not make false, and the condition it was parsed into
+/// - `indexed`: Rows are found by their value in this column through a hash index instead of a scan, see `Table::create_index`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnType,
+    pub nullable: bool,
+    pub default: Option<DataItem>,
+    pub unique: bool,
+    pub min: Option<DataItem>,
+    pub max: Option<DataItem>,
+    pub pattern: Option<Pattern>,
+    pub check: Option<(String, Expr)>,
+    /// The table whose row names the values must be, and `true` if deleting such a row also deletes the rows referencing it
+    pub reference: Option<(String, bool)>,
+    pub indexed: bool,
+}
+
+impl Column {
+    /// # `new`
+    /// Creates a column without any constraints
+    pub fn new(name: String, kind: ColumnType) -> Column {
+        Column {
+            name,
+            kind,
+            nullable: false,
+            default: None,
+            unique: false,
+            min: None,
+            max: None,
+            pattern: None,
+            check: None,
+            reference: None,
+            indexed: false,
+        }
+    }
+
+    /// # `parse`
+    /// Parses a header field written as `<type>[?]:<name>[=<default>][|<constraint>]...` where the constraints are
+    /// `unique`, `min=<value>`, `max=<value>`, `regex=<pattern>`, `check=<condition>` and `ref=<table>[:cascade]`, such as
+    /// `u:Age=18|min=0|max=150` or `w:Program|ref=programs`. `index` gives the column an index.
+    /// Values holding a `|` are written in double quotes, such as `t:Sep="|"`
+    pub fn parse(field: &str) -> Result<Column, &'static str> {
+        const ERROR: &str = "Error while reading header";
+        let (kind, rest) = field.split_once(':').ok_or(ERROR)?;
+        let (kind, nullable) = match kind.strip_suffix('?') {
+            Some(kind) => (kind, true),
+            None => (kind, false),
+        };
+        let kind = ColumnType::from_code(kind).ok_or(ERROR)?;
+
+        let parts = split_constraints(rest)?;
+        let mut parts = parts.iter().map(String::as_str);
+        let name = parts.next().unwrap_or_default();
+        let (name, default) = match name.split_once('=') {
+            Some((name, default)) => (name, Some(default)),
+            None => (name, None),
+        };
+        let mut column = Column::new(name.to_string(), kind);
+        column.nullable = nullable;
+
+        for constraint in parts {
+            match constraint.split_once('=') {
+                None if constraint == "unique" => column.unique = true,
+                None if constraint == "index" => column.indexed = true,
+                Some(("min", value)) => column.min = Some(kind.parse(value)?),
+                Some(("max", value)) => column.max = Some(kind.parse(value)?),
+                Some(("regex", pattern)) => {
+                    if !matches!(kind, ColumnType::Word | ColumnType::Text) {
+                        return Err("Only Word and Text columns can have a regex");
+                    }
+                    column.pattern = Some(Pattern::new(pattern)?);
+                }
+                Some(("check", _)) if column.check.is_some() => return Err("A column can only have one CHECK"),
+                Some(("check", condition)) => {
+                    let parsed = Expr::parse(condition).map_err(|_| "Error while reading the CHECK of a column")?;
+                    column.check = Some((condition.to_string(), parsed));
+                }
+                Some(("ref", table)) => {
+                    let reference = match table.split_once(':') {
+                        Some((table, "cascade")) => (table, true),
+                        Some((table, "restrict")) => (table, false),
+                        Some(_) => return Err("Expected cascade or restrict after the table of a reference"),
+                        None => (table, false),
+                    };
+                    if reference.0.is_empty() {
+                        return Err("Expected a table after ref=");
+                    }
+                    column.reference = Some((reference.0.to_string(), reference.1));
+                }
+                _ => return Err("Unknown column constraint in header"),
+            }
+        }
+
+        if let Some(default) = default {
+            let default = column.value(Some(default).filter(|default| *default != NULL))?;
+            column.check(&default)?;
+            column.default = Some(default);
+        }
+
+        Ok(column)
+    }
+
+    /// # `value`
+    /// Parses text into a value of this column. `None` is a missing value which gives `DataItem::Null` if the column is nullable
+    pub fn value(&self, text: Option<&str>) -> Result<DataItem, &'static str> {
+        match text {
+            Some(text) => self.kind.parse(text),
+            None if self.nullable => Ok(DataItem::Null),
+            None => Err("Column can not be NULL"),
+        }
+    }
+
+    /// # `check`
+    /// Checks that the value meets the constraints of this column, except `unique` which depends on the other rows of the table
+    pub fn check(&self, value: &DataItem) -> Result<(), &'static str> {
+        if value.is_null() {
+            return if self.nullable {
+                Ok(())
+            } else {
+                Err("Column can not be NULL")
+            };
+        }
+
+        if let Some(min) = &self.min {
+            if value.compare(min).is_none_or(|ordering| ordering.is_lt()) {
+                return Err("Value is smaller than the minimum of the column");
+            }
+        }
+        if let Some(max) = &self.max {
+            if value.compare(max).is_none_or(|ordering| ordering.is_gt()) {
+                return Err("Value is bigger than the maximum of the column");
+            }
+        }
+        if let (Some(pattern), DataItem::Word(text) | DataItem::Text(text)) = (&self.pattern, value) {
+            if !pattern.is_match(text) {
+                return Err("Value does not match the regex of the column");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Column {
+    /// Written the way it is in a header, such as `u?:Age=18|min=0`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}:{}", self.kind.code(), if self.nullable { "?" } else { "" }, self.name)?;
+        match &self.default {
+            Some(DataItem::Null) => write!(f, "={}", NULL)?,
+            Some(default) => write!(f, "={}", quote_constraint(&default.to_string()))?,
+            None => {}
+        }
+        if self.unique {
+            write!(f, "|unique")?;
+        }
+        if let Some(min) = &self.min {
+            write!(f, "|min={}", quote_constraint(&min.to_string()))?;
+        }
+        if let Some(max) = &self.max {
+            write!(f, "|max={}", quote_constraint(&max.to_string()))?;
+        }
+        if let Some(pattern) = &self.pattern {
+            write!(f, "|regex={}", quote_constraint(pattern.source()))?;
+        }
+        if let Some((condition, _)) = &self.check {
+            write!(f, "|check={}", quote_constraint(condition))?;
+        }
+        if let Some((table, cascade)) = &self.reference {
+            write!(f, "|ref={}{}", table, if *cascade { ":cascade" } else { "" })?;
+        }
+        if self.indexed {
+            write!(f, "|index")?;
+        }
+        Ok(())
+    }
+}
+
+/// # `split_constraints`
+/// Splits the part of a header field after the `:` at every `|` that is not inside a quoted value. A value is quoted when a `"`
+/// follows the first `=` of its part, with `""` for a quote inside it, and is returned without the quotes
+fn split_constraints(rest: &str) -> Result<Vec<String>, &'static str> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut part = String::new();
+    let mut chars = rest.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '|' => parts.push(std::mem::take(&mut part)),
+            '=' if !part.contains('=') && chars.next_if_eq(&'"').is_some() => {
+                part.push('=');
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.next_if_eq(&'"').is_some() => part.push('"'),
+                        Some('"') => break,
+                        Some(c) => part.push(c),
+                        None => return Err("Missing closing quote in header"),
+                    }
+                }
+                if chars.peek().is_some_and(|c| *c != '|') {
+                    return Err("Expected | after a quoted value in header");
+                }
+            }
+            c => part.push(c),
+        }
+    }
+    parts.push(part);
+    Ok(parts)
+}
+
+/// # `quote_constraint`
+/// Returns a value of a header field the way `split_constraints` reads it back, in double quotes if it holds a `|` or starts with a quote
+fn quote_constraint(value: &str) -> String {
+    if value.contains('|') || value.starts_with('"') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// # `Schema`
+/// The columns of a `Table` in order, together with a `Map` from column name to its index
+/// and the `CHECK` conditions of the columns bound to them, see `bind_checks`
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    columns: Vec<Column>,
+    indices: Map<String, usize>,
+    checks: Vec<(usize, Expr)>,
+}
+
+impl Schema {
+    /// # `parse`
+    /// Parses the fields of a header line such as `u:Age,w?:Program` into a `Schema`. `Err()` if two columns have the same name
+    pub fn parse(hdr: &[String]) -> Result<Schema, &'static str> {
+        let mut schema = Schema::default();
+        for field in hdr {
+            schema.push(Column::parse(field)?)?;
+        }
+        schema
+            .bind_checks()
+            .map_err(|_| "A CHECK in the header does not fit the columns of the table")?;
+        Ok(schema)
+    }
+
+    /// # `push`
+    /// Adds a column after the others. Its `CHECK` is only used once `bind_checks` is called after all columns are pushed
+    pub fn push(&mut self, column: Column) -> Result<(), &'static str> {
+        self.indices.insert(column.name.clone(), self.columns.len())?;
+        self.columns.push(column);
+        Ok(())
+    }
+
+    /// # `qualified`
+    /// Returns the schema of rows made of a row of each of the given tables after each other, such as those of a join.
+    /// The columns are named `<alias>.<column>` and keep only their type. A column can also be found by its own name if
+    /// no other table has a column with that name
+    pub fn qualified(tables: &[(String, Schema)]) -> Result<Schema, &'static str> {
+        let mut schema = Schema::default();
+        for (alias, table) in tables {
+            for column in table.columns() {
+                let mut qualified = Column::new(format!("{}.{}", alias, column.name), column.kind);
+                qualified.nullable = true;
+                schema.push(qualified)?;
+            }
+        }
+
+        let mut idx = 0;
+        for (alias, table) in tables {
+            for column in table.columns() {
+                let shared = tables
+                    .iter()
+                    .any(|(other, table)| other != alias && table.index_of(&column.name).is_some());
+                if !shared && !schema.indices.contains_key(&column.name) {
+                    schema.indices.insert(column.name.clone(), idx)?;
+                }
+                idx += 1;
+            }
+        }
+        Ok(schema)
+    }
+
+    /// # `replaced`
+    /// Returns the schema with the column at the index replaced by the given column
+    pub fn replaced(&self, idx: usize, column: Column) -> Result<Schema, &'static str> {
+        let mut schema = Schema::default();
+        for (other, old) in self.columns.iter().enumerate() {
+            schema.push(if other == idx { column.clone() } else { old.clone() })?;
+        }
+        Ok(schema)
+    }
+
+    /// # `bind_checks`
+    /// Binds the `CHECK` condition of every column that has one to the columns and keeps it next to the index of the column,
+    /// so rows are checked without parsing the conditions again. `Err()` if a condition does not fit the columns,
+    /// such as after a column it uses was dropped
+    pub fn bind_checks(&mut self) -> Result<(), String> {
+        let mut checks: Vec<(usize, Expr)> = Vec::new();
+        for (idx, column) in self.columns.iter().enumerate() {
+            let Some((_, condition)) = &column.check else { continue };
+            if condition.has_aggregate() {
+                return Err(format!("The CHECK of column {} can not use aggregates", column.name));
+            }
+            let condition = condition
+                .clone()
+                .bind_condition(self)
+                .map_err(|error| format!("The CHECK of column {}: {}", column.name, error))?;
+            checks.push((idx, condition));
+        }
+        self.checks = checks;
+        Ok(())
+    }
+
+    /// # `checks`
+    /// Returns the bound `CHECK` conditions next to the index of their column
+    pub fn checks(&self) -> &[(usize, Expr)] {
+        &self.checks
+    }
+
+    /// # `check_conditions`
+    /// `Err()` naming the column and its value if the `CHECK` condition of a column is false for the row.
+    /// A condition that is unknown, such as one comparing with `NULL`, is met
+    pub fn check_conditions(&self, row: &[DataItem]) -> Result<(), String> {
+        for (idx, condition) in self.checks.iter() {
+            if condition.truth(row) == Some(false) {
+                let column = &self.columns[*idx];
+                let text = column.check.as_ref().map_or("", |(text, _)| text.as_str());
+                return Err(format!("Value {} of column {} breaks its CHECK ({})", row[*idx], column.name, text));
+            }
+        }
+        Ok(())
+    }
+
+    /// # `len`
+    /// Returns the amount of columns
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// # `is_empty`
+    /// Returns `true` if there are no columns
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// # `columns`
+    /// Returns the columns in order
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// # `index_of`
+    /// Returns the index of the column with the given name
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.indices.get(name.to_string())
+    }
+
+    /// # `fields`
+    /// Returns every column written the way it is in a header
+    pub fn fields(&self) -> Vec<String> {
+        self.columns.iter().map(Column::to_string).collect()
+    }
+}
@@ -1,88 +1,278 @@
-use super::DataItem;
-use crate::hash_map::Map;
+use super::csv::{parse_records, quote, NULL};
+use super::{Accumulator, Column, ColumnType, CompareOp, DataItem, Expr, GroupKey, Schema};
+use crate::hash_map::{DiskMap, Map, DISK_MAGIC};
+use std::cmp::Ordering;
 use std::fs::*;
-use std::io::{BufRead, Write};
+use std::io::{Read, Seek, Write};
+
+/// # `DISK_EXTENSION`
+/// Tables stored in files with this extension are saved as a `DiskMap` rather than a .csv file
+pub const DISK_EXTENSION: &str = ".hdb";
 
 /// # `SearchResult`
 /// Type that mostly used when returning results from requesting data from the table
 type SearchResult = Vec<(String, Option<Vec<DataItem>>)>;
 
+/// # `Index`
+/// A hash index of a column: the names of the rows holding each value, in the order they got that value. `NULL`s are not indexed
+type Index = Map<GroupKey, Map<String, ()>>;
+
+/// # `UndoLog`
+/// The changes of rows made in a transaction in the order they were made, each the name of the row and the content it had before,
+/// `None` if it did not exist
+type UndoLog = Vec<(String, Option<Vec<DataItem>>)>;
+
+/// # `NO_TRANSACTION`
+/// Error of ending a transaction or using a savepoint when no transaction is open
+const NO_TRANSACTION: &str = "No transaction is open, start one with BEGIN";
+
+/// # `ordering`
+/// Orders two values of a column that is sorted by, ascending or descending
+fn ordering(a: &DataItem, b: &DataItem, descending: bool) -> Ordering {
+    let ordering = a.total_cmp(b);
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
 /// # `Table`
-/// A structure that holds represents a Table. The `Table` contains a header which gives names to each column.
-/// It also holds a `Map` which correlates a row name as `String` to a vector of `DataItem`s which represents a row in a table.
+/// A structure that holds represents a Table. The `Table` contains a `Schema` which gives names, types and constraints to each column.
+/// It also holds a `Storage` which correlates a row name as `String` to a vector of `DataItem`s which represents a row in a table.
+/// Every indexed column has an `Index` next to its index in the schema, kept up to date as rows change.
+/// While a `Database` checks a change, `references` holds the values written to columns referencing other tables, see `Database::change`.
+/// While a transaction is open `journal` holds its `UndoLog`, and each savepoint is its name next to the length of the log when it was made.
 #[derive(Debug)]
 pub struct Table {
     path: String,
-    header_idx_map: Map<String, usize>,
-    header: Vec<(String, DataItem)>,
-    map: Map<String, Vec<DataItem>>,
+    schema: Schema,
+    map: Storage,
+    indexes: Vec<(usize, Index)>,
+    references: Option<Vec<(usize, DataItem)>>,
+    journal: Option<UndoLog>,
+    savepoints: Vec<(String, usize)>,
+}
+
+/// # `Storage`
+/// Where the rows of a `Table` are kept.
+/// - `Memory`: Every row is read from a .csv file into a `Map` when the table is opened, the whole file is written again on save
+/// - `Disk`: Rows stay in a `DiskMap` file and are only read when needed. Changes are written into the file as they are made
+#[derive(Debug)]
+enum Storage {
+    Memory(Map<String, Vec<DataItem>>),
+    Disk(DiskMap<String, Vec<DataItem>>),
+}
+
+impl Storage {
+    /// # `get`
+    /// Returns the row with the given name as `Ok(Option<Vec<DataItem>>)`. `None` if no such row exists
+    fn get(&self, row_name: &String) -> Result<Option<Vec<DataItem>>, &'static str> {
+        match self {
+            Storage::Memory(map) => Ok(map.get_ref(row_name).cloned()),
+            Storage::Disk(map) => map.get(row_name),
+        }
+    }
+
+    /// # `insert`
+    /// Inserts a new row. `Err()` is returned if a row with that name already exists
+    fn insert(&mut self, row_name: String, row: Vec<DataItem>) -> Result<(), &'static str> {
+        match self {
+            Storage::Memory(map) => map.insert(row_name, row),
+            Storage::Disk(map) => map.insert(row_name, row),
+        }
+    }
+
+    /// # `set`
+    /// Replaces the content of an existing row and returns the previous content
+    fn set(&mut self, row_name: String, row: Vec<DataItem>) -> Result<Vec<DataItem>, &'static str> {
+        match self {
+            Storage::Memory(map) => map.set(row_name, row),
+            Storage::Disk(map) => map.set(row_name, row),
+        }
+    }
+
+    /// # `remove`
+    /// Removes a row and returns its content
+    fn remove(&mut self, row_name: String) -> Result<Vec<DataItem>, &'static str> {
+        match self {
+            Storage::Memory(map) => map.remove(row_name),
+            Storage::Disk(map) => map.remove(row_name),
+        }
+    }
+
+    /// # `row_names`
+    /// Returns the names of every row. Rows in memory are given in the order they were inserted
+    fn row_names(&mut self) -> Result<Vec<String>, &'static str> {
+        match self {
+            Storage::Memory(map) => Ok(map.keys().clone()),
+            Storage::Disk(map) => map.keys(),
+        }
+    }
 }
 
 impl Table {
     /// # `new`
     /// Takes a given `String` of a file path to a .csv file containg a table that follow this Table's rules (Read README)
-    /// a `Result<Table, &'static str>` where `Ok(Table)` is return if no errors arise (such as giving columns with the same name).
+    /// a `Result<Table, String>` where `Ok(Table)` is return if no errors arise (such as giving columns with the same name).
     /// If there is an error it is returned as `Err()`
-    pub fn new(path: String) -> Result<Table, &'static str> {
-        let mut header_idx_map: Map<String, usize> = Map::new();
-        let mut header: Vec<(String, DataItem)> = Vec::new();
-
+    /// Files written by `save_as` with the `.hdb` extension are opened as disk tables, only their header is read until rows are requested.
+    pub fn new(path: String) -> Result<Table, String> {
         // Get table file
-        if let Ok(file) = File::open(path.clone()) {
-            let mut lines = std::io::BufReader::new(file).lines();
+        if let Ok(mut file) = File::open(path.clone()) {
+            let mut magic = [0u8; 4];
+            if file.read_exact(&mut magic).is_ok() && magic == *DISK_MAGIC {
+                return Ok(Table::open_disk(path)?);
+            }
+            let mut content = String::new();
+            file.rewind()
+                .and_then(|_| file.read_to_string(&mut content))
+                .map_err(|_| "Error while reading table file")?;
+            let mut records = parse_records(&content)?.into_iter();
 
             // Read header of table
-            if let Some(Ok(hdr)) = lines.next() {
-                // Read columns of header and prepare them for parsing
-                let columns = hdr.split(',').map(|data| data.trim().split_at(2));
-
-                // Go through each column and parse them into the header in this struct
-                for col in columns.enumerate() {
-                    let col_data = match col.1 .0 {
-                        "w:" => (col.1 .1.to_string(), DataItem::Word(String::new())),
-                        "b:" => (col.1 .1.to_string(), DataItem::Boolean(false)),
-                        "u:" => (col.1 .1.to_string(), DataItem::UInteger(0)),
-                        "i:" => (col.1 .1.to_string(), DataItem::Integer(0)),
-                        "f:" => (col.1 .1.to_string(), DataItem::Float(0.0)),
-                        _ => return Err("Error while reading header"),
-                    };
-
-                    header.push(col_data);
-                    header_idx_map.insert(col.1 .1.to_string(), col.0)?;
-                }
-            }
+            let schema = if let Some(hdr) = records.next() {
+                Schema::parse(&Table::header_fields(hdr))?
+            } else {
+                Schema::default()
+            };
 
             let mut table = Table {
                 path: path.clone(),
-                header_idx_map,
-                header: Vec::from(header),
-                map: Map::new(),
+                schema,
+                map: Storage::Memory(Map::new()),
+                indexes: Vec::new(),
+                references: None,
+                journal: None,
+                savepoints: Vec::new(),
             };
 
-            // Go through each row and inserting  their data into this struct's map
-            for line in lines {
-                if let Ok(line) = line {
-                    let mut col_data = line.split(',').map(|data| data.trim().to_string());
-                    table.new_row(
-                        if let Some(item) = col_data.next() {
-                            item
-                        } else {
-                            break;
-                        },
-                        col_data.collect::<Vec<String>>(),
-                    )?;
-                }
+            // Go through each row and inserting  their data into this struct's map.
+            // Values referencing other tables are not looked up, as those tables may not be open yet
+            table.collect_references();
+            for record in records {
+                let mut col_data = record.into_iter();
+                table.new_row(
+                    match col_data.next() {
+                        Some(Some(item)) => item,
+                        Some(None) => return Err("Row name can not be NULL".to_string()),
+                        None => break,
+                    },
+                    col_data.collect::<Vec<Option<String>>>(),
+                )?;
             }
+            table.take_references();
+            table.build_indexes()?;
 
             return Ok(table);
         }
 
-        Err("File not found")
+        Err("File not found".to_string())
+    }
+
+    /// # `create`
+    /// Creates a new empty table with the given columns and writes it to a .csv file at the path.
+    /// `Err()` if a file already exists at the path
+    pub fn create(path: String, schema: Schema) -> Result<Table, &'static str> {
+        if std::path::Path::new(&path).exists() {
+            return Err("A file with the name of the table already exists");
+        }
+        let mut table = Table {
+            path,
+            schema,
+            map: Storage::Memory(Map::new()),
+            indexes: Vec::new(),
+            references: None,
+            journal: None,
+            savepoints: Vec::new(),
+        };
+        table.build_indexes()?;
+        table.save()?;
+        Ok(table)
+    }
+
+    /// # `from_rows`
+    /// Creates a table that is only kept in memory from a schema and its rows, such as the result of a join. The rows are not
+    /// checked against the constraints of the schema. `Err()` if two rows have the same name
+    pub fn from_rows(schema: Schema, rows: Vec<(String, Vec<DataItem>)>) -> Result<Table, &'static str> {
+        let mut map = Map::new();
+        for (row_name, row) in rows {
+            map.insert(row_name, row)?;
+        }
+        Ok(Table {
+            path: String::new(),
+            schema,
+            map: Storage::Memory(map),
+            indexes: Vec::new(),
+            references: None,
+            journal: None,
+            savepoints: Vec::new(),
+        })
+    }
+
+    /// # `open_disk`
+    /// Opens a table stored as a `DiskMap`. The header of the table is kept in the metadata of the `DiskMap`
+    fn open_disk(path: String) -> Result<Table, &'static str> {
+        let map: DiskMap<String, Vec<DataItem>> = DiskMap::open(&path)?;
+        let hdr = std::str::from_utf8(map.metadata()).map_err(|_| "Error while reading header")?;
+        let hdr = parse_records(hdr)?.into_iter().next().unwrap_or_default();
+        let schema = Schema::parse(&Table::header_fields(hdr))?;
+
+        let mut table = Table {
+            path,
+            schema,
+            map: Storage::Disk(map),
+            indexes: Vec::new(),
+            references: None,
+            journal: None,
+            savepoints: Vec::new(),
+        };
+        table.build_indexes()?;
+        Ok(table)
+    }
+
+    /// # `path`
+    /// Returns the path of the file the table is saved to
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// # `schema`
+    /// Returns the columns of the table
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// # `header_fields`
+    /// Turns the fields of a header record back into text, the header has no missing values so an unquoted `NULL` is just a name
+    fn header_fields(hdr: Vec<Option<String>>) -> Vec<String> {
+        hdr.into_iter()
+            .map(|field| field.unwrap_or_else(|| NULL.to_string()))
+            .collect()
+    }
+
+    /// # `header_line`
+    /// Returns the header in the format it is stored in files, such as `u:Age,w:Program`
+    fn header_line(&self) -> String {
+        self.schema
+            .fields()
+            .iter()
+            .map(|field| quote(field))
+            .collect::<Vec<String>>()
+            .join(",")
     }
 
     /// # `save`
     /// Saves the current table to the file it was opened from.
+    /// Disk tables are already up to date since changes are written as they are made, this only makes sure they reached the disk.
     pub fn save(&mut self) -> Result<(), &'static str> {
+        if self.journal.is_some() {
+            return Err("Tables can not be saved inside a transaction, COMMIT or ROLLBACK first");
+        }
+        if let Storage::Disk(map) = &mut self.map {
+            return map.flush();
+        }
+
         let file = OpenOptions::new()
             .create(true)
             .write(true)
@@ -95,204 +285,939 @@ impl Table {
         let mut file = file.unwrap();
 
         // Entire file saved here first
-        let mut buffer = String::new();
-
-        // Write header
-        for column in self.header.iter() {
-            match column.1 {
-                DataItem::Boolean(_) => buffer.push_str("b:"),
-                DataItem::Word(_) => buffer.push_str("w:"),
-                DataItem::UInteger(_) => buffer.push_str("u:"),
-                DataItem::Integer(_) => buffer.push_str("i:"),
-                DataItem::Float(_) => buffer.push_str("f:"),
-            }
-            buffer.push_str(format!("{},", column.0).as_str());
-        }
-        buffer.pop(); // remove the last ,
+        let mut buffer = self.header_line();
 
         // Get rows, iterate through them and save their data to the table
-        let keys = self.map.keys().clone();
+        let keys = self.map.row_names()?;
         for key in keys {
-            buffer.push_str(format!("\n{}", key).as_str());
-            if let Some(row) = self.map.get(key) {
+            buffer.push_str(format!("\n{}", quote(&key)).as_str());
+            if let Some(row) = self.map.get(&key)? {
                 for data in row {
-                    buffer.push_str(format!(",{}", data.to_string()).as_str());
+                    if data.is_null() {
+                        buffer.push_str(format!(",{}", NULL).as_str());
+                    } else {
+                        buffer.push_str(format!(",{}", quote(&data.to_string())).as_str());
+                    }
                 }
             }
         }
 
-        if let Err(_) = file.write(buffer.as_bytes()) {
+        if file.write_all(buffer.as_bytes()).is_err() {
             return Err("Error writing to file");
         }
 
         Ok(())
     }
 
+    /// # `save_as`
+    /// Saves the table into a new file and continues working on that file from now on.
+    /// If the path ends with `.hdb` the table is saved as a disk table, otherwise as a .csv file.
+    pub fn save_as(&mut self, path: String) -> Result<(), &'static str> {
+        if self.journal.is_some() {
+            return Err("Tables can not be saved inside a transaction, COMMIT or ROLLBACK first");
+        }
+        let keys = self.map.row_names()?;
+
+        if path.ends_with(DISK_EXTENSION) {
+            let mut disk: DiskMap<String, Vec<DataItem>> =
+                DiskMap::create(&path, 31, self.header_line().into_bytes())?;
+            for key in keys {
+                if let Some(row) = self.map.get(&key)? {
+                    disk.insert(key, row)?;
+                }
+            }
+            disk.flush()?;
+            self.map = Storage::Disk(disk);
+        } else {
+            let mut memory: Map<String, Vec<DataItem>> = Map::new();
+            for key in keys {
+                if let Some(row) = self.map.get(&key)? {
+                    memory.insert(key, row)?;
+                }
+            }
+            self.map = Storage::Memory(memory);
+        }
+
+        self.path = path;
+        self.save()
+    }
+
     /// # `get`
     /// Takes a `&[String]` representing the name columns to extract and another `&[String]` representing which rows to extract those columns from.
     /// This returns a `SearchResult` which is a vector containing tuples of `(String, Option<Vec<DataItem>>)` in which string represents the row name
     /// and the `Option<Vec<DataItem>>` represents the extract column data from each row, if it exists.
-    pub fn get(&mut self, columns: &[String], rows: &[String]) -> SearchResult {
-        let mut result: SearchResult = SearchResult::new();
-
-        let mut col_idx: Vec<usize>;
-        if columns.contains(&"*".to_string()) && columns.len() == 1 {
-            col_idx = (0..self.header.len()).into_iter().collect();
+    /// If a filter is given, only existing rows for which it is true are returned. `Err()` if the filter does not fit the columns of the table.
+    /// Rows are sorted by the columns of `order`, each `true` if descending, as in `select`
+    pub fn get(
+        &mut self,
+        columns: &[String],
+        rows: &[String],
+        filter: Option<&Expr>,
+        order: &[(String, bool)],
+    ) -> Result<SearchResult, String> {
+        let columns = if columns.contains(&"*".to_string()) && columns.len() == 1 {
+            None
         } else {
-            // Get indices of columns
-            col_idx = Vec::with_capacity(columns.len());
-            for col in columns {
-                if let Some(idx) = self.header_idx_map.get(col.to_string()) {
-                    col_idx.push(idx);
-                }
-            }
+            // Columns that do not exist are left out
+            Some(
+                columns
+                    .iter()
+                    .filter_map(|column| self.schema.index_of(column).map(Expr::Column))
+                    .collect::<Vec<Expr>>(),
+            )
+        };
+        let order = order
+            .iter()
+            .map(|(column, descending)| (Expr::Identifier(column.clone()), *descending))
+            .collect::<Vec<(Expr, bool)>>();
+
+        self.select(columns.as_deref(), rows, filter, &order)
+    }
+
+    /// # `select`
+    /// Computes the given expressions, or every column for `None`, for each of the requested rows for which the filter is true.
+    /// Rows are sorted by the values of the expressions of `order`, each `true` if descending, using `DataItem::total_cmp`.
+    /// The sort is stable so rows that are equal keep the order they were requested or inserted in, rows that do not exist come last.
+    /// `Err()` if an expression does not fit the columns of the table or uses an aggregate, see `aggregate` for those
+    pub fn select(
+        &mut self,
+        columns: Option<&[Expr]>,
+        rows: &[String],
+        filter: Option<&Expr>,
+        order: &[(Expr, bool)],
+    ) -> Result<SearchResult, String> {
+        let without_aggregates = |expr: &Expr| match expr.has_aggregate() {
+            true => Err("Aggregates can only be used in GET, with GROUP BY or over every row".to_string()),
+            false => Ok(expr.clone()),
+        };
+        let columns = match columns {
+            Some(columns) => columns
+                .iter()
+                .map(|column| without_aggregates(column)?.bind_column(&self.schema))
+                .collect::<Result<Vec<Expr>, String>>()?,
+            None => (0..self.schema.len()).map(Expr::Column).collect(),
+        };
+        let order = order
+            .iter()
+            .map(|(expr, descending)| Ok((without_aggregates(expr)?.bind(&self.schema)?, *descending)))
+            .collect::<Result<Vec<(Expr, bool)>, String>>()?;
+
+        // Fetch rows (as requested) together with the values they are sorted by
+        let mut found_rows = self
+            .fetch(rows, filter)?
+            .into_iter()
+            .map(|(row, found)| {
+                let keys = found.as_ref().map(|r| order.iter().map(|(expr, _)| expr.value(r)).collect::<Vec<DataItem>>());
+                (keys, row, found)
+            })
+            .collect::<Vec<_>>();
+
+        if !order.is_empty() {
+            found_rows.sort_by(|(a, _, _), (b, _, _)| match (a, b) {
+                (Some(a), Some(b)) => a
+                    .iter()
+                    .zip(b.iter())
+                    .zip(order.iter())
+                    .map(|((a, b), (_, descending))| ordering(a, b, *descending))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
         }
 
+        // Compute the requested columns, row by row
+        Ok(found_rows
+            .into_iter()
+            .map(|(_, row, found)| {
+                let row_res = found.map(|r| columns.iter().map(|column| column.value(&r)).collect::<Vec<DataItem>>());
+                (row, row_res)
+            })
+            .collect())
+    }
+
+    /// # `fetch`
+    /// Returns the requested rows, or every row for `*`, that exist and for which the filter is true. Rows that do not exist are
+    /// `None` and only returned without a filter. `Err()` if the filter does not fit the columns of the table or uses an aggregate
+    fn fetch(&mut self, rows: &[String], filter: Option<&Expr>) -> Result<SearchResult, String> {
+        if filter.is_some_and(Expr::has_aggregate) {
+            return Err("Aggregates can not be used in WHERE, use HAVING instead".to_string());
+        }
+        let filter = filter.map(|filter| filter.clone().bind_condition(&self.schema)).transpose()?;
+
         let rows = if rows.contains(&"*".to_string()) && rows.len() == 1 {
-            (*self.map.keys()).clone()
+            match filter.as_ref().and_then(|filter| self.lookup(filter)) {
+                Some(found) => found,
+                None => self.map.row_names()?,
+            }
         } else {
             rows.to_vec()
         };
-        let size = rows.len();
-        // Fetch columns, row by row (as requested)
+
+        let mut found_rows: SearchResult = Vec::with_capacity(rows.len());
         for row in rows.into_iter() {
-            if let Some(r) = self.map.get(row.to_string()) {
-                let mut row_res: Vec<DataItem> = Vec::with_capacity(size);
-                for idx in col_idx.iter() {
-                    row_res.push(r[*idx].clone());
+            let found = self.map.get(&row)?;
+            if let Some(filter) = &filter {
+                if found.as_ref().and_then(|r| filter.truth(r)) != Some(true) {
+                    continue;
                 }
-                result.push((row.to_string(), Some(row_res)));
-            } else {
-                result.push((row.to_string(), None));
             }
+            found_rows.push((row, found));
         }
+        Ok(found_rows)
+    }
 
-        result
+    /// # `aggregate`
+    /// Computes the given columns, which may hold aggregates such as `COUNT(*)`, over the requested rows for which the filter is true.
+    /// The rows are put in groups with equal values in the `group_by` columns, or in a single group if there are none, and each
+    /// group gives one row of the result. Groups are kept if `having` is true for them and sorted by `order` like in `get`,
+    /// otherwise they come in the order their first row was found. `Err()` if a column is used outside of an aggregate without
+    /// being grouped by, if an aggregate can not be used on its values or if a sum gets too big
+    pub fn aggregate(
+        &mut self,
+        columns: &[Expr],
+        rows: &[String],
+        filter: Option<&Expr>,
+        group_by: &[String],
+        having: Option<&Expr>,
+        order: &[(Expr, bool)],
+    ) -> Result<Vec<Vec<DataItem>>, String> {
+        let grouped = group_by
+            .iter()
+            .map(|column| self.schema.index_of(column).ok_or_else(|| format!("No such column: {}", column)))
+            .collect::<Result<Vec<usize>, String>>()?;
+
+        // Aggregates are computed into extra columns after the columns of the table, in the row of each group
+        let mut aggregates = Vec::new();
+        let mut split = |expr: Expr| expr.split_aggregates(&self.schema, &grouped, &mut aggregates);
+        let columns = columns
+            .iter()
+            .map(|column| split(column.clone().bind_column(&self.schema)?))
+            .collect::<Result<Vec<Expr>, String>>()?;
+        let having = having
+            .map(|having| split(having.clone().bind_condition(&self.schema)?))
+            .transpose()?;
+        let order = order
+            .iter()
+            .map(|(expr, descending)| Ok((split(expr.clone().bind(&self.schema)?)?, *descending)))
+            .collect::<Result<Vec<(Expr, bool)>, String>>()?;
+
+        let new_group = |row: Vec<DataItem>| {
+            let accumulators = aggregates
+                .iter()
+                .map(|(aggregate, _, distinct)| Accumulator::new(*aggregate, *distinct))
+                .collect::<Vec<Accumulator>>();
+            (row, accumulators)
+        };
+        let mut groups: Map<GroupKey, (Vec<DataItem>, Vec<Accumulator>)> = Map::new();
+        if grouped.is_empty() {
+            // Without GROUP BY every row is in one group, which also exists if there are no rows
+            groups.insert(GroupKey(Vec::new()), new_group(vec![DataItem::Null; self.schema.len()]))?;
+        }
+
+        for (_, row) in self.fetch(rows, filter)? {
+            let Some(row) = row else {
+                continue;
+            };
+            let key = GroupKey(grouped.iter().map(|idx| row[*idx].clone()).collect());
+            if !groups.contains_key(&key) {
+                groups.insert(key.clone(), new_group(row.clone()))?;
+            }
+            if let Some((_, accumulators)) = groups.get_mut(&key) {
+                for (accumulator, (_, arg, _)) in accumulators.iter_mut().zip(aggregates.iter()) {
+                    // COUNT(*) counts every row, NULL or not
+                    accumulator.add(arg.as_ref().map_or(DataItem::Boolean(true), |arg| arg.value(&row)))?;
+                }
+            }
+        }
+
+        let mut result: Vec<(Vec<DataItem>, Vec<DataItem>)> = Vec::with_capacity(groups.len());
+        for key in groups.keys().clone() {
+            let (mut row, accumulators) = groups.remove(key)?;
+            for accumulator in accumulators {
+                row.push(accumulator.finish()?);
+            }
+            if having.as_ref().is_some_and(|having| having.truth(&row) != Some(true)) {
+                continue;
+            }
+            result.push((
+                order.iter().map(|(expr, _)| expr.value(&row)).collect(),
+                columns.iter().map(|column| column.value(&row)).collect(),
+            ));
+        }
+
+        result.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b.iter())
+                .zip(order.iter())
+                .map(|((a, b), (_, descending))| ordering(a, b, *descending))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(result.into_iter().map(|(_, row)| row).collect())
     }
 
     /// # `set`
-    /// Takes a given row name as `String` and a vector `Vec<(String, String)>` containing pairs of header name and what data they should change to.
+    /// Takes a given row name as `String` and a vector `Vec<(String, Option<String>)>` containing pairs of header name and what data they should change to.
+    /// `None` sets the column to `DataItem::Null`, which is only allowed in nullable columns.
+    /// Nothing is changed if any of the values does not meet the constraints of its column.
     pub fn set(
         &mut self,
         row_name: String,
-        content: Vec<(String, String)>,
-    ) -> Result<(), &'static str> {
+        content: Vec<(String, Option<String>)>,
+    ) -> Result<(), String> {
         // Get Row
-        if let Some(mut row) = self.map.get(row_name.clone()) {
+        if let Some(mut row) = self.map.get(&row_name)? {
             // Every content given to change
-            for item in content.iter() {
+            for (column, text) in content.iter() {
                 // Get index of header to change
-                if let Some(idx) = self.header_idx_map.get(item.0.to_string()) {
-                    // Get content to set the row data to
-                    if let Some(current_content) = content.get(idx) {
-                        // Get header data type
-                        if let Some(value) = self.header.get(idx) {
-                            let value = match value.1 {
-                                DataItem::Boolean(_) => DataItem::Boolean(
-                                    if let Ok(value) = current_content.1.parse::<bool>() {
-                                        value
-                                    } else {
-                                        return Err("Error parsing value as Boolean");
-                                    },
-                                ),
-                                DataItem::Float(_) => DataItem::Float(
-                                    if let Ok(value) = current_content.1.parse::<f32>() {
-                                        value
-                                    } else {
-                                        return Err("Error parsing value as Float");
-                                    },
-                                ),
-                                DataItem::UInteger(_) => DataItem::UInteger(
-                                    if let Ok(value) = current_content.1.parse::<u32>() {
-                                        value
-                                    } else {
-                                        return Err("Error parsing value as UInteger");
-                                    },
-                                ),
-                                DataItem::Integer(_) => DataItem::Integer(
-                                    if let Ok(value) = current_content.1.parse::<i32>() {
-                                        value
-                                    } else {
-                                        return Err("Error parsing value as Integer");
-                                    },
-                                ),
-                                DataItem::Word(_) => DataItem::Word(current_content.1.to_string()),
-                            };
-                            row[idx] = value;
-                        }
-                    }
+                if let Some(idx) = self.schema.index_of(column) {
+                    row[idx] = self.schema.columns()[idx].value(text.as_deref())?;
                 }
             }
-            self.map.set(row_name, row)?;
+            self.check_row(&row_name, &row)?;
+            self.set_stored(row_name, row)?;
         }
 
         Ok(())
     }
 
+    /// # `update`
+    /// Sets columns of the requested rows, or every row for `*`, for which the filter is true. Each change is a column and an
+    /// expression that is computed from the row before it changes, so `Age = Age + 1` adds one to every age. Numbers are converted
+    /// to the type of their column, dropping a fraction for whole numbers. Returns the number of rows changed.
+    /// Nothing is changed if any new value does not fit its column or does not meet its constraints
+    pub fn update(&mut self, changes: Vec<(String, Expr)>, rows: &[String], filter: Option<&Expr>) -> Result<usize, String> {
+        let mut bound: Vec<(usize, Expr)> = Vec::with_capacity(changes.len());
+        for (column, expr) in changes {
+            let idx = self.schema.index_of(&column).ok_or_else(|| format!("No such column: {}", column))?;
+            if bound.iter().any(|(other, _)| *other == idx) {
+                return Err(format!("Column {} is set more than once", column));
+            }
+            if expr.has_aggregate() {
+                return Err("Aggregates can not be used in SET".to_string());
+            }
+            bound.push((idx, expr.bind_value(&self.schema, self.schema.columns()[idx].kind)?));
+        }
+
+        // Every new row is computed before any is written, so a bad value leaves the table as it was
+        let mut updated: Vec<(String, Vec<DataItem>)> = Vec::new();
+        for (row_name, found) in self.fetch(rows, filter)? {
+            let Some(old) = found else { continue };
+            let mut row = old.clone();
+            for (idx, expr) in bound.iter() {
+                let column = &self.schema.columns()[*idx];
+                let value = expr.value(&old);
+                row[*idx] = match column.kind.cast(value.clone()) {
+                    DataItem::Null if !value.is_null() => {
+                        return Err(format!("{} does not fit column {} of type {:?}", value, column.name, column.kind))
+                    }
+                    value => value,
+                };
+            }
+            updated.push((row_name, row));
+        }
+
+        // Constraints are checked against the rows written so far, and the old rows are put back if one fails
+        let mut written: Vec<(String, Vec<DataItem>)> = Vec::with_capacity(updated.len());
+        for (row_name, row) in updated {
+            let result = self
+                .check_row(&row_name, &row)
+                .and_then(|_| Ok(self.set_stored(row_name.clone(), row)?));
+            match result {
+                Ok(old) => written.push((row_name, old)),
+                Err(error) => {
+                    for (row_name, old) in written.into_iter().rev() {
+                        self.set_stored(row_name, old)?;
+                    }
+                    return Err(format!("Row {}: {}", row_name, error));
+                }
+            }
+        }
+        Ok(written.len())
+    }
+
     /// # `new_row`
-    /// Takes a row name as `&String` and its content as `Vec<String>` and inserts that row into the table.
-    /// This then returns `Result<(), &'static str>` where `OK(())` is if the item is inserted, otherwise `Err()` with the error.
+    /// Takes a row name as `&String` and its content as `Vec<Option<String>>` and inserts that row into the table.
+    /// This then returns `Result<(), String>` where `OK(())` is if the item is inserted, otherwise `Err()` with the error.
     /// The content `Vec` must contain the content in order in which they appear in the header.
     /// That is if the header has [UInteger, Boolean, String] then the content `Vec` must be in that order, otherwise `Err()` is returned
     /// and the row is not inserted.
-    pub fn new_row(&mut self, row_name: String, content: Vec<String>) -> Result<(), &'static str> {
+    /// `None` is a missing value and gives `DataItem::Null`, only nullable columns may be missing.
+    /// Columns at the end may be left out if they have a default value or are nullable.
+    pub fn new_row(&mut self, row_name: String, content: Vec<Option<String>>) -> Result<(), String> {
         // Incorrect row size check
-        if content.len() != self.header.len() {
-            return Err("Incorrect amount of column data given");
+        if content.len() > self.schema.len() {
+            return Err("Incorrect amount of column data given".to_string());
+        }
+
+        let mut given: Vec<Option<Option<String>>> = content.into_iter().map(Some).collect();
+        given.resize(self.schema.len(), None);
+        self.insert_given(row_name, given)
+    }
+
+    /// # `new_row_named`
+    /// Takes a row name as `String` and pairs of column name and value as `Vec<(String, Option<String>)>` and inserts that row into the table.
+    /// The columns may be given in any order. Columns left out get their default value, or `DataItem::Null` if they are nullable.
+    /// `Err()` lists the columns that were left out but have neither.
+    pub fn new_row_named(&mut self, row_name: String, content: Vec<(String, Option<String>)>) -> Result<(), String> {
+        let mut given: Vec<Option<Option<String>>> = vec![None; self.schema.len()];
+        for (column, text) in content {
+            let idx = self
+                .schema
+                .index_of(&column)
+                .ok_or_else(|| format!("No such column: {}", column))?;
+            if given[idx].replace(text).is_some() {
+                return Err(format!("Column {} is given more than once", column));
+            }
+        }
+
+        self.insert_given(row_name, given)
+    }
+
+    /// # `insert_given`
+    /// Inserts a row from the value given for each column, `None` for columns that were left out
+    fn insert_given(&mut self, row_name: String, given: Vec<Option<Option<String>>>) -> Result<(), String> {
+        let missing = self
+            .schema
+            .columns()
+            .iter()
+            .zip(&given)
+            .filter(|(column, text)| text.is_none() && column.default.is_none() && !column.nullable)
+            .map(|(column, _)| column.name.as_str())
+            .collect::<Vec<&str>>();
+        if !missing.is_empty() {
+            return Err(format!("Missing values for required columns: {}", missing.join(", ")));
         }
 
-        let mut converted_data: Vec<DataItem> = Vec::with_capacity(content.len());
         // Incorrect types in row check
-        for idx in 0..content.len() {
-            if let Some(value) = self.header.get(idx) {
-                if let Some(item) = content.get(idx) {
-                    match value.1 {
-                        DataItem::Boolean(_) => converted_data.push(DataItem::Boolean(
-                            if let Ok(value) = item.parse::<bool>() {
-                                value
-                            } else {
-                                return Err("Error parsing value as Boolean");
-                            },
-                        )),
-                        DataItem::Float(_) => converted_data.push(DataItem::Float(
-                            if let Ok(value) = item.parse::<f32>() {
-                                value
-                            } else {
-                                return Err("Error parsing value as Float");
-                            },
-                        )),
-                        DataItem::UInteger(_) => converted_data.push(DataItem::UInteger(
-                            if let Ok(value) = item.parse::<u32>() {
-                                value
-                            } else {
-                                return Err("Error parsing value as UInteger");
-                            },
-                        )),
-                        DataItem::Integer(_) => converted_data.push(DataItem::Integer(
-                            if let Ok(value) = item.parse::<i32>() {
-                                value
-                            } else {
-                                return Err("Error parsing value as Integer");
-                            },
-                        )),
-                        DataItem::Word(_) => converted_data.push(DataItem::Word(
-                            if let Some(content) = content.get(idx) {
-                                content.clone()
-                            } else {
-                                return Err("Error parsing value as Word");
-                            },
-                        )),
+        let converted_data = self
+            .schema
+            .columns()
+            .iter()
+            .zip(given)
+            .map(|(column, text)| match (text, &column.default) {
+                (Some(text), _) => column.value(text.as_deref()),
+                (None, Some(default)) => Ok(default.clone()),
+                (None, None) => Ok(DataItem::Null),
+            })
+            .collect::<Result<Vec<DataItem>, &'static str>>()?;
+
+        self.check_row(&row_name, &converted_data)?;
+        Ok(self.insert_stored(row_name.to_string(), converted_data)?)
+    }
+
+    /// # `check_row`
+    /// Checks that every value of the row meets the constraints of its column and that no `CHECK` condition is false for it.
+    /// Values of columns referencing another table are added to `references` for the `Database` to look up, see `Database::change`.
+    /// Values of `unique` columns are compared with the rows holding the same value according to the index of the column,
+    /// or with the values of every other row if it has none. `Err()` names the column and value that break a constraint
+    fn check_row(&mut self, row_name: &String, row: &[DataItem]) -> Result<(), String> {
+        for (column, value) in self.schema.columns().iter().zip(row) {
+            column
+                .check(value)
+                .map_err(|error| format!("Value {} of column {}: {}", value, column.name, error))?;
+        }
+        self.schema.check_conditions(row)?;
+        for (idx, column) in self.schema.columns().iter().enumerate() {
+            let (Some((table, _)), false) = (&column.reference, row[idx].is_null()) else { continue };
+            match &mut self.references {
+                Some(references) => references.push((idx, row[idx].clone())),
+                None => {
+                    return Err(format!(
+                        "Column {} references table {}, so it can only be given a value through a database",
+                        column.name, table
+                    ))
+                }
+            }
+        }
+
+        let (indexed, scanned): (Vec<usize>, Vec<usize>) = (0..self.schema.len())
+            .filter(|idx| self.schema.columns()[*idx].unique && !row[*idx].is_null())
+            .partition(|idx| self.schema.columns()[*idx].indexed);
+        let duplicate = |table: &Table, idx: usize, other_name: &String| {
+            Err(format!(
+                "Value {} of column {} must be unique but row {} already has it",
+                row[idx],
+                table.schema.columns()[idx].name,
+                other_name
+            ))
+        };
+
+        for idx in indexed {
+            for other_name in self.indexed_rows(idx, &row[idx]).unwrap_or_default() {
+                if other_name != *row_name {
+                    return duplicate(self, idx, &other_name);
+                }
+            }
+        }
+        if scanned.is_empty() {
+            return Ok(());
+        }
+
+        for other_name in self.map.row_names()? {
+            if other_name == *row_name {
+                continue;
+            }
+            if let Some(other) = self.map.get(&other_name)? {
+                if let Some(idx) = scanned.iter().find(|idx| other[**idx].equals(&row[**idx]) == Some(true)) {
+                    return duplicate(self, *idx, &other_name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// # `delete`
+    /// Removes the requested rows, or every row for `*`, for which the filter is true. Returns the names and content of the removed
+    /// rows in the order they were found, so they can be shown or inserted again. If a row can not be removed the rows removed
+    /// before it are put back
+    pub fn delete(&mut self, rows: &[String], filter: Option<&Expr>) -> Result<Vec<(String, Vec<DataItem>)>, String> {
+        let mut removed: Vec<(String, Vec<DataItem>)> = Vec::new();
+        for (row_name, found) in self.fetch(rows, filter)? {
+            if found.is_none() {
+                continue;
+            }
+            match self.remove_stored(row_name.clone()) {
+                Ok(row) => removed.push((row_name, row)),
+                Err(error) => {
+                    for (row_name, row) in removed {
+                        self.insert_stored(row_name, row)?;
                     }
+                    return Err(format!("Row {}: {}", row_name, error));
                 }
             }
         }
+        Ok(removed)
+    }
+
+    /// # `collect_references`
+    /// Starts keeping the values written to columns referencing other tables next to the index of their column, until
+    /// `take_references`. Without it such values can not be written, as the table can not see the tables they reference
+    pub fn collect_references(&mut self) {
+        self.references = Some(Vec::new());
+    }
+
+    /// # `take_references`
+    /// Returns the values kept since `collect_references` and stops keeping them
+    pub fn take_references(&mut self) -> Vec<(usize, DataItem)> {
+        self.references.take().unwrap_or_default()
+    }
+
+    /// # `contains_row`
+    /// Checks if there is a row with the given name
+    pub fn contains_row(&mut self, row_name: &str) -> Result<bool, &'static str> {
+        Ok(self.map.get(&row_name.to_string())?.is_some())
+    }
 
-        self.map.insert(row_name.to_string(), converted_data)
+    /// # `row_names`
+    /// Returns the names of every row
+    pub fn row_names(&mut self) -> Result<Vec<String>, &'static str> {
+        self.map.row_names()
     }
 
     /// # `remove_row`
     /// Takes a row name and removes it. Returns `Ok(Vec<DataItem>)` containing the row removed.
-    pub fn remove_row(&mut self, row_name: &String) -> Result<Vec<DataItem>, &'static str> {
-        self.map.remove(row_name.clone())
+    pub fn remove_row(&mut self, row_name: &str) -> Result<Vec<DataItem>, &'static str> {
+        self.remove_stored(row_name.to_string())
+    }
+
+    /// # `begin`
+    /// Starts a transaction. Every change of a row is logged from now on, so it can be undone with `rollback` until `commit`.
+    /// `Err()` if a transaction is already open
+    pub fn begin(&mut self) -> Result<(), &'static str> {
+        if self.journal.is_some() {
+            return Err("A transaction is already open");
+        }
+        self.journal = Some(Vec::new());
+        Ok(())
+    }
+
+    /// # `in_transaction`
+    /// Checks if a transaction is open
+    pub fn in_transaction(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// # `commit`
+    /// Ends the transaction and keeps its changes
+    pub fn commit(&mut self) -> Result<(), &'static str> {
+        self.journal.take().ok_or(NO_TRANSACTION)?;
+        self.savepoints.clear();
+        Ok(())
+    }
+
+    /// # `savepoint`
+    /// Marks the changes made so far, so a `rollback` to it only undoes the changes after it. A savepoint with the same name is replaced
+    pub fn savepoint(&mut self, name: &str) -> Result<(), &'static str> {
+        let len = self.journal.as_ref().ok_or(NO_TRANSACTION)?.len();
+        self.savepoints.retain(|(other, _)| other != name);
+        self.savepoints.push((name.to_string(), len));
+        Ok(())
+    }
+
+    /// # `release`
+    /// Forgets the savepoint and the savepoints made after it. Their changes stay part of the transaction
+    pub fn release(&mut self, name: &str) -> Result<(), String> {
+        let idx = self.savepoint_index(name)?;
+        self.savepoints.truncate(idx);
+        Ok(())
+    }
+
+    /// # `rollback`
+    /// Undoes the changes of the transaction, the last first, and ends it. Given a savepoint only the changes made after it are undone,
+    /// the transaction stays open and so does the savepoint, while the savepoints made after it are forgotten
+    pub fn rollback(&mut self, savepoint: Option<&str>) -> Result<(), String> {
+        let start = match savepoint {
+            Some(name) => {
+                let idx = self.savepoint_index(name)?;
+                self.savepoints.truncate(idx + 1);
+                self.savepoints[idx].1
+            }
+            None => {
+                self.savepoints.clear();
+                0
+            }
+        };
+
+        self.undo(start)?;
+        if savepoint.is_none() {
+            self.journal = None;
+        }
+        Ok(())
+    }
+
+    /// # `changes`
+    /// Returns how many changes the open transaction has logged, `None` if no transaction is open
+    pub fn changes(&self) -> Option<usize> {
+        self.journal.as_ref().map(Vec::len)
+    }
+
+    /// # `undo`
+    /// Undoes the changes logged after the first `start` changes of the transaction, the last first. The transaction stays open
+    pub fn undo(&mut self, start: usize) -> Result<(), String> {
+        // The log is taken out while undoing so the undoing is not logged itself, and put back even if undoing fails,
+        // keeping the changes that were not undone
+        let mut journal = self.journal.take().ok_or(NO_TRANSACTION)?;
+        let mut result = Ok(());
+        while journal.len() > start {
+            let (row_name, old) = journal.pop().unwrap();
+            result = self.undo_change(&row_name, old.clone());
+            if result.is_err() {
+                journal.push((row_name, old));
+                break;
+            }
+        }
+        self.journal = Some(journal);
+        result
+    }
+
+    /// # `undo_change`
+    /// Puts back the row as it was before one logged change, `None` meaning it did not exist
+    fn undo_change(&mut self, row_name: &str, old: Option<Vec<DataItem>>) -> Result<(), String> {
+        match (self.map.get(&row_name.to_string())?, old) {
+            (Some(_), Some(old)) => {
+                self.set_stored(row_name.to_string(), old)?;
+            }
+            (Some(_), None) => {
+                self.remove_stored(row_name.to_string())?;
+            }
+            (None, Some(old)) => self.insert_stored(row_name.to_string(), old)?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    /// # `savepoint_index`
+    /// Returns where the savepoint with the given name is in `savepoints`
+    fn savepoint_index(&self, name: &str) -> Result<usize, String> {
+        if self.journal.is_none() {
+            return Err(NO_TRANSACTION.to_string());
+        }
+        self.savepoints
+            .iter()
+            .position(|(other, _)| other == name)
+            .ok_or_else(|| format!("No such savepoint: {}", name))
+    }
+
+    /// # `log`
+    /// Adds the content a row had before a change to the log of the transaction, if one is open
+    fn log(&mut self, row_name: &str, old: Option<Vec<DataItem>>) {
+        if let Some(journal) = &mut self.journal {
+            journal.push((row_name.to_string(), old));
+        }
+    }
+
+    /// # `insert_stored`
+    /// Inserts a row into the storage and the indexes
+    fn insert_stored(&mut self, row_name: String, row: Vec<DataItem>) -> Result<(), &'static str> {
+        self.map.insert(row_name.clone(), row.clone())?;
+        self.log(&row_name, None);
+        self.index_row(&row_name, &row)
+    }
+
+    /// # `set_stored`
+    /// Replaces a row in the storage and the indexes, returns the previous content
+    fn set_stored(&mut self, row_name: String, row: Vec<DataItem>) -> Result<Vec<DataItem>, &'static str> {
+        let old = self.map.set(row_name.clone(), row.clone())?;
+        self.log(&row_name, Some(old.clone()));
+        if !self.indexes.is_empty() {
+            self.unindex_row(&row_name, &old);
+            self.index_row(&row_name, &row)?;
+        }
+        Ok(old)
+    }
+
+    /// # `remove_stored`
+    /// Removes a row from the storage and the indexes, returns its content
+    fn remove_stored(&mut self, row_name: String) -> Result<Vec<DataItem>, &'static str> {
+        let old = self.map.remove(row_name.clone())?;
+        self.log(&row_name, Some(old.clone()));
+        self.unindex_row(&row_name, &old);
+        Ok(old)
+    }
+
+    /// # `index_row`
+    /// Adds the row to the index of every indexed column under its value
+    fn index_row(&mut self, row_name: &str, row: &[DataItem]) -> Result<(), &'static str> {
+        for (idx, index) in self.indexes.iter_mut() {
+            if row[*idx].is_null() {
+                continue;
+            }
+            let key = GroupKey(vec![row[*idx].clone()]);
+            match index.get_mut(&key) {
+                Some(rows) => rows.insert(row_name.to_string(), ())?,
+                None => {
+                    let mut rows = Map::new();
+                    rows.insert(row_name.to_string(), ())?;
+                    index.insert(key, rows)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// # `unindex_row`
+    /// Removes the row from the index of every indexed column, values without rows left are removed as well
+    fn unindex_row(&mut self, row_name: &str, row: &[DataItem]) {
+        for (idx, index) in self.indexes.iter_mut() {
+            let key = GroupKey(vec![row[*idx].clone()]);
+            let emptied = index.get_mut(&key).is_some_and(|rows| {
+                rows.remove(row_name.to_string()).ok();
+                rows.is_empty()
+            });
+            if emptied {
+                index.remove(key).ok();
+            }
+        }
+    }
+
+    /// # `build_indexes`
+    /// Builds the index of every indexed column from the rows of the table
+    fn build_indexes(&mut self) -> Result<(), &'static str> {
+        self.indexes = (0..self.schema.len())
+            .filter(|idx| self.schema.columns()[*idx].indexed)
+            .map(|idx| (idx, Map::new()))
+            .collect();
+        if self.indexes.is_empty() {
+            return Ok(());
+        }
+        for (row_name, row) in self.rows()? {
+            self.index_row(&row_name, &row)?;
+        }
+        Ok(())
+    }
+
+    /// # `lookup`
+    /// Returns the names of the rows a bound filter can be true for according to an index, `None` if no index can be used.
+    /// An index is used for `<column> = <value>` on an indexed column, alone or joined to the rest of the filter by `AND`
+    fn lookup(&mut self, filter: &Expr) -> Option<Vec<String>> {
+        match filter {
+            Expr::And(left, right) => self.lookup(left).or_else(|| self.lookup(right)),
+            Expr::Compare(left, CompareOp::Equal, right) => {
+                let (idx, value) = match (&**left, &**right) {
+                    (Expr::Column(idx), Expr::Value(value)) | (Expr::Value(value), Expr::Column(idx)) => (*idx, value),
+                    _ => return None,
+                };
+                self.indexed_rows(idx, value)
+            }
+            _ => None,
+        }
+    }
+
+    /// # `indexed_rows`
+    /// Returns the names of the rows holding the value in the column according to its index, `None` if the column has no index
+    fn indexed_rows(&mut self, idx: usize, value: &DataItem) -> Option<Vec<String>> {
+        let (_, index) = self.indexes.iter_mut().find(|(column, _)| *column == idx)?;
+        Some(
+            index
+                .get_mut(&GroupKey(vec![value.clone()]))
+                .map_or_else(Vec::new, |rows| rows.keys().clone()),
+        )
+    }
+
+    /// # `create_index`
+    /// Gives the column a hash index, so `WHERE <column> = <value>` finds its rows without reading every row.
+    /// The index is kept up to date as rows change and is written in the header as `|index`, so it is built again when the table is opened
+    pub fn create_index(&mut self, name: &str) -> Result<(), String> {
+        let idx = self.schema.index_of(name).ok_or_else(|| format!("No such column: {}", name))?;
+        let mut column = self.schema.columns()[idx].clone();
+        if column.indexed {
+            return Err(format!("Column {} already has an index", name));
+        }
+        column.indexed = true;
+        self.restructure(self.schema.replaced(idx, column)?, Vec::new())
+    }
+
+    /// # `rows`
+    /// Returns the name and content of every row
+    fn rows(&mut self) -> Result<Vec<(String, Vec<DataItem>)>, &'static str> {
+        let mut rows: Vec<(String, Vec<DataItem>)> = Vec::new();
+        for row_name in self.map.row_names()? {
+            if let Some(row) = self.map.get(&row_name)? {
+                rows.push((row_name, row));
+            }
+        }
+        Ok(rows)
+    }
+
+    /// # `restructure`
+    /// Replaces the schema and the content of every row after the columns changed, then builds the indexes again.
+    /// Disk tables get the new header in their metadata. `Err()` if a `CHECK` does not fit the new columns or is false for a row.
+    /// If writing fails partway, the old schema, header and rows are written back so the table is left as it was
+    fn restructure(&mut self, mut schema: Schema, rows: Vec<(String, Vec<DataItem>)>) -> Result<(), String> {
+        if self.journal.is_some() {
+            return Err("Columns can not be changed inside a transaction, COMMIT or ROLLBACK first".to_string());
+        }
+        schema.bind_checks()?;
+        for (row_name, row) in rows.iter() {
+            schema.check_conditions(row).map_err(|error| format!("Row {}: {}", row_name, error))?;
+        }
+
+        let old_rows = if rows.is_empty() { Vec::new() } else { self.rows()? };
+        let old = std::mem::replace(&mut self.schema, schema);
+        if let Err(error) = self.write_structure(rows) {
+            self.schema = old;
+            // The first error is the one worth returning, putting back the old rows is all that is left to try
+            let _ = self.write_structure(old_rows);
+            return Err(error.to_string());
+        }
+        Ok(())
+    }
+
+    /// # `write_structure`
+    /// Writes the header of the current schema to the metadata of a disk table, then the given rows, and builds the indexes again
+    fn write_structure(&mut self, rows: Vec<(String, Vec<DataItem>)>) -> Result<(), &'static str> {
+        if let Storage::Disk(_) = self.map {
+            let header = self.header_line().into_bytes();
+            if let Storage::Disk(map) = &mut self.map {
+                map.set_metadata(header)?;
+            }
+        }
+        for (row_name, row) in rows {
+            self.map.set(row_name, row)?;
+        }
+        self.build_indexes()
+    }
+
+    /// # `check_unused`
+    /// `Err()` if the `CHECK` of a column reads the column with the given index, as the text of the condition would no longer
+    /// name it once it is dropped or renamed. The `CHECK` of the column itself only counts when it keeps the column
+    fn check_unused(&self, idx: usize, own: bool) -> Result<(), String> {
+        for (check_idx, condition) in self.schema.checks() {
+            if (own || *check_idx != idx) && condition.uses_column(idx) {
+                let columns = self.schema.columns();
+                return Err(format!("Column {} is used by the CHECK of column {}", columns[idx].name, columns[*check_idx].name));
+            }
+        }
+        Ok(())
+    }
+
+    /// # `add_column`
+    /// Adds a column after the others. Existing rows get its default value, or `DataItem::Null` if it has none.
+    /// `Err()` if a column with that name exists or if the rows can not be given a value that meets the constraints of the column
+    pub fn add_column(&mut self, column: Column) -> Result<(), String> {
+        if self.schema.index_of(&column.name).is_some() {
+            return Err(format!("Column {} already exists", column.name));
+        }
+        let mut rows = self.rows()?;
+        let value = column.default.clone().unwrap_or(DataItem::Null);
+        if !rows.is_empty() {
+            column
+                .check(&value)
+                .map_err(|error| format!("Column {} needs a default value for the rows of the table: {}", column.name, error))?;
+            if column.unique && rows.len() > 1 && !value.is_null() {
+                return Err(format!("Column {} is unique, so its default can not be given to every row", column.name));
+            }
+        }
+
+        let mut schema = self.schema.clone();
+        schema.push(column)?;
+        for (_, row) in rows.iter_mut() {
+            row.push(value.clone());
+        }
+        self.restructure(schema, rows)
+    }
+
+    /// # `drop_column`
+    /// Removes the column with the given name and its value from every row
+    pub fn drop_column(&mut self, name: &str) -> Result<(), String> {
+        let idx = self.schema.index_of(name).ok_or_else(|| format!("No such column: {}", name))?;
+        self.check_unused(idx, false)?;
+        let mut schema = Schema::default();
+        for column in self.schema.columns().iter().filter(|column| column.name != name) {
+            schema.push(column.clone())?;
+        }
+
+        let mut rows = self.rows()?;
+        for (_, row) in rows.iter_mut() {
+            row.remove(idx);
+        }
+        self.restructure(schema, rows)
+    }
+
+    /// # `rename_column`
+    /// Gives the column a new name. `Err()` if there is no such column or a column already has the new name
+    pub fn rename_column(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        let idx = self.schema.index_of(name).ok_or_else(|| format!("No such column: {}", name))?;
+        if self.schema.index_of(new_name).is_some() {
+            return Err(format!("Column {} already exists", new_name));
+        }
+        self.check_unused(idx, true)?;
+        let mut column = self.schema.columns()[idx].clone();
+        column.name = new_name.to_string();
+        self.restructure(self.schema.replaced(idx, column)?, Vec::new())
+    }
+
+    /// # `alter_column_type`
+    /// Changes the type of the column and converts its values, default and bounds like `CAST`. Nothing is changed if a value
+    /// can not be converted or no longer meets the constraints of the column, such as two values that became equal in a unique column
+    pub fn alter_column_type(&mut self, name: &str, kind: ColumnType) -> Result<(), String> {
+        let idx = self.schema.index_of(name).ok_or_else(|| format!("No such column: {}", name))?;
+        let convert = |value: &DataItem| match kind.cast(value.clone()) {
+            DataItem::Null if !value.is_null() => Err(format!("{} can not be converted to {:?}", value, kind)),
+            converted => Ok(converted),
+        };
+
+        let mut column = self.schema.columns()[idx].clone();
+        if column.pattern.is_some() && !kind.is_text() {
+            return Err("Only Word and Text columns can have a regex".to_string());
+        }
+        column.kind = kind;
+        column.default = column.default.as_ref().map(convert).transpose()?;
+        column.min = column.min.as_ref().map(convert).transpose()?;
+        column.max = column.max.as_ref().map(convert).transpose()?;
+
+        let mut rows = self.rows()?;
+        let mut seen: Map<GroupKey, ()> = Map::new();
+        for (row_name, row) in rows.iter_mut() {
+            row[idx] = convert(&row[idx])?;
+            column.check(&row[idx]).map_err(|error| format!("Row {}: {}", row_name, error))?;
+            if column.unique && !row[idx].is_null() && seen.insert(GroupKey(vec![row[idx].clone()]), ()).is_err() {
+                return Err(format!("Row {}: Value must be unique in its column", row_name));
+            }
+        }
+
+        self.restructure(self.schema.replaced(idx, column)?, rows)
     }
 }
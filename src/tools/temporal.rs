@@ -0,0 +1,204 @@
+use std::fmt::{Debug, Display, Formatter};
+
+/// # `Date`
+/// A calendar date, stored as the amount of days since 1970-01-01. Written as `YYYY-MM-DD`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub days: i32,
+}
+
+/// # `DateTime`
+/// A date and a time of day in UTC, stored as the amount of seconds since 1970-01-01 00:00:00. Written as `YYYY-MM-DDTHH:MM:SS`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub seconds: i64,
+}
+
+/// # `Duration`
+/// A signed amount of time in seconds. Written as `[-]H:MM:SS` where the hours may go past 24
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration {
+    pub seconds: i64,
+}
+
+/// # `days_from_civil`
+/// Returns the amount of days between 1970-01-01 and the given date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Count years from March so that the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// # `civil_from_days`
+/// Returns the year, month and day of the date the given amount of days after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// # `days_in_month`
+/// Returns the amount of days in the given month of the given year
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// # `parse_number`
+/// Parses a field of digits only, such as the `MM` in `YYYY-MM-DD`
+fn parse_number(text: &str, error: &'static str) -> Result<i64, &'static str> {
+    if text.is_empty() || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(error);
+    }
+    text.parse::<i64>().map_err(|_| error)
+}
+
+impl Date {
+    /// # `parse`
+    /// Parses a date written as `YYYY-MM-DD`
+    pub fn parse(text: &str) -> Result<Date, &'static str> {
+        const ERROR: &str = "Error parsing value as Date";
+        let mut parts = text.trim().splitn(3, '-');
+        let (year, month, day) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(year), Some(month), Some(day)) => (
+                parse_number(year, ERROR)?,
+                parse_number(month, ERROR)? as u32,
+                parse_number(day, ERROR)? as u32,
+            ),
+            _ => return Err(ERROR),
+        };
+
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(ERROR);
+        }
+
+        i32::try_from(days_from_civil(year, month, day))
+            .map(|days| Date { days })
+            .map_err(|_| ERROR)
+    }
+}
+
+impl DateTime {
+    /// # `parse`
+    /// Parses a date and time written as `YYYY-MM-DDTHH:MM:SS`. A space may be used instead of the `T`
+    pub fn parse(text: &str) -> Result<DateTime, &'static str> {
+        const ERROR: &str = "Error parsing value as DateTime";
+        let text = text.trim();
+        let (date, time) = text
+            .split_once('T')
+            .or_else(|| text.split_once(' '))
+            .ok_or(ERROR)?;
+
+        let date = Date::parse(date).map_err(|_| ERROR)?;
+        let mut parts = time.splitn(3, ':');
+        let (hours, minutes, seconds) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(hours), Some(minutes), Some(seconds)) => (
+                parse_number(hours, ERROR)?,
+                parse_number(minutes, ERROR)?,
+                parse_number(seconds, ERROR)?,
+            ),
+            _ => return Err(ERROR),
+        };
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(ERROR);
+        }
+
+        Ok(DateTime {
+            seconds: date.days as i64 * 86400 + hours * 3600 + minutes * 60 + seconds,
+        })
+    }
+}
+
+impl Duration {
+    /// # `parse`
+    /// Parses a duration written as `[-]H:MM:SS`
+    pub fn parse(text: &str) -> Result<Duration, &'static str> {
+        const ERROR: &str = "Error parsing value as Duration";
+        let text = text.trim();
+        let (sign, text) = match text.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, text),
+        };
+
+        let mut parts = text.splitn(3, ':');
+        let (hours, minutes, seconds) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(hours), Some(minutes), Some(seconds)) => (
+                parse_number(hours, ERROR)?,
+                parse_number(minutes, ERROR)?,
+                parse_number(seconds, ERROR)?,
+            ),
+            _ => return Err(ERROR),
+        };
+        if minutes > 59 || seconds > 59 {
+            return Err(ERROR);
+        }
+
+        hours
+            .checked_mul(3600)
+            .and_then(|total| total.checked_add(minutes * 60 + seconds))
+            .map(|total| Duration { seconds: sign * total })
+            .ok_or(ERROR)
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (year, month, day) = civil_from_days(self.days as i64);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let days = self.seconds.div_euclid(86400);
+        let time = self.seconds.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.seconds < 0 { "-" } else { "" };
+        let total = self.seconds.unsigned_abs();
+        write!(f, "{}{}:{:02}:{:02}", sign, total / 3600, total / 60 % 60, total % 60)
+    }
+}
+
+/// Printed the way they are written, `Date(2001-02-28)` reads better than `Date(Date { days: 11381 })`
+macro_rules! debug_as_display {
+    ($($t:ty),*) => {$(
+        impl Debug for $t {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Display::fmt(self, f)
+            }
+        }
+    )*};
+}
+
+debug_as_display!(Date, DateTime, Duration);
@@ -0,0 +1,112 @@
+use azeezd_hash::tools::{query, Accumulator, Aggregate, ColumnType, DataItem, Decimal, Expr, Statement, Table};
+
+mod common;
+
+use common::temp_table;
+
+fn accumulate(aggregate: Aggregate, distinct: bool, values: Vec<DataItem>) -> Result<DataItem, &'static str> {
+    let mut accumulator = Accumulator::new(aggregate, distinct);
+    for value in values {
+        accumulator.add(value)?;
+    }
+    accumulator.finish()
+}
+
+/// Runs a GET and returns the rows of its result
+fn aggregate(table: &mut Table, text: &str) -> Result<Vec<Vec<DataItem>>, String> {
+    match Statement::parse(text).unwrap() {
+        Statement::Get { columns, rows, filter, group_by, having, order, .. } => table.aggregate(
+            &columns.unwrap().into_iter().map(|(column, _)| column).collect::<Vec<Expr>>(),
+            &rows.unwrap_or_else(|| vec!["*".to_string()]),
+            filter.as_ref(),
+            &group_by,
+            having.as_ref(),
+            &order,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn accumulators_skip_nulls_and_do_not_overflow() {
+    let big = vec![DataItem::Long(i64::MAX), DataItem::Long(i64::MAX), DataItem::Long(-i64::MAX)];
+    assert_eq!(accumulate(Aggregate::Sum, false, big), Ok(DataItem::Long(i64::MAX)));
+    assert_eq!(
+        accumulate(Aggregate::Sum, false, vec![DataItem::ULong(u64::MAX), DataItem::ULong(0)]),
+        Ok(DataItem::ULong(u64::MAX))
+    );
+    assert!(accumulate(Aggregate::Sum, false, vec![DataItem::ULong(u64::MAX), DataItem::ULong(1)]).is_err());
+    assert!(accumulate(Aggregate::Sum, false, vec![DataItem::Long(i64::MAX), DataItem::Long(1)]).is_err());
+
+    // The value has the type `kind` gives for the values summed
+    for values in [vec![DataItem::ULong(1), DataItem::ULong(2)], vec![DataItem::Integer(-1), DataItem::UInteger(4)]] {
+        let sum = accumulate(Aggregate::Sum, false, values.clone()).unwrap();
+        assert_eq!(ColumnType::of(&sum), Aggregate::Sum.kind(ColumnType::of(&values[0])));
+        assert_eq!(sum.as_f64(), Some(3.0));
+    }
+
+    let prices = ["1.5", "2.25", "NULL"].map(|price| Decimal::parse(price).map_or(DataItem::Null, DataItem::Decimal));
+    assert_eq!(
+        accumulate(Aggregate::Sum, false, prices.to_vec()),
+        Ok(DataItem::Decimal(Decimal::parse("3.75").unwrap()))
+    );
+    assert_eq!(accumulate(Aggregate::Avg, false, prices.to_vec()), Ok(DataItem::Double(1.875)));
+    assert_eq!(accumulate(Aggregate::Count, false, prices.to_vec()), Ok(DataItem::ULong(2)));
+    assert_eq!(accumulate(Aggregate::Sum, false, vec![DataItem::Null]), Ok(DataItem::Null));
+    assert_eq!(accumulate(Aggregate::Count, false, Vec::new()), Ok(DataItem::ULong(0)));
+
+    let words = ["b", "a", "b", "c"].map(|word| DataItem::Word(word.to_string()));
+    assert_eq!(accumulate(Aggregate::Count, true, words.to_vec()), Ok(DataItem::ULong(3)));
+    assert_eq!(accumulate(Aggregate::Max, false, words.to_vec()), Ok(DataItem::Word("c".to_string())));
+    assert_eq!(accumulate(Aggregate::Min, false, words.to_vec()), Ok(DataItem::Word("a".to_string())));
+}
+
+#[test]
+fn groups_are_filtered_and_sorted() {
+    let (mut table, path) = temp_table(
+        "groups",
+        "w?:Program,u:Age,dec:Fee\nBob,CDATE,20,10.50\nAl,CMETE,30,5\nCy,CDATE,22,1.25\nDi,NULL,40,3\nEd,CDATE,20,0",
+    );
+
+    assert_eq!(
+        aggregate(&mut table, "GET Program, COUNT(*), SUM(Fee), MAX(Age) OF * GROUP BY Program").unwrap(),
+        vec![
+            vec![
+                DataItem::Word("CDATE".to_string()),
+                DataItem::ULong(3),
+                DataItem::Decimal(Decimal::parse("11.75").unwrap()),
+                DataItem::UInteger(22),
+            ],
+            vec![
+                DataItem::Word("CMETE".to_string()),
+                DataItem::ULong(1),
+                DataItem::Decimal(Decimal::parse("5").unwrap()),
+                DataItem::UInteger(30),
+            ],
+            vec![DataItem::Null, DataItem::ULong(1), DataItem::Decimal(Decimal::parse("3").unwrap()), DataItem::UInteger(40)],
+        ]
+    );
+    assert_eq!(
+        aggregate(&mut table, "GET Program, COUNT(DISTINCT Age) OF * WHERE Age < 35 GROUP BY Program HAVING COUNT(*) > 1")
+            .unwrap(),
+        vec![vec![DataItem::Word("CDATE".to_string()), DataItem::ULong(2)]]
+    );
+    assert_eq!(
+        aggregate(&mut table, "GET Program OF * GROUP BY Program ORDER BY COUNT(*) DESC, Program").unwrap(),
+        vec![vec![DataItem::Word("CDATE".to_string())], vec![DataItem::Null], vec![DataItem::Word("CMETE".to_string())]]
+    );
+    assert_eq!(
+        aggregate(&mut table, "GET COUNT(*), AVG(Age) OF * WHERE Age > 100").unwrap(),
+        vec![vec![DataItem::ULong(0), DataItem::Null]]
+    );
+
+    assert!(aggregate(&mut table, "GET Age, COUNT(*) OF * GROUP BY Program").is_err());
+    assert!(aggregate(&mut table, "GET SUM(Program) OF *").is_err());
+    assert!(aggregate(&mut table, "GET MAX(COUNT(*)) OF *").is_err());
+    assert!(aggregate(&mut table, "GET COUNT(*) OF * WHERE COUNT(*) > 1").is_err());
+    assert!(query(&mut table, "GET * OF * GROUP BY Program").is_err());
+    query(&mut table, "GET Program, AVG(Fee) OF * GROUP BY Program LIMIT 1").unwrap();
+    assert!(Expr::parse("COUNT(*) > 1").unwrap().has_aggregate());
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,122 @@
+use azeezd_hash::hash_map::{ByteReader, Deserialize, Serialize};
+use azeezd_hash::tools::{parse_bytes, DataItem, Date, DateTime, Decimal, Duration};
+
+#[test]
+fn dates_round_trip() {
+    for text in ["1970-01-01", "2000-02-29", "1969-12-31", "0001-01-01", "9999-12-31"] {
+        assert_eq!(Date::parse(text).unwrap().to_string(), text);
+    }
+    assert_eq!(Date::parse("1970-01-02").unwrap().days, 1);
+    assert!(Date::parse("2001-02-29").is_err());
+    assert!(Date::parse("2001-13-01").is_err());
+    assert!(Date::parse("2001-1").is_err());
+
+    let moment = DateTime::parse("2021-06-30 23:59:59").unwrap();
+    assert_eq!(moment.to_string(), "2021-06-30T23:59:59");
+    assert!(DateTime::parse("2021-06-30T24:00:00").is_err());
+
+    assert_eq!(Duration::parse("-25:01:02").unwrap().seconds, -(25 * 3600 + 62));
+    assert_eq!(Duration::parse("-25:01:02").unwrap().to_string(), "-25:01:02");
+    assert!(Duration::parse("1:60:00").is_err());
+}
+
+#[test]
+fn decimals_compare_by_value() {
+    let a = Decimal::parse("1.50").unwrap();
+    let b = Decimal::parse("1.5").unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.to_string(), "1.50");
+    assert!(Decimal::parse("-0.01").unwrap() < Decimal::parse("0").unwrap());
+    assert_eq!(Decimal::parse("-0.05").unwrap().to_string(), "-0.05");
+    assert!(Decimal::parse("1.2.3").is_err());
+    assert!(Decimal::parse("99999999999999999999").is_err());
+}
+
+#[test]
+fn numbers_of_different_types_compare_exactly() {
+    use azeezd_hash::hash_map::Hashable;
+    use std::cmp::Ordering;
+
+    let big = 1i64 << 53;
+    assert_eq!(DataItem::Long(big + 1).compare(&DataItem::ULong(big as u64)), Some(Ordering::Greater));
+    assert_eq!(DataItem::Long(big + 1).compare(&DataItem::Double(big as f64)), Some(Ordering::Greater));
+    assert_eq!(DataItem::ULong(u64::MAX).compare(&DataItem::Long(i64::MAX)), Some(Ordering::Greater));
+    assert_eq!(DataItem::Double(u64::MAX as f64).compare(&DataItem::ULong(u64::MAX)), Some(Ordering::Greater));
+    assert_eq!(DataItem::Integer(3).compare(&DataItem::Double(2.5)), Some(Ordering::Greater));
+    assert_eq!(DataItem::Double(f64::NAN).compare(&DataItem::Integer(3)), None);
+
+    let tenth = DataItem::Decimal(Decimal::parse("0.1").unwrap());
+    assert_eq!(tenth.compare(&DataItem::Double(0.1)), Some(Ordering::Less));
+    assert_eq!(tenth.compare(&DataItem::Float(0.1)), Some(Ordering::Less));
+    let half = DataItem::Decimal(Decimal::parse("-2.500").unwrap());
+    assert_eq!(half.compare(&DataItem::Double(-2.5)), Some(Ordering::Equal));
+    assert_eq!(DataItem::Decimal(Decimal::parse("3.0").unwrap()).compare(&DataItem::Long(3)), Some(Ordering::Equal));
+
+    // Equal numbers hash the same, even when the decimal has more digits than an f64
+    let exact = DataItem::Decimal(Decimal::parse("67108864.001953125").unwrap());
+    let float = DataItem::Double((1 << 26) as f64 + 2f64.powi(-9));
+    assert_eq!(exact.compare(&float), Some(Ordering::Equal));
+    assert_eq!(exact.hash_code(), float.hash_code());
+    assert_eq!(half.hash_code(), DataItem::Double(-2.5).hash_code());
+}
+
+#[test]
+fn items_serialize_round_trip() {
+    let items = vec![
+        DataItem::Text("a, \"b\"".to_string()),
+        DataItem::Long(-1 << 40),
+        DataItem::ULong(u64::MAX),
+        DataItem::Double(0.1),
+        DataItem::Date(Date::parse("2020-01-01").unwrap()),
+        DataItem::DateTime(DateTime::parse("2020-01-01T12:00:00").unwrap()),
+        DataItem::Duration(Duration::parse("1:00:00").unwrap()),
+        DataItem::Decimal(Decimal::parse("-12.345").unwrap()),
+        DataItem::Bytes(parse_bytes("0x00ff10").unwrap()),
+    ];
+
+    let mut bytes = Vec::new();
+    items.serialize(&mut bytes);
+    let mut reader = ByteReader::new(&bytes);
+    assert_eq!(Vec::<DataItem>::deserialize(&mut reader).unwrap(), items);
+    assert_eq!(items[8].to_string(), "0x00ff10");
+    assert!(parse_bytes("0x0").is_err());
+}
+
+#[test]
+fn null_compares_as_unknown() {
+    use std::cmp::Ordering;
+    let null = DataItem::Null;
+    assert_eq!(null.equals(&DataItem::Null), None);
+    assert_eq!(null.compare(&DataItem::Integer(1)), None);
+    assert_eq!(DataItem::Integer(1).compare(&null), None);
+    assert_eq!(DataItem::Integer(2).compare(&DataItem::Double(2.5)), Some(Ordering::Less));
+    assert_eq!(DataItem::Word("a".to_string()).equals(&DataItem::Text("a".to_string())), Some(true));
+    assert_eq!(DataItem::Boolean(true).compare(&DataItem::Integer(1)), None);
+
+    let mut bytes = Vec::new();
+    null.serialize(&mut bytes);
+    assert_eq!(DataItem::deserialize(&mut ByteReader::new(&bytes)).unwrap(), DataItem::Null);
+    assert_eq!(null.to_string(), "NULL");
+}
+
+#[test]
+fn total_order_sorts_everything() {
+    let mut items = [
+        DataItem::Double(f64::NAN),
+        DataItem::Word("b".to_string()),
+        DataItem::Integer(-3),
+        DataItem::Null,
+        DataItem::Double(2.5),
+        DataItem::Text("a".to_string()),
+        DataItem::Boolean(true),
+        DataItem::ULong(1),
+    ];
+    items.sort_by(DataItem::total_cmp);
+    assert_eq!(items[0], DataItem::Null);
+    assert_eq!(items[1], DataItem::Boolean(true));
+    assert_eq!(items[2..4], [DataItem::Integer(-3), DataItem::ULong(1)]);
+    assert_eq!(items[4], DataItem::Double(2.5));
+    assert!(matches!(items[5], DataItem::Double(nan) if nan.is_nan()));
+    assert_eq!(items[6..], [DataItem::Text("a".to_string()), DataItem::Word("b".to_string())]);
+    assert!(DataItem::Double(f64::NAN).total_cmp(&DataItem::Float(f32::NAN)).is_eq());
+}
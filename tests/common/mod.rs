@@ -0,0 +1,30 @@
+//! Helpers shared by the integration tests. Every test file only uses some of them
+#![allow(dead_code)]
+
+use azeezd_hash::tools::{Database, Table};
+use std::path::PathBuf;
+
+/// # `temp_path`
+/// Returns a path in the temporary directory for the given name and extension, unique per test process
+pub fn temp_path(name: &str, extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("azeezd-hash-{}-{}{}", name, std::process::id(), extension))
+}
+
+/// # `temp_table`
+/// Writes the content to a .csv file in the temporary directory, unique per test, and opens it as a `Table`
+pub fn temp_table(name: &str, content: &str) -> (Table, PathBuf) {
+    let path = temp_path(name, ".csv");
+    std::fs::write(&path, content).unwrap();
+    (Table::new(path.to_string_lossy().to_string()).unwrap(), path)
+}
+
+/// # `temp_database`
+/// Writes the tables to .csv files in a directory in the temporary directory, unique per test, and opens it as a `Database`
+pub fn temp_database(name: &str, tables: &[(&str, &str)]) -> (Database, PathBuf) {
+    let dir = temp_path(name, "");
+    std::fs::create_dir_all(&dir).unwrap();
+    for (table, content) in tables {
+        std::fs::write(dir.join(format!("{}.csv", table)), content).unwrap();
+    }
+    (Database::open(&dir.to_string_lossy()).unwrap(), dir)
+}
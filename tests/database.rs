@@ -0,0 +1,235 @@
+use azeezd_hash::tools::{query_database, DataItem, Database, Expr, Statement};
+
+mod common;
+
+use common::temp_database;
+
+const STUDENTS: &str = "u:Age,w?:Program\nBob,10,CDATE\nBobby,20,CDATE\nAl,30,CMETE\nCy,40,NONE\nDi,50,NULL";
+const PROGRAMS: &str = "w:Name,t:Dept\ncs,CDATE,Computer Science\nme,CMETE,Mechanics";
+
+#[test]
+fn tables_are_chosen_by_name() {
+    let (mut database, dir) = temp_database("catalog", &[("students", STUDENTS), ("programs", PROGRAMS)]);
+
+    assert_eq!(database.names(), ["programs", "students"]);
+    assert!(query_database(&mut database, "GET * OF *").is_err());
+    assert!(query_database(&mut database, "USE nope").is_err());
+    query_database(&mut database, "USE students").unwrap();
+    query_database(&mut database, "DELETE Bob").unwrap();
+    assert_eq!(database.current().unwrap().get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap().len(), 4);
+
+    query_database(&mut database, "CLOSE").unwrap();
+    assert_eq!(database.names(), ["programs"]);
+    assert!(query_database(&mut database, "GET * OF *").is_err());
+    let path = dir.join("students.csv").to_string_lossy().to_string();
+    query_database(&mut database, &format!("OPEN '{}' AS pupils", path)).unwrap();
+    assert!(query_database(&mut database, &format!("OPEN '{}' AS pupils", path)).is_err());
+    assert_eq!(database.current().unwrap().get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap().len(), 5);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn joins_match_rows_by_value() {
+    let (mut database, dir) = temp_database("join", &[("students", STUDENTS), ("programs", PROGRAMS)]);
+    let joins = |query: &str| match Statement::parse(query).unwrap() {
+        Statement::Get { joins, .. } => joins,
+        _ => unreachable!(),
+    };
+
+    // Rows without a matching program, or with a NULL one, are left out
+    let mut joined = database.join(&joins("GET * OF students s JOIN programs p ON s.Program = p.Name")).unwrap();
+    let rows = joined.get(&["s.Age".to_string(), "Dept".to_string()], &["*".to_string()], None, &[]).unwrap();
+    assert_eq!(
+        rows,
+        [
+            ("Bob,cs".to_string(), Some(vec![DataItem::UInteger(10), DataItem::Text("Computer Science".to_string())])),
+            ("Bobby,cs".to_string(), Some(vec![DataItem::UInteger(20), DataItem::Text("Computer Science".to_string())])),
+            ("Al,me".to_string(), Some(vec![DataItem::UInteger(30), DataItem::Text("Mechanics".to_string())])),
+        ]
+    );
+
+    // A table joined with itself needs aliases, and its columns must be qualified
+    let mut joined = database
+        .join(&joins("GET * OF students a JOIN students b ON a.Program = b.Program AND a.Age < b.Age"))
+        .unwrap();
+    let filter = Expr::parse("b.Age > 15").unwrap();
+    let rows = joined.get(&["a.Age".to_string()], &["*".to_string()], Some(&filter), &[]).unwrap();
+    assert_eq!(rows, [("Bob,Bobby".to_string(), Some(vec![DataItem::UInteger(10)]))]);
+    assert!(database.join(&joins("GET * OF students JOIN students ON Age = Age")).is_err());
+    assert!(query_database(&mut database, "GET Age OF students a JOIN students b ON a.Age = b.Age").is_err());
+    assert!(query_database(&mut database, "GET * OF students JOIN programs ON Age = Name").is_err());
+    query_database(&mut database, "GET Dept, COUNT(*) OF students JOIN programs ON Program = Name GROUP BY Dept").unwrap();
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn tables_are_created_and_altered() {
+    let (mut database, dir) = temp_database("ddl", &[("students", STUDENTS)]);
+    let header = |database: &mut Database, table: &str| database.table(table).unwrap().schema().fields().join(",");
+
+    query_database(&mut database, "CREATE TABLE pets (w:Kind, u?:Legs=4|max=8, \"t?:Full Name\")").unwrap();
+    assert!(query_database(&mut database, "CREATE TABLE pets (w:Kind)").is_err());
+    assert_eq!(std::fs::read_to_string(dir.join("pets.csv")).unwrap(), "w:Kind,u?:Legs=4|max=8,t?:Full Name");
+    query_database(&mut database, "USE pets").unwrap();
+    query_database(&mut database, "INSERT Rex : dog, 4, 'Rex the Dog'").unwrap();
+    query_database(&mut database, "INSERT Tweety : bird, 2, NULL").unwrap();
+
+    query_database(&mut database, "ALTER TABLE pets ADD COLUMN b:Cute=true").unwrap();
+    assert!(query_database(&mut database, "ALTER TABLE pets ADD u:Age").is_err());
+    assert!(query_database(&mut database, "ALTER TABLE pets ADD u:Age=1|unique").is_err());
+    query_database(&mut database, "ALTER TABLE pets RENAME COLUMN Legs TO Feet").unwrap();
+    assert!(query_database(&mut database, "ALTER TABLE pets RENAME Feet TO Kind").is_err());
+    query_database(&mut database, "ALTER TABLE pets ALTER COLUMN Feet TYPE Double").unwrap();
+    assert!(query_database(&mut database, "ALTER TABLE pets ALTER Kind TYPE u").is_err());
+    query_database(&mut database, "ALTER TABLE pets DROP COLUMN Kind").unwrap();
+    assert_eq!(header(&mut database, "pets"), "f64?:Feet=4|max=8,t?:Full Name,b:Cute=true");
+    let rows = database.current().unwrap().get(&["*".to_string()], &["Tweety".to_string()], None, &[]).unwrap();
+    assert_eq!(rows[0].1, Some(vec![DataItem::Double(2.0), DataItem::Null, DataItem::Boolean(true)]));
+
+    query_database(&mut database, "DROP TABLE pets").unwrap();
+    assert!(!dir.join("pets.csv").exists());
+    assert!(query_database(&mut database, "GET * OF *").is_err());
+
+    // Disk tables keep their header in the file, so it has to change with the columns
+    let path = dir.join("students.hdb").to_string_lossy().to_string();
+    query_database(&mut database, "USE students").unwrap();
+    query_database(&mut database, &format!("SAVE AS '{}'", path)).unwrap();
+    query_database(&mut database, "ALTER TABLE students ALTER COLUMN Age TYPE Long").unwrap();
+    query_database(&mut database, "ALTER TABLE students ADD w?:Code").unwrap();
+    query_database(&mut database, "CLOSE").unwrap();
+    query_database(&mut database, &format!("OPEN '{}'", path)).unwrap();
+    assert_eq!(header(&mut database, "students"), "i64:Age,w?:Program,w?:Code");
+    let rows = database.current().unwrap().get(&["*".to_string()], &["Bob".to_string()], None, &[]).unwrap();
+    assert_eq!(rows[0].1, Some(vec![DataItem::Long(10), DataItem::Word("CDATE".to_string()), DataItem::Null]));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failed_column_changes_leave_disk_tables_intact() {
+    let mut notes = String::from("t:Note|index");
+    for idx in 0..20 {
+        notes.push_str(&format!("\nr{},x", idx));
+    }
+    notes.push_str(&format!("\nlong,{}", "x".repeat(2500)));
+    let (mut database, dir) = temp_database("restructure", &[("notes", &notes)]);
+    let path = dir.join("notes.hdb").to_string_lossy().to_string();
+    query_database(&mut database, "USE notes").unwrap();
+    query_database(&mut database, &format!("SAVE AS '{}'", path)).unwrap();
+
+    // The long row no longer fits a page once it gets the default, after some of the short rows may have been written
+    let error = query_database(&mut database, &format!("ALTER TABLE notes ADD t:Extra={}", "y".repeat(2000))).unwrap_err();
+    assert!(error.contains("too big"), "{}", error);
+
+    for _ in 0..2 {
+        let table = database.current().unwrap();
+        assert_eq!(table.schema().fields(), ["t:Note|index"]);
+        let rows = table.get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap();
+        assert_eq!(rows.len(), 21);
+        assert!(rows.iter().all(|(_, row)| row.as_ref().is_some_and(|row| row.len() == 1)));
+        let filter = Expr::parse("Note = x").unwrap();
+        assert_eq!(table.get(&["*".to_string()], &["*".to_string()], Some(&filter), &[]).unwrap().len(), 20);
+
+        query_database(&mut database, "CLOSE").unwrap();
+        query_database(&mut database, &format!("OPEN '{}'", path)).unwrap();
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn references_keep_rows_intact() {
+    let (mut database, dir) = temp_database(
+        "references",
+        &[
+            ("depts", "t:Title\nsci,Science\nart,Arts"),
+            ("programs", "w:Dept|ref=depts:cascade\nCDATE,sci\nCMETE,sci\nCART,art"),
+            ("students", "u:Age,w?:Program|ref=programs\nBob,20,CDATE\nAl,30,CART\nCy,40,NULL"),
+        ],
+    );
+    let count = |database: &mut Database, table: &str| database.table(table).unwrap().row_names().unwrap().len();
+
+    // Values must be row names of the referenced table, or NULL
+    query_database(&mut database, "USE students").unwrap();
+    assert_eq!(
+        query_database(&mut database, "INSERT Di : 50, CNOPE"),
+        Err("Value CNOPE of column Program is not a row of table programs".to_string())
+    );
+    query_database(&mut database, "INSERT Di : 50, CMETE").unwrap();
+    query_database(&mut database, "INSERT Ed : 60, NULL").unwrap();
+    assert!(query_database(&mut database, "SET Program = CNOPE OF Cy").is_err());
+    query_database(&mut database, "SET Program = CDATE OF Cy").unwrap();
+
+    // Changes made through the table itself are looked up as well, and a failed one is undone inside a transaction
+    let program = |database: &mut Database, row: &str| {
+        database.table("students").unwrap().get(&["Program".to_string()], &[row.to_string()], None, &[]).unwrap()[0].1.clone()
+    };
+    assert!(database.change(|table| table.new_row("Gus".to_string(), vec![Some("1".to_string()), Some("CNOPE".to_string())])).is_err());
+    assert!(!database.table("students").unwrap().contains_row("Gus").unwrap());
+    query_database(&mut database, "BEGIN").unwrap();
+    query_database(&mut database, "SET Program = CMETE OF Bob").unwrap();
+    assert!(query_database(&mut database, "SET Program = CNOPE WHERE Age > 10").is_err());
+    assert_eq!(program(&mut database, "Bob"), Some(vec![DataItem::Word("CMETE".to_string())]));
+    query_database(&mut database, "ROLLBACK").unwrap();
+    assert_eq!(program(&mut database, "Bob"), Some(vec![DataItem::Word("CDATE".to_string())]));
+    let students = database.table("students").unwrap();
+    assert!(students.new_row("Hal".to_string(), vec![Some("1".to_string()), Some("CDATE".to_string())]).is_err());
+    students.new_row("Hal".to_string(), vec![Some("1".to_string()), None]).unwrap();
+
+    // A referenced row can not be deleted while a column without CASCADE references it
+    query_database(&mut database, "USE programs").unwrap();
+    assert!(query_database(&mut database, "DELETE CART").is_err());
+    assert_eq!(count(&mut database, "programs"), 3);
+
+    // Deleting a department deletes its programs, which fails as long as a student references one of them
+    query_database(&mut database, "USE depts").unwrap();
+    assert!(query_database(&mut database, "DELETE art").is_err());
+    assert_eq!((count(&mut database, "depts"), count(&mut database, "programs")), (2, 3));
+    query_database(&mut database, "USE students").unwrap();
+    query_database(&mut database, "DELETE Al").unwrap();
+    query_database(&mut database, "USE depts").unwrap();
+    query_database(&mut database, "DELETE art").unwrap();
+    assert_eq!((count(&mut database, "depts"), count(&mut database, "programs")), (1, 2));
+
+    // The referenced table must be open and can not be dropped
+    assert!(database.drop_table("programs").is_err());
+    database.close(Some("programs")).unwrap();
+    query_database(&mut database, "USE students").unwrap();
+    assert!(query_database(&mut database, "INSERT Fay : 70, CDATE").is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn transactions_span_every_table() {
+    let (mut database, dir) = temp_database("transaction", &[("students", STUDENTS), ("programs", PROGRAMS)]);
+    let count = |database: &mut Database, table: &str| database.table(table).unwrap().row_names().unwrap().len();
+
+    // Changes of disk tables are written as they are made, so they are undone in the file as well
+    let path = dir.join("programs.hdb").to_string_lossy().to_string();
+    query_database(&mut database, "USE programs").unwrap();
+    query_database(&mut database, &format!("SAVE AS '{}'", path)).unwrap();
+
+    query_database(&mut database, "BEGIN").unwrap();
+    query_database(&mut database, "DELETE *").unwrap();
+    query_database(&mut database, "USE students").unwrap();
+    query_database(&mut database, "SAVEPOINT kept").unwrap();
+    query_database(&mut database, "DELETE WHERE Age > 20").unwrap();
+    assert_eq!((count(&mut database, "students"), count(&mut database, "programs")), (2, 0));
+    assert!(query_database(&mut database, "CLOSE programs").is_err());
+    assert!(query_database(&mut database, "CREATE TABLE pets (w:Kind)").is_err());
+
+    query_database(&mut database, "ROLLBACK TO kept").unwrap();
+    assert_eq!((count(&mut database, "students"), count(&mut database, "programs")), (5, 0));
+    query_database(&mut database, "ROLLBACK").unwrap();
+    assert_eq!(count(&mut database, "programs"), 2);
+    assert!(query_database(&mut database, "ROLLBACK").is_err());
+
+    query_database(&mut database, "CLOSE programs").unwrap();
+    query_database(&mut database, &format!("OPEN '{}'", path)).unwrap();
+    assert_eq!(count(&mut database, "programs"), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,179 @@
+use azeezd_hash::hash_map::{DiskMap, Hashable, Map};
+use std::fs::remove_file;
+
+mod common;
+
+fn temp_path(name: &str) -> String {
+    common::temp_path(name, ".hdb").to_string_lossy().to_string()
+}
+
+#[test]
+fn insert_get_set_remove() {
+    let path = temp_path("basic");
+    let mut map: DiskMap<String, Vec<u32>> = DiskMap::create(&path, 7, Vec::new()).unwrap();
+
+    map.insert("Bob".to_string(), vec![10, 20]).unwrap();
+    map.insert("Bobby".to_string(), vec![]).unwrap();
+    assert!(map.insert("Bob".to_string(), vec![1]).is_err());
+
+    assert_eq!(map.get(&"Bob".to_string()).unwrap(), Some(vec![10, 20]));
+    assert_eq!(map.set("Bob".to_string(), vec![1]).unwrap(), vec![10, 20]);
+    assert_eq!(map.get(&"Bob".to_string()).unwrap(), Some(vec![1]));
+    assert_eq!(map.remove("Bobby".to_string()).unwrap(), vec![]);
+    assert_eq!(map.get(&"Bobby".to_string()).unwrap(), None);
+    assert!(map.remove("Bobby".to_string()).is_err());
+    assert_eq!(map.len(), 1);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn reopen_keeps_pairs_and_metadata() {
+    let path = temp_path("reopen");
+    {
+        let mut map: DiskMap<String, u64> =
+            DiskMap::create(&path, 31, b"u:Age,w:Program".to_vec()).unwrap();
+        for idx in 0..100 {
+            map.insert(format!("Row{}", idx), idx).unwrap();
+        }
+        map.flush().unwrap();
+    }
+
+    let map: DiskMap<String, u64> = DiskMap::open(&path).unwrap();
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.metadata(), b"u:Age,w:Program");
+    for idx in 0..100 {
+        assert_eq!(map.get(&format!("Row{}", idx)).unwrap(), Some(idx));
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn overflow_pages_and_rehash() {
+    let path = temp_path("overflow");
+    // A single bucket forces overflow pages and rehashing right away
+    let mut map: DiskMap<String, String> = DiskMap::create(&path, 1, Vec::new()).unwrap();
+    let mut reference: Map<String, String> = Map::new();
+
+    for idx in 0..2000 {
+        let value = "x".repeat(idx % 300);
+        map.insert(format!("Row{}", idx), value.clone()).unwrap();
+        reference.insert(format!("Row{}", idx), value).unwrap();
+    }
+    // Grow some values past the room left in their page
+    for idx in (0..2000).step_by(3) {
+        let value = "y".repeat(1000);
+        map.set(format!("Row{}", idx), value.clone()).unwrap();
+        reference.set(format!("Row{}", idx), value).unwrap();
+    }
+
+    let read: Map<String, String> = map.iter().map(|pair| pair.unwrap()).collect();
+    assert_eq!(read, reference);
+    assert_eq!(map.len(), reference.len());
+
+    remove_file(path).unwrap();
+}
+
+/// # `page_counts`
+/// Reads the amount of buckets, pages and free pages from the header of a disk map file
+fn page_counts(path: &str) -> (u64, u64, u64) {
+    let header = std::fs::read(path).unwrap();
+    let field = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+    (field(10), field(34), field(50))
+}
+
+#[test]
+fn freed_pages_are_used_again() {
+    let path = temp_path("free");
+    let mut map: DiskMap<String, String> = DiskMap::create(&path, 31, Vec::new()).unwrap();
+
+    for round in 0..5 {
+        let (buckets_before, pages_before, free_before) = page_counts(&path);
+        for idx in 0..300 {
+            map.insert(format!("Row{}-{}", round, idx), "x".repeat(1000)).unwrap();
+        }
+        // Overflow pages come from the free page list before the file grows, unless the map was rehashed into a new file
+        let (buckets, pages, free) = page_counts(&path);
+        let in_use = pages - 1 - buckets - free;
+        if round > 0 && buckets == buckets_before {
+            assert_eq!(free, free_before.saturating_sub(in_use));
+            assert_eq!(pages, pages_before + in_use.saturating_sub(free_before));
+        }
+
+        // Emptying the map puts every overflow page on the free page list
+        for idx in 0..300 {
+            map.remove(format!("Row{}-{}", round, idx)).unwrap();
+        }
+        let (buckets, pages, free) = page_counts(&path);
+        assert!(free > 0);
+        assert_eq!(free, pages - 1 - buckets);
+    }
+
+    let mut map: DiskMap<String, String> = DiskMap::open(&path).unwrap();
+    map.insert("Bob".to_string(), "y".repeat(1000)).unwrap();
+    assert_eq!(map.iter().count(), 1);
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn colliding_keys_are_spread_over_buckets() {
+    let path = temp_path("collide");
+    let mut map: DiskMap<String, String> = DiskMap::create(&path, 31, Vec::new()).unwrap();
+
+    // "Aa" and "B@" have the same hash code, so every string made of 10 of them has the same hash code too
+    let keys = (0..1024)
+        .map(|bits: u32| (0..10).map(|bit| if bits >> bit & 1 == 1 { "Aa" } else { "B@" }).collect::<String>())
+        .collect::<Vec<String>>();
+    assert!(keys.iter().all(|key| key.hash_code() == keys[0].hash_code()));
+    for key in keys.iter() {
+        map.insert(key.clone(), "x".repeat(200)).unwrap();
+    }
+
+    // The pairs take about 57 pages, kept in a single chain nearly all of them would be overflow pages
+    let (buckets, pages, free) = page_counts(&path);
+    assert!(pages - 1 - buckets - free < 40, "{} buckets, {} pages", buckets, pages);
+    for key in keys.iter() {
+        assert_eq!(map.get(key).unwrap(), Some("x".repeat(200)));
+    }
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn opening_leaves_the_file_unchanged() {
+    let path = temp_path("unchanged");
+    let mut map: DiskMap<String, u32> = DiskMap::create(&path, 7, b"meta".to_vec()).unwrap();
+    for idx in 0..100 {
+        map.insert(format!("Row{}", idx), idx).unwrap();
+    }
+    drop(map);
+
+    let bytes = std::fs::read(&path).unwrap();
+    let map: DiskMap<String, u32> = DiskMap::open(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    assert_eq!(map.metadata(), b"meta");
+    assert!((0..100).all(|idx| map.get(&format!("Row{}", idx)).unwrap() == Some(idx)));
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn record_bigger_than_page_is_rejected() {
+    let path = temp_path("big");
+    let mut map: DiskMap<String, String> = DiskMap::create(&path, 1, Vec::new()).unwrap();
+    assert!(map.insert("Big".to_string(), "x".repeat(5000)).is_err());
+    assert!(map.is_empty());
+
+    remove_file(path).unwrap();
+}
+
+#[test]
+fn not_a_disk_map() {
+    let path = temp_path("invalid");
+    std::fs::write(&path, "u:Age,w:Program\nBob,10,CDATE").unwrap();
+    assert!(DiskMap::<String, u32>::open(&path).is_err());
+
+    remove_file(path).unwrap();
+}
@@ -0,0 +1,102 @@
+use azeezd_hash::tools::{DataItem, Expr, Schema};
+
+fn schema() -> Schema {
+    Schema::parse(&["u:Age", "w?:Program", "d:Born"].map(String::from)).unwrap()
+}
+
+fn row(age: u32, program: Option<&str>, born: &str) -> Vec<DataItem> {
+    vec![
+        DataItem::UInteger(age),
+        program.map_or(DataItem::Null, |program| DataItem::Word(program.to_string())),
+        schema().columns()[2].value(Some(born)).unwrap(),
+    ]
+}
+
+fn truth(expr: &str, row: &[DataItem]) -> Option<bool> {
+    Expr::parse(expr).unwrap().bind_condition(&schema()).unwrap().truth(row)
+}
+
+#[test]
+fn conditions_follow_three_valued_logic() {
+    let bob = row(16, Some("CDATE"), "2001-01-01");
+    let cy = row(30, None, "2010-10-10");
+
+    assert_eq!(truth("Age > 15 AND Program = CDATE", &bob), Some(true));
+    assert_eq!(truth("Age > 15 AND Program = CDATE", &cy), None);
+    assert_eq!(truth("Age > 40 AND Program = CDATE", &cy), Some(false));
+    assert_eq!(truth("Age > 15 OR Program = CDATE", &cy), Some(true));
+    assert_eq!(truth("NOT (Program = CDATE)", &cy), None);
+    assert_eq!(truth("Program IS NULL", &cy), Some(true));
+    assert_eq!(truth("Program IS NOT NULL", &bob), Some(true));
+    assert_eq!(truth("Program IN (CMETE, CDATE)", &bob), Some(true));
+    assert_eq!(truth("Program NOT IN (CMETE, NULL)", &bob), None);
+    assert_eq!(truth("Age BETWEEN 10 AND 16", &bob), Some(true));
+    assert_eq!(truth("Age NOT BETWEEN 10 AND 16", &bob), Some(false));
+    assert_eq!(truth("Program LIKE C_E%", &bob), Some(false));
+    assert_eq!(truth("Program LIKE 'C%E'", &bob), Some(true));
+    assert_eq!(truth("Born < 2005-01-01 AND Age != 15", &bob), Some(true));
+    assert_eq!(truth("Age <> 16 OR (Age >= 16 AND Age <= 16)", &bob), Some(true));
+}
+
+#[test]
+fn binding_checks_types() {
+    let bind = |expr: &str| Expr::parse(expr).unwrap().bind_condition(&schema());
+    assert!(bind("Age > abc").is_err());
+    assert!(bind("Age = Born").is_err());
+    assert!(bind("Age LIKE 1%").is_err());
+    assert!(bind("Age").is_err());
+    assert!(bind("Born BETWEEN 2001-01-01 AND 2001-13-01").is_err());
+    assert!(bind("Age > 1.5 OR Program = 3").is_ok());
+
+    assert!(Expr::parse("Age > 1 AND").is_err());
+    assert!(Expr::parse("Age > 1)").is_err());
+    assert!(Expr::parse("Age IN 1, 2").is_err());
+    assert!(Expr::parse("Age = '1").is_err());
+    assert!(bind("\"Nope\" = 1").is_err());
+    assert!(bind("Age = 'x'").is_err());
+}
+
+fn value(expr: &str, row: &[DataItem]) -> DataItem {
+    Expr::parse(expr).unwrap().bind(&schema()).unwrap().value(row)
+}
+
+#[test]
+fn values_are_computed_per_row() {
+    let bob = row(16, Some("CDate"), "2001-01-01");
+    let cy = row(30, None, "2010-10-10");
+
+    assert_eq!(value("Age * 2 + 1", &bob), DataItem::Long(33));
+    assert_eq!(value("Age - 20", &bob), DataItem::Long(-4));
+    assert_eq!(value("Age / 5", &bob), DataItem::Long(3));
+    assert_eq!(value("Age % 5", &bob), DataItem::Long(1));
+    assert_eq!(value("Age / 0", &bob), DataItem::Null);
+    assert_eq!(value("Age / 4.0", &bob), DataItem::Double(4.0));
+    assert_eq!(value("CAST(Age AS dec) * CAST('1.5' AS dec)", &bob).to_string(), "24.0");
+    assert_eq!(value("9223372036854775807 + 9223372036854775807", &bob), DataItem::ULong(u64::MAX - 1));
+    assert_eq!(value("Age + 1", &cy), DataItem::Long(31));
+
+    assert_eq!(value("UPPER(Program)", &bob), DataItem::Word("CDATE".to_string()));
+    assert_eq!(value("lower(Program)", &bob), DataItem::Word("cdate".to_string()));
+    assert_eq!(value("LENGTH('héllo')", &bob), DataItem::ULong(5));
+    assert_eq!(value("ABS(-2147483648)", &bob), DataItem::Long(2147483648));
+    assert_eq!(value("ROUND(2.5)", &bob), DataItem::Double(3.0));
+    assert_eq!(value("ROUND(CAST('-1.255' AS dec), 2)", &bob).to_string(), "-1.26");
+    assert_eq!(value("ROUND(1250, -2)", &bob), DataItem::Long(1300));
+    assert_eq!(value("COALESCE(Program, none)", &cy), DataItem::Word("none".to_string()));
+    assert_eq!(value("CONCAT(Program, '-', Age)", &cy), DataItem::Text("-30".to_string()));
+    assert_eq!(value("UPPER(Program)", &cy), DataItem::Null);
+
+    assert_eq!(value("CAST(Age AS Text)", &bob), DataItem::Text("16".to_string()));
+    assert_eq!(value("CAST(3.9 AS i)", &bob), DataItem::Integer(3));
+    assert_eq!(value("CAST(-1 AS u)", &bob), DataItem::Null);
+    assert_eq!(value("CAST(Born AS t)", &bob), DataItem::Text("2001-01-01".to_string()));
+    assert_eq!(value("CAST('2001-02-03' AS d) > Born", &bob), DataItem::Boolean(true));
+
+    let bind = |expr: &str| Expr::parse(expr).unwrap().bind(&schema());
+    assert!(bind("Program + 1").is_err());
+    assert!(bind("Age + abc").is_err());
+    assert!(bind("UPPER(Age)").is_err());
+    assert!(bind("UPPER(Program, Program)").is_err());
+    assert!(bind("ROUND(Age, 1.5)").is_err());
+    assert!(Expr::parse("NOPE(Age)").is_err());
+}
@@ -0,0 +1,163 @@
+//! Runs long random sequences of operations on `Map` and checks every result against `std::collections::HashMap`.
+//! The same checks are done with inputs from a fuzzer by the targets in `fuzz/`.
+
+use azeezd_hash::hash_map::Map;
+use std::collections::HashMap;
+
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Checks that the map holds exactly the pairs of the model
+fn assert_same(map: &mut Map<String, u64>, model: &HashMap<String, u64>) {
+    assert_eq!(map.len(), model.len());
+    assert_eq!(map.iter().count(), model.len());
+    for (key, value) in model.iter() {
+        assert_eq!(map.get_ref(key), Some(value));
+    }
+
+    let mut keys = map.keys().clone();
+    keys.sort();
+    let mut model_keys: Vec<String> = model.keys().cloned().collect();
+    model_keys.sort();
+    assert_eq!(keys, model_keys);
+}
+
+fn run(seed: u64, operations: usize, key_space: u64) {
+    let mut random = Xorshift(seed);
+    let mut map: Map<String, u64> = Map::new();
+    let mut model: HashMap<String, u64> = HashMap::new();
+
+    for step in 0..operations {
+        let key = format!("k{}", random.next() % key_space);
+        let value = random.next();
+
+        match random.next() % 10 {
+            0..=2 => assert_eq!(
+                map.insert(key.clone(), value).is_ok(),
+                !model.contains_key(&key) && model.insert(key, value).is_none()
+            ),
+            3 => assert_eq!(map.get(key.clone()), model.get(&key).cloned()),
+            4 => assert_eq!(map.remove(key.clone()).ok(), model.remove(&key)),
+            5 => assert_eq!(
+                map.set(key.clone(), value).ok(),
+                model.get_mut(&key).map(|old| std::mem::replace(old, value))
+            ),
+            6 => {
+                let keep = value % 4;
+                map.retain(|_, value| *value % 4 != keep);
+                model.retain(|_, value| *value % 4 != keep);
+            }
+            7 => {
+                let keys: Vec<String> = (0..4).map(|idx| format!("k{}", (value + idx) % key_space)).collect();
+                let mut removed = map.remove_many(keys.clone());
+                let mut expected: Vec<(String, u64)> = keys
+                    .into_iter()
+                    .filter_map(|key| model.remove(&key).map(|value| (key, value)))
+                    .collect();
+                removed.sort();
+                expected.sort();
+                assert_eq!(removed, expected);
+            }
+            8 => {
+                map.extend([(key.clone(), value)]);
+                model.insert(key, value);
+            }
+            _ => {
+                if value.is_multiple_of(50) {
+                    map.clear();
+                    model.clear();
+                }
+            }
+        }
+
+        if step % 97 == 0 {
+            assert_same(&mut map, &model);
+        }
+    }
+
+    assert_same(&mut map, &model);
+    assert!(!map.is_keyed());
+}
+
+#[test]
+fn small_key_space() {
+    // Few keys means many removed slots being reused
+    for seed in 1..20 {
+        run(seed, 2_000, 16);
+    }
+}
+
+#[test]
+fn large_key_space() {
+    // Many keys means many resizes
+    for seed in 1..5 {
+        run(seed * 7919, 20_000, 5_000);
+    }
+}
+
+#[test]
+fn colliding_keys() {
+    // "AB" and "B!" have the same hash code, and so does every string of as many of those blocks
+    let keys: Vec<String> = (0..256usize)
+        .map(|idx| (0..8).map(|bit| if idx >> bit & 1 == 0 { "AB" } else { "B!" }).collect())
+        .collect();
+
+    let mut map: Map<String, usize> = keys.iter().cloned().zip(0..).collect();
+    assert_eq!(map.len(), keys.len());
+    // The collisions are detected and the Map no longer hashes with `hash_code`
+    assert!(map.is_keyed());
+    for (idx, key) in keys.iter().enumerate().step_by(2) {
+        assert_eq!(map.remove(key.clone()), Ok(idx));
+    }
+    for (idx, key) in keys.iter().enumerate() {
+        assert_eq!(map.get_ref(key), if idx % 2 == 0 { None } else { Some(&idx) });
+    }
+}
+
+#[test]
+fn equality_ignores_layout() {
+    let forward: Map<String, u64> = (0..100).map(|idx| (format!("k{}", idx), idx)).collect();
+    let mut backward: Map<String, u64> = Map::new();
+    backward.resize(1021).unwrap();
+    for idx in (0..100).rev() {
+        backward.insert(format!("k{}", idx), idx).unwrap();
+    }
+    assert_eq!(forward, backward);
+
+    backward.set("k5".to_string(), 6).unwrap();
+    assert_ne!(forward, backward);
+    assert_eq!(format!("{:?}", Map::<String, u64>::new()), "{}");
+}
+
+#[test]
+fn single_removals_clean_up_removed_slots() {
+    // Every removal leaves a removed slot and every insertion uses a new key, so without cleaning them up hardly any empty slots
+    // would be left and every probe would run past the limit of the flooding check
+    let mut map: Map<String, u64> = Map::new();
+    let mut model: HashMap<String, u64> = HashMap::new();
+    for idx in 0..20_000u64 {
+        map.insert(format!("k{}", idx), idx).unwrap();
+        model.insert(format!("k{}", idx), idx);
+        if idx >= 100 {
+            let old = format!("k{}", idx - 100);
+            assert_eq!(map.remove(old.clone()), Ok(idx - 100));
+            model.remove(&old);
+        }
+    }
+    assert_same(&mut map, &model);
+
+    // A few keys sharing a hash code are not enough to be taken for flooding when the probes stay short
+    for idx in 0..10u64 {
+        let key: String = (0..4).map(|bit| if idx >> bit & 1 == 0 { "AB" } else { "B!" }).collect();
+        map.insert(key, idx).unwrap();
+    }
+    assert!(!map.is_keyed());
+}
@@ -0,0 +1,306 @@
+use azeezd_hash::tools::{
+    tokenize, Aggregate, Alteration, ArithOp, ColumnType, CompareOp, DataItem, Expr, InsertValues, Statement, TokenKind,
+};
+
+#[test]
+fn tokens_keep_values_together() {
+    let kinds = tokenize("INSERT \"Al Bo\": -1:30:00, 2001-02-03,x<>y")
+        .unwrap()
+        .into_iter()
+        .map(|token| token.kind)
+        .collect::<Vec<TokenKind>>();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Ident("INSERT".to_string()),
+            TokenKind::QuotedIdent("Al Bo".to_string()),
+            TokenKind::Symbol(":"),
+            TokenKind::Symbol("-"),
+            TokenKind::Number("1:30:00".to_string()),
+            TokenKind::Symbol(","),
+            TokenKind::Number("2001-02-03".to_string()),
+            TokenKind::Symbol(","),
+            TokenKind::Ident("x".to_string()),
+            TokenKind::Symbol("!="),
+            TokenKind::Ident("y".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn statements_parse_into_their_parts() {
+    assert_eq!(
+        Statement::parse("GET Age, \"Of Course\" OF OFFICE").unwrap(),
+        Statement::Get {
+            columns: Some(vec![
+                (Expr::Name("Age".to_string()), None),
+                (Expr::Identifier("Of Course".to_string()), None)
+            ]),
+            rows: Some(vec!["OFFICE".to_string()]),
+            joins: Vec::new(),
+            filter: None,
+            group_by: Vec::new(),
+            having: None,
+            order: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    );
+    assert!(matches!(
+        Statement::parse("GET * OF * WHERE Age > 3").unwrap(),
+        Statement::Get { columns: None, rows: None, filter: Some(_), .. }
+    ));
+    assert_eq!(
+        Statement::parse("get * of * order by Age desc, Name ASC, Code limit 10 offset 5").unwrap(),
+        Statement::Get {
+            columns: None,
+            rows: None,
+            joins: Vec::new(),
+            filter: None,
+            group_by: Vec::new(),
+            having: None,
+            order: vec![
+                (Expr::Name("Age".to_string()), true),
+                (Expr::Name("Name".to_string()), false),
+                (Expr::Name("Code".to_string()), false),
+            ],
+            limit: Some(10),
+            offset: Some(5),
+        }
+    );
+    let count = |arg: Option<&str>, distinct| {
+        Expr::Aggregate(Aggregate::Count, arg.map(|arg| Box::new(Expr::Name(arg.to_string()))), distinct)
+    };
+    assert!(matches!(
+        Statement::parse("GET Program, count(*), COUNT(DISTINCT Age) OF * GROUP BY Program HAVING COUNT(*) > 1").unwrap(),
+        Statement::Get { columns: Some(columns), group_by, having: Some(_), .. }
+            if columns[1..] == [(count(None, false), None), (count(Some("Age"), true), None)] && group_by == ["Program"]
+    ));
+    assert_eq!(
+        Statement::parse("SET Age=Age + 1, Note=NULL, Name='NULL' OF Bob").unwrap(),
+        Statement::Set {
+            changes: vec![
+                (
+                    "Age".to_string(),
+                    Expr::Arithmetic(
+                        Box::new(Expr::Name("Age".to_string())),
+                        ArithOp::Add,
+                        Box::new(Expr::Name("1".to_string())),
+                    ),
+                ),
+                ("Note".to_string(), Expr::Value(DataItem::Null)),
+                ("Name".to_string(), Expr::Literal("NULL".to_string())),
+            ],
+            rows: Some(vec!["Bob".to_string()]),
+            filter: None,
+        }
+    );
+    assert!(matches!(
+        Statement::parse("SET Program=CMETE WHERE Program=CDATE").unwrap(),
+        Statement::Set { rows: None, filter: Some(_), .. }
+    ));
+    assert!(Statement::parse("SET Program=CMETE").is_err());
+    assert_eq!(
+        Statement::parse("INSERT Bilbo (Program=CDATE)").unwrap(),
+        Statement::Insert {
+            row: "Bilbo".to_string(),
+            values: InsertValues::Named(vec![("Program".to_string(), Some("CDATE".to_string()))]),
+        }
+    );
+    assert_eq!(
+        Statement::parse("INSERT Bilbo : 111, NULL").unwrap(),
+        Statement::Insert {
+            row: "Bilbo".to_string(),
+            values: InsertValues::Ordered(vec![Some("111".to_string()), None]),
+        }
+    );
+    assert_eq!(
+        Statement::parse("SAVE AS 'my tables/a.hdb'").unwrap(),
+        Statement::Save { path: Some("my tables/a.hdb".to_string()) }
+    );
+    assert_eq!(
+        Statement::parse("DELETE Bob, \"Al Bo\"").unwrap(),
+        Statement::Delete { rows: Some(vec!["Bob".to_string(), "Al Bo".to_string()]), filter: None }
+    );
+    let on = Expr::Compare(
+        Box::new(Expr::Name("s.Program".to_string())),
+        CompareOp::Equal,
+        Box::new(Expr::Name("p.Name".to_string())),
+    );
+    assert!(matches!(
+        Statement::parse("GET s.Age OF students s JOIN programs AS p ON s.Program = p.Name WHERE Age > 3").unwrap(),
+        Statement::Get { rows: None, joins, filter: Some(_), .. } if joins == [
+            ("students".to_string(), Some("s".to_string()), None),
+            ("programs".to_string(), Some("p".to_string()), Some(on.clone())),
+        ]
+    ));
+    assert!(matches!(
+        Statement::parse("GET * OF Bob, Al WHERE Age > 3").unwrap(),
+        Statement::Get { rows: Some(rows), joins, .. } if rows.len() == 2 && joins.is_empty()
+    ));
+    assert!(Statement::parse("GET * OF students JOIN programs").is_err());
+    assert_eq!(
+        Statement::parse("OPEN tables/students.csv AS s").unwrap(),
+        Statement::Open { path: "tables/students.csv".to_string(), table: Some("s".to_string()) }
+    );
+    assert_eq!(Statement::parse("CLOSE").unwrap(), Statement::Close { table: None });
+    assert_eq!(
+        Statement::parse("CREATE TABLE t (u:Age=18|min=0, w?:Code|regex=[a-z](\\d), \"t:Full, Name\")").unwrap(),
+        Statement::CreateTable {
+            table: "t".to_string(),
+            columns: vec!["u:Age=18|min=0".to_string(), "w?:Code|regex=[a-z](\\d)".to_string(), "t:Full, Name".to_string()],
+        }
+    );
+    assert_eq!(
+        Statement::parse("alter table t rename column Age to Years").unwrap(),
+        Statement::AlterTable {
+            table: "t".to_string(),
+            alteration: Alteration::RenameColumn("Age".to_string(), "Years".to_string()),
+        }
+    );
+    assert_eq!(
+        Statement::parse("ALTER TABLE t ALTER Age TYPE Long").unwrap(),
+        Statement::AlterTable { table: "t".to_string(), alteration: Alteration::ColumnType("Age".to_string(), ColumnType::Long) }
+    );
+    assert!(Statement::parse("ALTER TABLE t ALTER Age TYPE Nope").is_err());
+    assert!(Statement::parse("ALTER TABLE t ADD COLUMN u:A, u:B").is_err());
+    assert!(matches!(
+        Statement::parse("DELETE WHERE Age > 3").unwrap(),
+        Statement::Delete { rows: None, filter: Some(_) }
+    ));
+    assert_eq!(Statement::parse("begin").unwrap(), Statement::Begin);
+    assert_eq!(Statement::parse("COMMIT").unwrap(), Statement::Commit);
+    assert_eq!(Statement::parse("ROLLBACK").unwrap(), Statement::Rollback { savepoint: None });
+    assert_eq!(
+        Statement::parse("ROLLBACK TO SAVEPOINT a").unwrap(),
+        Statement::Rollback { savepoint: Some("a".to_string()) }
+    );
+    assert_eq!(Statement::parse("ROLLBACK TO a").unwrap(), Statement::Rollback { savepoint: Some("a".to_string()) });
+    assert_eq!(Statement::parse("SAVEPOINT a").unwrap(), Statement::Savepoint { name: "a".to_string() });
+    assert_eq!(Statement::parse("RELEASE a").unwrap(), Statement::Release { savepoint: "a".to_string() });
+    assert!(Statement::parse("ROLLBACK a").is_err());
+    assert!(Statement::parse("SAVEPOINT").is_err());
+}
+
+#[test]
+fn keywords_ignore_case_and_quotes_tell_names_from_text() {
+    assert_eq!(
+        Statement::parse("get Age of \"of\" -- a comment\n").unwrap(),
+        Statement::parse("GET /* all of */ Age OF \"of\"").unwrap()
+    );
+    assert!(Statement::parse("delete Where").is_err());
+
+    let kinds = tokenize(r#"'It''s' 'a\'b\n' "Say ""hi""""#)
+        .unwrap()
+        .into_iter()
+        .map(|token| token.kind)
+        .collect::<Vec<TokenKind>>();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::String("It's".to_string()),
+            TokenKind::String("a'b\n".to_string()),
+            TokenKind::QuotedIdent("Say \"hi\"".to_string()),
+        ]
+    );
+
+    assert_eq!(
+        Statement::parse("set Note='Hello, world', Done=TRUE of \"Bilbo Baggins\"").unwrap(),
+        Statement::Set {
+            changes: vec![
+                ("Note".to_string(), Expr::Literal("Hello, world".to_string())),
+                ("Done".to_string(), Expr::Value(DataItem::Boolean(true))),
+            ],
+            rows: Some(vec!["Bilbo Baggins".to_string()]),
+            filter: None,
+        }
+    );
+    assert!(Statement::parse("DELETE 'Bob'").is_err());
+    assert!(tokenize("GET * /* OF *").is_err());
+}
+
+#[test]
+fn arithmetic_binds_tighter_than_comparisons() {
+    let name = |name: &str| Box::new(Expr::Name(name.to_string()));
+    assert_eq!(
+        Expr::parse("Age * 2 + 1 > Max - Min % 3").unwrap(),
+        Expr::Compare(
+            Box::new(Expr::Arithmetic(
+                Box::new(Expr::Arithmetic(name("Age"), ArithOp::Multiply, name("2"))),
+                ArithOp::Add,
+                name("1"),
+            )),
+            CompareOp::Greater,
+            Box::new(Expr::Arithmetic(
+                name("Max"),
+                ArithOp::Subtract,
+                Box::new(Expr::Arithmetic(name("Min"), ArithOp::Remainder, name("3"))),
+            )),
+        )
+    );
+    assert_eq!(Expr::parse("Age -1").unwrap(), Expr::parse("Age - 1").unwrap());
+    assert_eq!(Expr::parse("Age-1").unwrap(), Expr::parse("Age - 1").unwrap());
+    assert_eq!(Expr::parse("Age%Min+1").unwrap(), Expr::parse("Age % Min + 1").unwrap());
+    assert_eq!(
+        Expr::parse("Age*-2 > -1.5").unwrap(),
+        Expr::Compare(
+            Box::new(Expr::Arithmetic(name("Age"), ArithOp::Multiply, name("-2"))),
+            CompareOp::Greater,
+            name("-1.5"),
+        )
+    );
+    assert_eq!(
+        tokenize("GET WHERE Age+1 > 3").unwrap().into_iter().map(|token| token.kind).collect::<Vec<TokenKind>>(),
+        vec![
+            TokenKind::Ident("GET".to_string()),
+            TokenKind::Ident("WHERE".to_string()),
+            TokenKind::Ident("Age".to_string()),
+            TokenKind::Symbol("+"),
+            TokenKind::Number("1".to_string()),
+            TokenKind::Symbol(">"),
+            TokenKind::Number("3".to_string()),
+        ]
+    );
+    assert_eq!(
+        Statement::parse("GET Age*2, Age/2 OF x").unwrap(),
+        Statement::parse("GET Age * 2, Age / 2 OF x").unwrap()
+    );
+    assert!(matches!(
+        Statement::parse("GET * OF * WHERE Program LIKE C%").unwrap(),
+        Statement::Get { filter: Some(Expr::Like(..)), .. }
+    ));
+    assert_eq!(Expr::parse("Born = 2001-02-03").unwrap(), Expr::Compare(name("Born"), CompareOp::Equal, name("2001-02-03")));
+    assert_eq!(
+        Statement::parse("GET Age / 2 AS Half OF * ORDER BY Half").unwrap(),
+        Statement::parse("get Age / 2 as Half of * order by Half").unwrap()
+    );
+    // Names holding an arithmetic symbol are only read as one name in double quotes
+    assert_eq!(
+        Statement::parse("DELETE \"Mary-Jane\"").unwrap(),
+        Statement::Delete { rows: Some(vec!["Mary-Jane".to_string()]), filter: None }
+    );
+    assert!(Statement::parse("DELETE Mary-Jane").is_err());
+    assert!(Statement::parse("GET * OF Mary-Jane").is_err());
+}
+
+#[test]
+fn errors_point_at_the_failure() {
+    let error = Statement::parse("GET Age Program OF *").unwrap_err();
+    assert_eq!(error.pos, 8);
+    assert_eq!(
+        error.describe("GET Age Program OF *"),
+        "Expected OF after the columns of GET at position 8\nGET Age Program OF *\n        ^"
+    );
+
+    assert_eq!(Statement::parse("GET * OF * WHERE Age > 15 AND").unwrap_err().pos, 29);
+    assert_eq!(Statement::parse("DELETE OF").unwrap_err().pos, 7);
+    assert_eq!(Statement::parse("SAVE Bob").unwrap_err().pos, 5);
+    assert_eq!(Statement::parse("INSERT Z : 'b").unwrap_err().pos, 11);
+    assert_eq!(Statement::parse("FOO").unwrap_err().pos, 0);
+    assert_eq!(Statement::parse("GET * OF * LIMIT -1").unwrap_err().pos, 17);
+    assert_eq!(Statement::parse("GET * OF * ORDER Age").unwrap_err().pos, 17);
+    assert_eq!(Statement::parse("GET FOO(Age) OF *").unwrap_err().pos, 4);
+    assert_eq!(Statement::parse("GET SUM(*) OF *").unwrap_err().pos, 8);
+    assert_eq!(Statement::parse("GET CAST(Age AS Nope) OF *").unwrap_err().pos, 16);
+    assert_eq!(Statement::parse("GET Age AS OF OF *").unwrap_err().pos, 11);
+}
@@ -0,0 +1,142 @@
+use azeezd_hash::tools::{Column, ColumnType, DataItem, Pattern, Schema};
+
+#[test]
+fn patterns_match_whole_text() {
+    let pattern = Pattern::new("C[A-Z]+").unwrap();
+    assert!(pattern.is_match("CDATE"));
+    assert!(!pattern.is_match("C"));
+    assert!(!pattern.is_match("xCDATE"));
+
+    let pattern = Pattern::new(r"\d\d?-[^-]*\.txt").unwrap();
+    assert!(pattern.is_match("7-notes.txt"));
+    assert!(pattern.is_match("12-.txt"));
+    assert!(!pattern.is_match("123-a.txt"));
+    assert!(!pattern.is_match("1-a-b.txt"));
+    assert!(Pattern::new("a.*b*c").unwrap().is_match("aXXbbbXc"));
+    assert!(Pattern::new("[a-]+").unwrap().is_match("a-a"));
+
+    assert!(Pattern::new("*a").is_err());
+    assert!(Pattern::new("[ab").is_err());
+    assert!(Pattern::new("a\\").is_err());
+}
+
+#[test]
+fn patterns_match_in_linear_time() {
+    let text = "a".repeat(40);
+    assert!(!Pattern::like("%a%a%a%a%a%a%a%a%b").is_match(&text));
+    assert!(Pattern::like("%a%a%a%a%a%a%a%a%").is_match(&text));
+    assert!(!Pattern::new("a*a*a*a*a*a*a*a*b").unwrap().is_match(&text));
+    assert!(Pattern::new("a*a*a*a*a*a*a*a*").unwrap().is_match(&text));
+
+    let like = Pattern::like("J_%n%n");
+    assert!(like.is_match("Johnson"));
+    assert!(like.is_match("Jansen"));
+    assert!(!like.is_match("Jon"));
+    assert!(!like.is_match("Johnsons"));
+    assert!(Pattern::like("%").is_match(""));
+    assert!(Pattern::like("100\\%").is_match("100%"));
+    assert!(!Pattern::like("100\\%").is_match("1000"));
+
+    assert!(Pattern::new("a?b+c*").unwrap().is_match("bbb"));
+    assert!(Pattern::new("a?b+c*").unwrap().is_match("abcc"));
+    assert!(!Pattern::new("a?b+c*").unwrap().is_match("ac"));
+    assert!(Pattern::new("x+x+x").unwrap().is_match("xxx"));
+    assert!(!Pattern::new("x+x+x").unwrap().is_match("xx"));
+}
+
+#[test]
+fn columns_parse_and_print_constraints() {
+    let column = Column::parse("u?:Age=18|min=0|max=150|unique").unwrap();
+    assert_eq!(column.kind, ColumnType::UInteger);
+    assert!(column.nullable && column.unique);
+    assert_eq!(column.default, Some(DataItem::UInteger(18)));
+    assert_eq!(column.to_string(), "u?:Age=18|unique|min=0|max=150");
+
+    assert!(column.check(&DataItem::UInteger(150)).is_ok());
+    assert!(column.check(&DataItem::UInteger(151)).is_err());
+    assert!(column.check(&DataItem::Null).is_ok());
+    assert!(Column::parse("u:Age").unwrap().check(&DataItem::Null).is_err());
+
+    let column = Column::parse("w:Program|regex=C[A-Z]+").unwrap();
+    assert!(column.check(&column.value(Some("CDATE")).unwrap()).is_ok());
+    assert!(column.check(&column.value(Some("cdate")).unwrap()).is_err());
+    assert!(column.value(Some("C DATE")).is_err());
+
+    assert!(Column::parse("u:Age=200|max=150").is_err());
+    assert!(Column::parse("u:Age|regex=1+").is_err());
+    assert!(Column::parse("q:Age").is_err());
+    assert!(Column::parse("u:Age|often").is_err());
+}
+
+#[test]
+fn schema_finds_columns() {
+    let fields = ["u:Age", "w:Program"].map(String::from);
+    let schema = Schema::parse(&fields).unwrap();
+    assert_eq!(schema.len(), 2);
+    assert_eq!(schema.index_of("Program"), Some(1));
+    assert_eq!(schema.index_of("Nope"), None);
+    assert_eq!(schema.fields(), fields);
+    assert!(Schema::parse(&["u:Age", "w:Age"].map(String::from)).is_err());
+}
+
+#[test]
+fn checks_are_bound_to_the_row() {
+    let fields = ["u:Age|check=Age >= 18 OR Guardian IS NOT NULL", "w?:Guardian|index", "u?:Cap|check=Cap < Age"].map(String::from);
+    let schema = Schema::parse(&fields).unwrap();
+    assert_eq!(schema.fields(), fields);
+    assert_eq!(schema.checks().len(), 2);
+
+    let row = |age: u32, guardian: Option<&str>, limit: Option<u32>| {
+        vec![
+            DataItem::UInteger(age),
+            guardian.map_or(DataItem::Null, |guardian| DataItem::Word(guardian.to_string())),
+            limit.map_or(DataItem::Null, DataItem::UInteger),
+        ]
+    };
+    assert!(schema.check_conditions(&row(20, None, Some(5))).is_ok());
+    assert!(schema.check_conditions(&row(10, Some("Mom"), None)).is_ok());
+    assert_eq!(
+        schema.check_conditions(&row(10, None, None)),
+        Err("Value 10 of column Age breaks its CHECK (Age >= 18 OR Guardian IS NOT NULL)".to_string())
+    );
+    assert!(schema.check_conditions(&row(20, None, Some(30))).is_err());
+
+    assert!(Schema::parse(&["u:Age|check=Nope > 1"].map(String::from)).is_err());
+    assert!(Schema::parse(&["u:Age|check=Age > CDATE"].map(String::from)).is_err());
+    assert!(Schema::parse(&["u:Age|check=COUNT(*) > 1"].map(String::from)).is_err());
+    assert!(Column::parse("u:Age|check=Age >").is_err());
+    assert!(Column::parse("u:Age|check=Age > 1|check=Age < 5").is_err());
+}
+
+#[test]
+fn quoted_values_parse_and_print() {
+    let fields = [
+        "t:Sep=\"|\"|unique",
+        "w?:Code=\"\"\"a|b\"|min=!|regex=\"[a-z|\"\"]+\"",
+        "t:Pipe|check=\"Pipe != 'a||b'\"|index",
+        "t:Plain=say \"hi\"",
+    ];
+    for field in fields {
+        let column = Column::parse(field).unwrap();
+        assert_eq!(column.to_string(), field);
+        assert_eq!(Column::parse(&column.to_string()).unwrap(), column);
+    }
+
+    let column = Column::parse(fields[1]).unwrap();
+    assert_eq!(column.default, Some(DataItem::Word("\"a|b".to_string())));
+    assert!(column.check(&DataItem::Word("a|\"b".to_string())).is_ok());
+    assert_eq!(Column::parse(fields[2]).unwrap().check.unwrap().0, "Pipe != 'a||b'");
+
+    assert!(Column::parse("t:Sep=\"|").is_err());
+    assert!(Column::parse("t:Sep=\"a\"b").is_err());
+}
+
+#[test]
+fn references_parse_and_print() {
+    let column = Column::parse("w?:Program|ref=programs:cascade").unwrap();
+    assert_eq!(column.reference, Some(("programs".to_string(), true)));
+    assert_eq!(column.to_string(), "w?:Program|ref=programs:cascade");
+    assert_eq!(Column::parse("w:Program|ref=programs:restrict").unwrap().to_string(), "w:Program|ref=programs");
+    assert!(Column::parse("w:Program|ref=").is_err());
+    assert!(Column::parse("w:Program|ref=programs:later").is_err());
+}
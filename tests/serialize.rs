@@ -0,0 +1,165 @@
+use azeezd_hash::hash_map::{ByteReader, Deserialize, Map, Serialize};
+use azeezd_hash::tools::DataItem;
+use std::fmt::Debug;
+
+fn round_trip<T: Serialize + Deserialize + PartialEq + Debug>(value: T) {
+    let mut bytes = Vec::new();
+    value.serialize(&mut bytes);
+
+    let mut reader = ByteReader::new(&bytes);
+    assert_eq!(T::deserialize(&mut reader).unwrap(), value);
+    assert_eq!(reader.remaining(), 0);
+}
+
+fn students() -> Map<String, Vec<DataItem>> {
+    let mut map = Map::new();
+    for idx in 0..100u32 {
+        map.insert(
+            format!("Student{}", idx),
+            vec![
+                DataItem::UInteger(idx),
+                DataItem::Integer(-(idx as i32)),
+                DataItem::Float(idx as f32 / 3.0),
+                DataItem::Word(format!("Program{}", idx % 7)),
+                DataItem::Boolean(idx % 2 == 0),
+            ],
+        )
+        .unwrap();
+    }
+    map
+}
+
+#[test]
+fn integers() {
+    for value in [0u64, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        round_trip(value);
+    }
+    for value in [0i64, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+        round_trip(value);
+    }
+    round_trip(u8::MAX);
+    round_trip(i16::MIN);
+    round_trip(usize::MAX);
+}
+
+#[test]
+fn small_integers_are_compact() {
+    let mut bytes = Vec::new();
+    5u64.serialize(&mut bytes);
+    (-3i32).serialize(&mut bytes);
+    assert_eq!(bytes.len(), 2);
+}
+
+#[test]
+fn out_of_range_integer_is_rejected() {
+    let mut bytes = Vec::new();
+    300u32.serialize(&mut bytes);
+    assert!(u8::deserialize(&mut ByteReader::new(&bytes)).is_err());
+}
+
+#[test]
+fn lengths_longer_than_the_data_are_rejected() {
+    // A length close to 2^64 must not be looped over or allocated for
+    let mut bytes = Vec::new();
+    usize::MAX.serialize(&mut bytes);
+    assert!(Vec::<Vec<u8>>::deserialize(&mut ByteReader::new(&bytes)).is_err());
+
+    let mut bytes = Vec::new();
+    1000usize.serialize(&mut bytes);
+    bytes.extend_from_slice(&[1, 2, 3]);
+    assert!(Vec::<u8>::deserialize(&mut ByteReader::new(&bytes)).is_err());
+    assert!(Map::<String, u8>::deserialize(&mut ByteReader::new(&bytes)).is_err());
+}
+
+#[test]
+fn collections_of_one_byte_items() {
+    // Empty collections take a single byte, the least any item takes, so their length is exactly the data left
+    round_trip(vec![Vec::<u8>::new(); 5]);
+    let mut map: Map<String, Vec<Vec<u8>>> = Map::new();
+    map.insert("empty".to_string(), vec![Vec::new(); 3]).unwrap();
+    map.insert("none".to_string(), Vec::new()).unwrap();
+    assert_eq!(Map::from_bytes(&map.to_bytes()).unwrap(), map);
+}
+
+#[test]
+fn floats() {
+    for value in [0.0f32, -0.0, 1.5, f32::MIN, f32::MAX, f32::INFINITY] {
+        round_trip(value);
+    }
+    round_trip(std::f64::consts::PI);
+
+    let mut bytes = Vec::new();
+    f32::NAN.serialize(&mut bytes);
+    assert!(f32::deserialize(&mut ByteReader::new(&bytes)).unwrap().is_nan());
+}
+
+#[test]
+fn strings() {
+    round_trip(String::new());
+    round_trip("CDATE".to_string());
+    round_trip("Ünïcödé ✓ 🦀".to_string());
+    round_trip("x".repeat(1000));
+}
+
+#[test]
+fn data_items() {
+    round_trip(DataItem::UInteger(u32::MAX));
+    round_trip(DataItem::Integer(i32::MIN));
+    round_trip(DataItem::Float(-2.5));
+    round_trip(DataItem::Word("Bob".to_string()));
+    round_trip(DataItem::Boolean(true));
+    round_trip(vec![DataItem::Boolean(false), DataItem::UInteger(3)]);
+}
+
+#[test]
+fn map_round_trip() {
+    let map = students();
+    let read: Map<String, Vec<DataItem>> = Map::from_bytes(&map.to_bytes()).unwrap();
+    assert_eq!(read, map);
+
+    let empty: Map<String, u32> = Map::new();
+    assert_eq!(Map::<String, u32>::from_bytes(&empty.to_bytes()).unwrap(), empty);
+}
+
+#[test]
+fn corrupted_map_is_rejected() {
+    let bytes = students().to_bytes();
+
+    for idx in [6, bytes.len() / 2, bytes.len() - 5] {
+        let mut corrupted = bytes.clone();
+        corrupted[idx] ^= 0x01;
+        assert_eq!(
+            Map::<String, Vec<DataItem>>::from_bytes(&corrupted).unwrap_err(),
+            "Checksum mismatch, data is corrupted"
+        );
+    }
+}
+
+#[test]
+fn truncated_map_is_rejected() {
+    let bytes = students().to_bytes();
+    for len in [0, 3, 10, bytes.len() - 1] {
+        assert!(Map::<String, Vec<DataItem>>::from_bytes(&bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn wrong_magic_and_version_are_rejected() {
+    let mut bytes = students().to_bytes();
+    bytes[0] = b'X';
+    assert_eq!(
+        Map::<String, Vec<DataItem>>::from_bytes(&bytes).unwrap_err(),
+        "Data is not a serialized Map"
+    );
+
+    // Bump the version and fix the checksum so only the version is wrong
+    let mut bytes = students().to_bytes();
+    bytes[4] += 1;
+    let len = bytes.len();
+    let crc = azeezd_hash::hash_map::checksum(&bytes[..len - 4]);
+    bytes[len - 4..].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(
+        Map::<String, Vec<DataItem>>::from_bytes(&bytes).unwrap_err(),
+        "Unsupported Map format version"
+    );
+}
@@ -0,0 +1,281 @@
+use azeezd_hash::tools::{query, DataItem, Expr, Statement, Table};
+
+mod common;
+
+use common::temp_table;
+
+#[test]
+fn named_insert_fills_defaults() {
+    let (mut table, path) = temp_table("named", "u:Age=18,w?:Program,w:Code\nBob,10,CDATE,a1");
+
+    query(&mut table, "INSERT Bilbo (Code=b)").unwrap();
+    query(&mut table, "INSERT Pip (Program=CMETE, Age=3, Code=p)").unwrap();
+    let rows = table.get(&["*".to_string()], &["Bilbo".to_string(), "Pip".to_string()], None, &[]).unwrap();
+    assert_eq!(
+        rows[0].1,
+        Some(vec![DataItem::UInteger(18), DataItem::Null, DataItem::Word("b".to_string())])
+    );
+    assert_eq!(
+        rows[1].1,
+        Some(vec![DataItem::UInteger(3), DataItem::Word("CMETE".to_string()), DataItem::Word("p".to_string())])
+    );
+
+    assert_eq!(
+        query(&mut table, "INSERT Sam (Program=CDATE)"),
+        Err("Missing values for required columns: Code".to_string())
+    );
+    assert!(query(&mut table, "INSERT Sam (Code=s, Nope=1)").is_err());
+    assert!(query(&mut table, "INSERT Sam (Code=s, Code=t)").is_err());
+    assert_eq!(table.get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap().len(), 3);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn constraints_reject_changes() {
+    let (mut table, path) = temp_table("constraints", "u:Age|max=150,w:Code|unique|regex=[a-z]\\d\nBob,10,a1\nAl,20,a2");
+
+    assert!(query(&mut table, "INSERT Cy : 151, a3").is_err());
+    assert!(query(&mut table, "INSERT Cy : 15, a1").is_err());
+    assert!(query(&mut table, "INSERT Cy : 15, aa").is_err());
+    assert!(query(&mut table, "SET Code=a1 OF Al").is_err());
+    query(&mut table, "SET Code=a2, Age=21 OF Al").unwrap();
+    query(&mut table, "INSERT Cy : 15, a3").unwrap();
+
+    let rows = table.get(&["Age".to_string()], &["Al".to_string()], None, &[]).unwrap();
+    assert_eq!(rows[0].1, Some(vec![DataItem::UInteger(21)]));
+    assert_eq!(
+        query(&mut table, "SET Code=a1 OF Cy"),
+        Err("Row Cy: Value a1 of column Code must be unique but row Bob already has it".to_string())
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rows_are_sorted_stably() {
+    let (mut table, path) = temp_table("order", "u?:Age,w:Code\nBob,20,b\nAl,10,a\nCy,20,c\nDi,NULL,d");
+    let names = |table: &mut Table, order: &[(String, bool)]| {
+        table
+            .get(&["*".to_string()], &["*".to_string()], None, order)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(names(&mut table, &[("Age".to_string(), false)]), ["Di", "Al", "Bob", "Cy"]);
+    assert_eq!(names(&mut table, &[("Age".to_string(), true)]), ["Bob", "Cy", "Al", "Di"]);
+    assert_eq!(
+        names(&mut table, &[("Age".to_string(), true), ("Code".to_string(), true)]),
+        ["Cy", "Bob", "Al", "Di"]
+    );
+    assert!(table.get(&["*".to_string()], &["*".to_string()], None, &[("Nope".to_string(), false)]).is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn computed_columns_and_aliases() {
+    let (mut table, path) = temp_table("computed", "u:Age,w:Code\nBob,20,b\nAl,10,a\nCy,30,c");
+    let rows = |table: &mut Table, text: &str| match Statement::parse(text).unwrap() {
+        Statement::Get { columns, order, .. } => table
+            .select(
+                Some(&columns.unwrap().into_iter().map(|(column, _)| column).collect::<Vec<Expr>>()),
+                &["*".to_string()],
+                None,
+                &order,
+            )
+            .unwrap(),
+        _ => unreachable!(),
+    };
+
+    assert_eq!(
+        rows(&mut table, "GET Age * 2 + 1, UPPER(Code) OF * ORDER BY 0 - Age")[0],
+        ("Cy".to_string(), Some(vec![DataItem::Long(61), DataItem::Word("C".to_string())]))
+    );
+    assert!(query(&mut table, "GET Age % 7 AS Left OF * ORDER BY Left DESC LIMIT 1").is_ok());
+    assert!(query(&mut table, "GET Agee OF *").is_err());
+    assert!(query(&mut table, "GET Code + 1 OF *").is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn bulk_updates_are_all_or_nothing() {
+    let (mut table, path) = temp_table(
+        "update",
+        "u:Age,w:Program,w?:Code|unique\nBob,10,CDATE,b\nBobby,20,CDATE,NULL\nAl,30,CMETE,NULL",
+    );
+    let column = |table: &mut Table, name: &str| {
+        table
+            .get(&[name.to_string()], &["*".to_string()], None, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(_, row)| row.unwrap().remove(0))
+            .collect::<Vec<DataItem>>()
+    };
+
+    assert_eq!(table.update(Vec::new(), &["*".to_string()], None), Ok(3));
+    query(&mut table, "SET Program=CMETE, Age = Age + 1 WHERE Program=CDATE").unwrap();
+    assert_eq!(column(&mut table, "Age"), [DataItem::UInteger(11), DataItem::UInteger(21), DataItem::UInteger(30)]);
+    assert!(column(&mut table, "Program").iter().all(|value| *value == DataItem::Word("CMETE".to_string())));
+
+    // One row going below zero, or two rows getting the same unique code, changes no row at all
+    assert!(query(&mut table, "SET Age = Age - 20 OF *").is_err());
+    assert!(query(&mut table, "SET Code = c WHERE Code IS NULL").is_err());
+    assert!(query(&mut table, "SET Program = 'two words' OF Bob").is_err());
+    assert!(query(&mut table, "SET Program = \"Hello\" OF Bob").is_err());
+    assert!(query(&mut table, "SET Age = Program WHERE Age > 0").is_err());
+    assert_eq!(column(&mut table, "Age"), [DataItem::UInteger(11), DataItem::UInteger(21), DataItem::UInteger(30)]);
+    assert_eq!(column(&mut table, "Code"), [DataItem::Word("b".to_string()), DataItem::Null, DataItem::Null]);
+
+    query(&mut table, "SET Age = Age / 2, Code = Program OF Al").unwrap();
+    let rows = table.get(&["*".to_string()], &["Al".to_string()], None, &[]).unwrap();
+    assert_eq!(
+        rows[0].1,
+        Some(vec![DataItem::UInteger(15), DataItem::Word("CMETE".to_string()), DataItem::Word("CMETE".to_string())])
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn deletes_return_removed_rows() {
+    let (mut table, path) = temp_table("delete", "u:Age,w:Program\nBob,10,CDATE\nBobby,20,CDATE\nAl,30,CMETE\nCy,40,CDATE");
+    let names = |table: &mut Table| {
+        table
+            .get(&["*".to_string()], &["*".to_string()], None, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>()
+    };
+
+    query(&mut table, "DELETE Bob, Nobody, Al").unwrap();
+    assert_eq!(names(&mut table), ["Bobby", "Cy"]);
+    assert!(query(&mut table, "DELETE WHERE Nope = 1").is_err());
+    assert_eq!(names(&mut table), ["Bobby", "Cy"]);
+
+    let filter = Expr::parse("Age > 25 AND Program = CDATE").unwrap();
+    let removed = table.delete(&["*".to_string()], Some(&filter)).unwrap();
+    assert_eq!(
+        removed,
+        [("Cy".to_string(), vec![DataItem::UInteger(40), DataItem::Word("CDATE".to_string())])]
+    );
+    assert_eq!(names(&mut table), ["Bobby"]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn indexes_follow_changes() {
+    let content = "u:Age,w?:Program\nBob,10,CDATE\nBobby,20,CDATE\nAl,30,CMETE\nCy,40,NULL";
+    let (mut plain, plain_path) = temp_table("index-plain", content);
+    let (mut indexed, path) = temp_table("index", content);
+    indexed.create_index("Program").unwrap();
+    assert!(indexed.create_index("Program").is_err());
+    assert!(indexed.create_index("Nope").is_err());
+
+    let names = |table: &mut Table, filter: &str| {
+        let mut names = table
+            .get(&["*".to_string()], &["*".to_string()], Some(&Expr::parse(filter).unwrap()), &[])
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+        names.sort();
+        names
+    };
+    let changes = [
+        "INSERT Di : 50, CDATE",
+        "SET Program=CMETE OF Bob",
+        "SET Program=NONE WHERE Program=CMETE AND Age > 20",
+        "DELETE Bobby",
+        "SET Program=CDATE WHERE Program IS NULL",
+    ];
+    for change in changes {
+        query(&mut plain, change).unwrap();
+        query(&mut indexed, change).unwrap();
+        for filter in ["Program = CDATE", "CMETE = Program AND Age < 35", "Program = NONE OR Age = 10", "Program = nothing"] {
+            assert_eq!(names(&mut indexed, filter), names(&mut plain, filter), "{} then {}", change, filter);
+        }
+    }
+    assert_eq!(names(&mut indexed, "Program = CDATE"), ["Cy", "Di"]);
+
+    // The index is kept in the header and built again when the table is opened
+    indexed.save().unwrap();
+    let mut reopened = Table::new(path.to_string_lossy().to_string()).unwrap();
+    assert!(reopened.schema().columns()[1].indexed);
+    assert_eq!(names(&mut reopened, "Program = CDATE"), ["Cy", "Di"]);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(plain_path).unwrap();
+}
+
+#[test]
+fn checks_reject_rows() {
+    let (mut table, path) = temp_table("check", "u:Age|check=Age >= 18 OR Program = KIDS,w:Program\nBob,20,CDATE\nAl,10,KIDS");
+
+    assert!(query(&mut table, "INSERT Cy : 12, CDATE").is_err());
+    query(&mut table, "INSERT Cy : 12, KIDS").unwrap();
+    assert_eq!(
+        query(&mut table, "SET Program = CDATE WHERE Age < 18"),
+        Err("Row Al: Value 10 of column Age breaks its CHECK (Age >= 18 OR Program = KIDS)".to_string())
+    );
+    query(&mut table, "SET Age = Age + 10 WHERE Program = KIDS").unwrap();
+    query(&mut table, "SET Program = CDATE WHERE Age >= 18").unwrap();
+
+    // The condition is kept in the header, and the columns it uses can not be taken away from it
+    table.save().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap().lines().next().unwrap(),
+        "u:Age|check=Age >= 18 OR Program = KIDS,w:Program"
+    );
+    assert!(table.drop_column("Program").is_err());
+    assert!(table.rename_column("Program", "Course").is_err());
+    assert!(table.alter_column_type("Program", azeezd_hash::tools::ColumnType::Text).is_ok());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn transactions_undo_changes() {
+    let (mut table, path) = temp_table("transaction", "u:Age,w:Code|unique|index\nBob,10,a1\nAl,20,b2");
+    let all = |table: &mut Table| table.get(&["*".to_string()], &["*".to_string()], None, &[]).unwrap();
+    let before = all(&mut table);
+
+    assert!(query(&mut table, "COMMIT").is_err());
+    query(&mut table, "BEGIN").unwrap();
+    assert!(query(&mut table, "BEGIN").is_err());
+    query(&mut table, "SET Age = Age + 1 OF *").unwrap();
+    query(&mut table, "INSERT Cy : 30, c3").unwrap();
+    query(&mut table, "SAVEPOINT one").unwrap();
+    query(&mut table, "DELETE Bob").unwrap();
+    query(&mut table, "INSERT Bob : 40, a1").unwrap();
+    // A failed insert is not logged, so undoing does not remove the row it clashed with
+    assert!(query(&mut table, "INSERT Al : 50, z9").is_err());
+    assert!(query(&mut table, "SAVE").is_err());
+    assert!(table.drop_column("Age").is_err());
+
+    query(&mut table, "ROLLBACK TO one").unwrap();
+    let rows = all(&mut table);
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].1, Some(vec![DataItem::UInteger(11), DataItem::Word("a1".to_string())]));
+    query(&mut table, "RELEASE one").unwrap();
+    assert!(query(&mut table, "ROLLBACK TO one").is_err());
+
+    // The index is undone with the rows, so the value of the removed row is free again
+    query(&mut table, "ROLLBACK").unwrap();
+    assert_eq!(all(&mut table), before);
+    assert!(!table.in_transaction());
+    query(&mut table, "INSERT Di : 40, c3").unwrap();
+
+    query(&mut table, "BEGIN").unwrap();
+    query(&mut table, "SET Age = 1 OF Di").unwrap();
+    query(&mut table, "COMMIT").unwrap();
+    assert!(query(&mut table, "ROLLBACK").is_err());
+    assert_eq!(all(&mut table)[2].1, Some(vec![DataItem::UInteger(1), DataItem::Word("c3".to_string())]));
+
+    std::fs::remove_file(path).unwrap();
+}